@@ -1,15 +1,26 @@
 use std::collections::HashSet;
-use std::path::PathBuf;
+use std::{env, fs};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::str::FromStr;
 use dunce::canonicalize;
+use glob::glob;
+use xsc_core::config::ProjectConfig;
+use xsc_core::r#static::info::WarningKind;
 use xsc_core::utils::warnings_from_str;
 
+use structopt::clap::ErrorKind;
 use structopt::StructOpt;
 
 #[derive(Debug, StructOpt)]
 #[structopt(name = "xs-check", about = env!("CARGO_PKG_DESCRIPTION"))]
 struct Opt {
-    #[structopt(parse(from_os_str))]
-    filepath: Option<PathBuf>,
+    #[structopt(
+        help = "Files, directories or glob patterns to check. Directories are searched recursively for .xs files. \
+        If none are given, the entry points of the xsc.toml found in the current directory or its parents are checked",
+        parse(from_os_str)
+    )]
+    filepaths: Vec<PathBuf>,
     
     #[structopt(short, long, help = "Show binary version & info")]
     version: bool,
@@ -22,6 +33,13 @@ struct Opt {
     )]
     ignores: Option<HashSet<u32>>,
 
+    #[structopt(
+        long,
+        require_equals = true,
+        help = "Treat warnings as errors. Optionally takes a comma separated list of names of the only warnings to treat as errors"
+    )]
+    warnings_as_errors: Option<Option<String>>,
+
     #[structopt(
         short,
         long,
@@ -30,6 +48,14 @@ struct Opt {
     )]
     extra_prelude_path: Option<PathBuf>,
 
+    #[structopt(
+        long,
+        default_value = "human",
+        possible_values = &["human", "json", "sarif"],
+        help = "Output format of the diagnostics. json prints one record per line, sarif prints a SARIF 2.1.0 log"
+    )]
+    format: Format,
+
     #[structopt(
         short = "I",
         long,
@@ -37,6 +63,319 @@ struct Opt {
         parse(from_os_str)
     )]
     include_dirs: Vec<PathBuf>,
+
+    #[structopt(subcommand)]
+    subcommand: Option<Subcommand>,
+}
+
+#[derive(Debug, StructOpt)]
+enum Subcommand {
+    #[structopt(about = "Format XS files in place")]
+    Fmt {
+        #[structopt(long, help = "Don't write the files, only list the ones that are not formatted")]
+        check: bool,
+
+        #[structopt(
+            help = "Files, directories or glob patterns to format. If none are given, the entry points of the \
+            xsc.toml found in the current directory or its parents are formatted",
+            parse(from_os_str)
+        )]
+        filepaths: Vec<PathBuf>,
+    },
+    #[structopt(about = "Run a function or rule of an XS file. The file is type checked first")]
+    Run {
+        #[structopt(long, default_value = "main", help = "Name of the function or rule to run")]
+        entry: String,
+
+        #[structopt(
+            long,
+            default_value = "1000000",
+            help = "Stop a call after this many loop iterations and gotos and report it as failed, 0 for no limit"
+        )]
+        max_iterations: u64,
+
+        #[structopt(
+            short,
+            long,
+            help = "Specify an additional prelude file",
+            parse(from_os_str)
+        )]
+        extra_prelude_path: Option<PathBuf>,
+
+        #[structopt(
+            short = "I",
+            long,
+            help = "Additional directories to search for includes",
+            parse(from_os_str)
+        )]
+        include_dirs: Vec<PathBuf>,
+
+        #[structopt(help = "The file to run", parse(from_os_str))]
+        filepath: PathBuf,
+    },
+    #[structopt(
+        about = "Run the tests in XS files. Tests are void functions without parameters that are named test_* or \
+        are tagged with @test"
+    )]
+    Test {
+        #[structopt(long, help = "Also write the results as JUnit XML to this file", parse(from_os_str))]
+        junit: Option<PathBuf>,
+
+        #[structopt(
+            long,
+            default_value = "1000000",
+            help = "Stop a call after this many loop iterations and gotos and report it as failed, 0 for no limit"
+        )]
+        max_iterations: u64,
+
+        #[structopt(
+            short,
+            long,
+            help = "Specify an additional prelude file",
+            parse(from_os_str)
+        )]
+        extra_prelude_path: Option<PathBuf>,
+
+        #[structopt(
+            short = "I",
+            long,
+            help = "Additional directories to search for includes",
+            parse(from_os_str)
+        )]
+        include_dirs: Vec<PathBuf>,
+
+        #[structopt(
+            help = "Files, directories or glob patterns to test. If none are given, the entry points of the \
+            xsc.toml found in the current directory or its parents are tested",
+            parse(from_os_str)
+        )]
+        filepaths: Vec<PathBuf>,
+    },
+    #[structopt(
+        about = "Run the rules of an XS file over a simulated game clock and print a timeline of which rule fired \
+        when. main runs before the first tick"
+    )]
+    Simulate {
+        #[structopt(long, default_value = "60", help = "Game time in seconds to simulate")]
+        duration: u32,
+
+        #[structopt(long, default_value = "500", help = "Length of a game tick in milliseconds")]
+        tick: u32,
+
+        #[structopt(
+            long,
+            default_value = "1000000",
+            help = "Stop a call after this many loop iterations and gotos and report it as failed, 0 for no limit"
+        )]
+        max_iterations: u64,
+
+        #[structopt(
+            short,
+            long,
+            help = "Specify an additional prelude file",
+            parse(from_os_str)
+        )]
+        extra_prelude_path: Option<PathBuf>,
+
+        #[structopt(
+            short = "I",
+            long,
+            help = "Additional directories to search for includes",
+            parse(from_os_str)
+        )]
+        include_dirs: Vec<PathBuf>,
+
+        #[structopt(help = "The file to simulate", parse(from_os_str))]
+        filepath: PathBuf,
+    },
+    #[structopt(about = "Print the Pseudo Assembly (PA) that an XS file and the files it includes lower to")]
+    EmitPa {
+        #[structopt(short, long, help = "Write the PA to this file instead", parse(from_os_str))]
+        output: Option<PathBuf>,
+
+        #[structopt(
+            short,
+            long,
+            help = "Specify an additional prelude file",
+            parse(from_os_str)
+        )]
+        extra_prelude_path: Option<PathBuf>,
+
+        #[structopt(
+            short = "I",
+            long,
+            help = "Additional directories to search for includes",
+            parse(from_os_str)
+        )]
+        include_dirs: Vec<PathBuf>,
+
+        #[structopt(help = "The file to compile", parse(from_os_str))]
+        filepath: PathBuf,
+    },
+    #[structopt(about = "Infer the types of names that an XS file or its includes use without defining, and print @extern declarations for them")]
+    Externs {
+        #[structopt(short, long, help = "Add the declarations to the top of the files that use the names")]
+        write: bool,
+
+        #[structopt(
+            short,
+            long,
+            help = "Specify an additional prelude file",
+            parse(from_os_str)
+        )]
+        extra_prelude_path: Option<PathBuf>,
+
+        #[structopt(
+            short = "I",
+            long,
+            help = "Additional directories to search for includes",
+            parse(from_os_str)
+        )]
+        include_dirs: Vec<PathBuf>,
+
+        #[structopt(help = "The file to analyse", parse(from_os_str))]
+        filepath: PathBuf,
+    },
+    #[structopt(about = "Print the control flow graphs of the functions and rules in an XS file as DOT")]
+    Cfg {
+        #[structopt(long = "fn", help = "Only print the graph of this function or rule")]
+        name: Option<String>,
+
+        #[structopt(short, long, help = "Write the DOT to this file instead", parse(from_os_str))]
+        output: Option<PathBuf>,
+
+        #[structopt(
+            short,
+            long,
+            help = "Specify an additional prelude file",
+            parse(from_os_str)
+        )]
+        extra_prelude_path: Option<PathBuf>,
+
+        #[structopt(
+            short = "I",
+            long,
+            help = "Additional directories to search for includes",
+            parse(from_os_str)
+        )]
+        include_dirs: Vec<PathBuf>,
+
+        #[structopt(help = "The file to analyse", parse(from_os_str))]
+        filepath: PathBuf,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Human,
+    Json,
+    Sarif,
+}
+
+impl FromStr for Format {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "human" => Ok(Format::Human),
+            "json" => Ok(Format::Json),
+            "sarif" => Ok(Format::Sarif),
+            _ => Err(format!("Unknown output format '{s}'")),
+        }
+    }
+}
+
+/// The documented exit codes of xs-check
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Status {
+    Clean = 0,
+    Errors = 1,
+    Warnings = 2,
+    Failure = 3,
+}
+
+impl From<Status> for ExitCode {
+    fn from(status: Status) -> Self {
+        ExitCode::from(status as u8)
+    }
+}
+
+pub enum Command {
+    Check(Args),
+    Fmt(FmtArgs),
+    Run(RunArgs),
+    Test(TestArgs),
+    Simulate(SimulateArgs),
+    EmitPa(EmitPaArgs),
+    Cfg(CfgArgs),
+    Externs(ExternsArgs),
+}
+
+pub struct Args {
+    pub filepaths: Vec<PathBuf>,
+    pub ignores: HashSet<u32>,
+    /// Codes of warnings that are reported as errors
+    pub promoted: HashSet<u32>,
+    pub extra_prelude_path: Option<PathBuf>,
+    pub include_dirs: Vec<PathBuf>,
+    pub format: Format,
+}
+
+pub struct FmtArgs {
+    pub filepaths: Vec<PathBuf>,
+    /// Only report unformatted files instead of writing them
+    pub check: bool,
+}
+
+pub struct RunArgs {
+    /// The file to run is checked with these arguments first
+    pub check: Args,
+    pub entry: String,
+    /// Loop iterations a call may take before it is stopped, 0 for no limit
+    pub max_iterations: u64,
+}
+
+pub struct TestArgs {
+    /// The files to test are checked with these arguments first
+    pub check: Args,
+    /// Where to write a JUnit XML report of the results
+    pub junit: Option<PathBuf>,
+    /// Loop iterations a test may take before it fails, 0 for no limit
+    pub max_iterations: u64,
+}
+
+pub struct SimulateArgs {
+    /// The file to simulate is checked with these arguments first
+    pub check: Args,
+    /// Game time to simulate in seconds
+    pub duration: u32,
+    /// Length of a tick in milliseconds
+    pub tick: u32,
+    /// Loop iterations a rule may take before the simulation is stopped, 0 for no limit
+    pub max_iterations: u64,
+}
+
+pub struct EmitPaArgs {
+    /// The file to compile is checked with these arguments first
+    pub check: Args,
+    /// Where to write the PA instead of stdout
+    pub output: Option<PathBuf>,
+}
+
+pub struct CfgArgs {
+    /// The file to analyse is checked with these arguments first
+    pub check: Args,
+    /// Only the graph of the function or rule with this name is printed
+    pub name: Option<String>,
+    /// Where to write the DOT instead of stdout
+    pub output: Option<PathBuf>,
+}
+
+pub struct ExternsArgs {
+    /// The file to analyse is checked with these arguments first
+    pub check: Args,
+    /// Add the declarations to the files instead of only printing them
+    pub write: bool,
 }
 
 include!(concat!(env!("OUT_DIR"), "/build_date.rs"));
@@ -52,34 +391,268 @@ fn print_info() {
     println!("Compiled: {BUILD_DATE}");
 }
 
-pub fn parse_args() -> Option<(PathBuf, HashSet<u32>, Option<PathBuf>, Vec<PathBuf>)> {
-    let opt = Opt::from_args();
-    if opt.version {
-        print_info();
-        return None;
+fn has_glob_chars(path: &Path) -> bool {
+    path.to_string_lossy().contains(['*', '?', '['])
+}
+
+fn collect_xs_files(dir: &Path, files: &mut Vec<PathBuf>) -> std::io::Result<()> {
+    let mut entries = fs::read_dir(dir)?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<Result<Vec<_>, _>>()?;
+    entries.sort();
+
+    for path in entries {
+        if path.is_dir() {
+            collect_xs_files(&path, files)?;
+        } else if path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("xs")) {
+            files.push(path);
+        }
     }
-    
-    match opt.filepath {
-        None => {
-            Opt::clap().print_help().unwrap();
-            println!();
-            None
-        }
-        Some(rel_path) => {
-            let filepath = match canonicalize(&rel_path) {
-                Ok(filepath) => { filepath }
+    Ok(())
+}
+
+/// Expands the given files, directories and glob patterns into a list of canonical file paths. Files
+/// named more than once are only checked once
+fn resolve_paths(rel_paths: Vec<PathBuf>) -> Option<Vec<PathBuf>> {
+    let mut paths = vec![];
+    for rel_path in rel_paths {
+        if has_glob_chars(&rel_path) {
+            let matches = match glob(&rel_path.to_string_lossy()) {
+                Ok(matches) => { matches }
                 Err(err) => {
-                    println!("Failed to open file '{}': {err}", rel_path.display());
+                    println!("Invalid glob pattern '{}': {err}", rel_path.display());
                     return None;
                 }
             };
-            
-            Some((
-                filepath,
-                opt.ignores.unwrap_or_else(HashSet::new),
-                opt.extra_prelude_path,
-                opt.include_dirs
-            ))
+            let len = paths.len();
+            for path in matches.flatten() {
+                if path.is_dir() {
+                    if let Err(err) = collect_xs_files(&path, &mut paths) {
+                        println!("Failed to read directory '{}': {err}", path.display());
+                        return None;
+                    }
+                } else {
+                    paths.push(path);
+                }
+            }
+            if paths.len() == len {
+                println!("No files matched the pattern '{}'", rel_path.display());
+                return None;
+            }
+        } else if rel_path.is_dir() {
+            if let Err(err) = collect_xs_files(&rel_path, &mut paths) {
+                println!("Failed to read directory '{}': {err}", rel_path.display());
+                return None;
+            }
+        } else {
+            paths.push(rel_path);
+        }
+    }
+
+    let mut seen = HashSet::new();
+    let mut filepaths = vec![];
+    for rel_path in paths {
+        let filepath = match canonicalize(&rel_path) {
+            Ok(filepath) => { filepath }
+            Err(err) => {
+                println!("Failed to open file '{}': {err}", rel_path.display());
+                return None;
+            }
+        };
+        if seen.insert(filepath.clone()) {
+            filepaths.push(filepath);
+        }
+    }
+    Some(filepaths)
+}
+
+/// The entry points listed in the project config of the current directory, if there are any
+fn entry_points() -> Result<Option<Vec<PathBuf>>, String> {
+    let Some(config_path) = env::current_dir().ok().and_then(|cwd| ProjectConfig::find(&cwd)) else {
+        return Ok(None);
+    };
+    let config = ProjectConfig::load(&config_path)?;
+    if config.entry_points.is_empty() {
+        return Ok(None);
+    }
+    Ok(Some(config.entry_points))
+}
+
+/// Resolves the paths given on the command line, falling back to the entry points of the project config
+fn filepaths(rel_paths: Vec<PathBuf>) -> Result<Vec<PathBuf>, Status> {
+    let mut rel_paths = rel_paths;
+    if rel_paths.is_empty() {
+        match entry_points() {
+            Ok(Some(paths)) => { rel_paths = paths }
+            Ok(None) => {
+                Opt::clap().print_help().unwrap();
+                println!();
+                return Err(Status::Failure);
+            }
+            Err(msg) => {
+                println!("{msg}");
+                return Err(Status::Failure);
+            }
+        }
+    }
+
+    let Some(filepaths) = resolve_paths(rel_paths) else {
+        return Err(Status::Failure);
+    };
+    if filepaths.is_empty() {
+        println!("No .xs files found");
+        return Err(Status::Failure);
+    }
+    Ok(filepaths)
+}
+
+/// Arguments to check the one file given to a subcommand that runs it
+fn single_file_args(
+    filepath: PathBuf,
+    extra_prelude_path: Option<PathBuf>,
+    include_dirs: Vec<PathBuf>,
+    verb: &str,
+) -> Result<Args, Status> {
+    let Some(filepaths) = resolve_paths(vec![filepath]) else {
+        return Err(Status::Failure);
+    };
+    if filepaths.len() != 1 {
+        println!("Exactly one file must be given to {verb}");
+        return Err(Status::Failure);
+    }
+    Ok(Args {
+        filepaths,
+        ignores: HashSet::new(),
+        promoted: HashSet::new(),
+        extra_prelude_path,
+        include_dirs,
+        format: Format::Human,
+    })
+}
+
+/// Returns the status to exit with if the program should not continue
+pub fn parse_args() -> Result<Command, Status> {
+    let opt = match Opt::from_args_safe() {
+        Ok(opt) => { opt }
+        Err(err) if matches!(err.kind, ErrorKind::HelpDisplayed | ErrorKind::VersionDisplayed) => {
+            println!("{}", err.message);
+            return Err(Status::Clean);
+        }
+        Err(err) => {
+            eprintln!("{}", err.message);
+            return Err(Status::Failure);
+        }
+    };
+    if opt.version {
+        print_info();
+        return Err(Status::Clean);
+    }
+
+    match opt.subcommand {
+        Some(Subcommand::Fmt { check, filepaths: rel_paths }) => {
+            return Ok(Command::Fmt(FmtArgs {
+                filepaths: filepaths(rel_paths)?,
+                check,
+            }));
+        }
+        Some(Subcommand::Run { entry, max_iterations, extra_prelude_path, include_dirs, filepath }) => {
+            return Ok(Command::Run(RunArgs {
+                check: single_file_args(
+                    filepath,
+                    extra_prelude_path.or(opt.extra_prelude_path),
+                    opt.include_dirs.into_iter().chain(include_dirs).collect(),
+                    "run",
+                )?,
+                entry,
+                max_iterations,
+            }));
+        }
+        Some(Subcommand::Simulate { duration, tick, max_iterations, extra_prelude_path, include_dirs, filepath }) => {
+            return Ok(Command::Simulate(SimulateArgs {
+                check: single_file_args(
+                    filepath,
+                    extra_prelude_path.or(opt.extra_prelude_path),
+                    opt.include_dirs.into_iter().chain(include_dirs).collect(),
+                    "simulate",
+                )?,
+                duration,
+                tick,
+                max_iterations,
+            }));
+        }
+        Some(Subcommand::EmitPa { output, extra_prelude_path, include_dirs, filepath }) => {
+            return Ok(Command::EmitPa(EmitPaArgs {
+                check: single_file_args(
+                    filepath,
+                    extra_prelude_path.or(opt.extra_prelude_path),
+                    opt.include_dirs.into_iter().chain(include_dirs).collect(),
+                    "compile",
+                )?,
+                output,
+            }));
+        }
+        Some(Subcommand::Cfg { name, output, extra_prelude_path, include_dirs, filepath }) => {
+            return Ok(Command::Cfg(CfgArgs {
+                check: single_file_args(
+                    filepath,
+                    extra_prelude_path.or(opt.extra_prelude_path),
+                    opt.include_dirs.into_iter().chain(include_dirs).collect(),
+                    "analyse",
+                )?,
+                name,
+                output,
+            }));
         }
+        Some(Subcommand::Externs { write, extra_prelude_path, include_dirs, filepath }) => {
+            return Ok(Command::Externs(ExternsArgs {
+                check: single_file_args(
+                    filepath,
+                    extra_prelude_path.or(opt.extra_prelude_path),
+                    opt.include_dirs.into_iter().chain(include_dirs).collect(),
+                    "analyse",
+                )?,
+                write,
+            }));
+        }
+        Some(Subcommand::Test { junit, max_iterations, extra_prelude_path, include_dirs, filepaths: rel_paths }) => {
+            return Ok(Command::Test(TestArgs {
+                check: Args {
+                    filepaths: filepaths(rel_paths)?,
+                    ignores: HashSet::new(),
+                    promoted: HashSet::new(),
+                    extra_prelude_path: extra_prelude_path.or(opt.extra_prelude_path),
+                    include_dirs: opt.include_dirs.into_iter().chain(include_dirs).collect(),
+                    format: Format::Human,
+                },
+                junit,
+                max_iterations,
+            }));
+        }
+        None => {}
     }
-}
\ No newline at end of file
+
+    let promoted = match opt.warnings_as_errors {
+        None => { HashSet::new() }
+        Some(None) => {
+            WarningKind::ALL.iter()
+                .map(WarningKind::as_u32)
+                .collect()
+        }
+        Some(Some(names)) => match warnings_from_str(&names) {
+            Ok(kinds) => { kinds }
+            Err(name) => {
+                eprintln!("error: Invalid value for '--warnings-as-errors': {name}");
+                return Err(Status::Failure);
+            }
+        }
+    };
+
+    Ok(Command::Check(Args {
+        filepaths: filepaths(opt.filepaths)?,
+        ignores: opt.ignores.unwrap_or_else(HashSet::new),
+        promoted,
+        extra_prelude_path: opt.extra_prelude_path,
+        include_dirs: opt.include_dirs,
+        format: opt.format,
+    }))
+}
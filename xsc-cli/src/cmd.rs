@@ -0,0 +1,15 @@
+mod cfg;
+mod emit_pa;
+mod externs;
+mod format;
+mod run;
+mod simulate;
+mod test;
+
+pub use cfg::cfg_file;
+pub use emit_pa::emit_pa;
+pub use externs::externs_file;
+pub use format::format_files;
+pub use run::{run_file, PRELUDE_PATH};
+pub use simulate::simulate_file;
+pub use test::test_files;
@@ -0,0 +1,43 @@
+use std::fs;
+
+use xsc_core::r#static::cfg::{proc_cfgs, to_dot};
+
+use crate::cli::{CfgArgs, Status};
+use crate::cmd::run::type_check;
+
+/// Type checks the file and prints the control flow graphs of its functions and rules in the DOT format
+pub fn cfg_file(args: &CfgArgs) -> Status {
+    let filepath = &args.check.filepaths[0];
+    let checked = match type_check(filepath, &args.check, &[], "analysing") {
+        Ok(checked) => { checked }
+        Err(status) => { return status; }
+    };
+    let src = match fs::read_to_string(filepath) {
+        Ok(src) => { src }
+        Err(err) => {
+            println!("Failed to read '{}': {err}", filepath.display());
+            return Status::Failure;
+        }
+    };
+
+    let ast = checked.ast_cache.get(filepath).map(|info| info.1.0.clone()).unwrap_or_default();
+    let mut cfgs = proc_cfgs(&ast);
+    if let Some(name) = &args.name {
+        cfgs.retain(|((proc_name, _span), _cfg)| proc_name.0 == *name);
+        if cfgs.is_empty() {
+            println!("No function or rule named '{name}' is defined in '{}'", filepath.display());
+            return Status::Failure;
+        }
+    }
+
+    let dot = to_dot(&cfgs, &src);
+    let Some(output) = &args.output else {
+        print!("{dot}");
+        return Status::Clean;
+    };
+    if let Err(err) = fs::write(output, dot) {
+        println!("Failed to write '{}': {err}", output.display());
+        return Status::Failure;
+    }
+    Status::Clean
+}
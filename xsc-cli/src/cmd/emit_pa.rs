@@ -0,0 +1,32 @@
+use std::fs;
+
+use xsc_core::codegen::pa;
+
+use crate::cli::{EmitPaArgs, Status};
+use crate::cmd::run::type_check;
+
+/// Type checks the file and prints the Pseudo Assembly it lowers to
+pub fn emit_pa(args: &EmitPaArgs) -> Status {
+    let filepath = &args.check.filepaths[0];
+    let checked = match type_check(filepath, &args.check, &[], "compiling") {
+        Ok(checked) => { checked }
+        Err(status) => { return status; }
+    };
+
+    let program = pa::lower(
+        filepath,
+        &checked.preludes,
+        &checked.ast_cache,
+        &checked.type_env,
+        &checked.settings.include_dirs,
+    );
+    let Some(output) = &args.output else {
+        print!("{program}");
+        return Status::Clean;
+    };
+    if let Err(err) = fs::write(output, program.to_string()) {
+        println!("Failed to write '{}': {err}", output.display());
+        return Status::Failure;
+    }
+    Status::Clean
+}
@@ -0,0 +1,74 @@
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::PathBuf;
+
+use xsc_core::r#static::info::XsError;
+use xsc_core::r#static::type_inf::infer_externs;
+
+use crate::cli::{ExternsArgs, Status};
+use crate::cmd::run::load;
+use crate::fmt::print_xs_errs;
+
+/// Infers the types of the names that the file and its includes use without defining, reports the uses that
+/// disagree on a type and prints (or adds) an `@extern` declaration for each name
+pub fn externs_file(args: &ExternsArgs) -> Status {
+    let filepath = &args.check.filepaths[0];
+    let checked = match load(filepath, &args.check, &[]) {
+        Ok((_checked, Status::Failure)) => { return Status::Failure; }
+        Ok((checked, _status)) => { checked }
+        Err(status) => { return status; }
+    };
+
+    // only the files with errors can use undefined names
+    let mut asts = checked.type_env.errs().keys()
+        .filter_map(|path| checked.ast_cache.get(path).map(|info| (path.clone(), info.1.0.clone())))
+        .collect::<Vec<_>>();
+    asts.sort_by(|(path1, _ast1), (path2, _ast2)| path1.cmp(path2));
+    let inferences = infer_externs(&checked.type_env, asts.iter().map(|(path, ast)| (path, ast.as_slice())));
+    if inferences.is_empty() {
+        println!("No undefined names with a known type are used in '{}'", filepath.display());
+        return Status::Clean;
+    }
+
+    let mut conflicts: HashMap<PathBuf, Vec<XsError>> = HashMap::new();
+    for (path, err) in inferences.iter().flat_map(|inference| inference.conflicts()) {
+        conflicts.entry(path).or_default().push(err);
+    }
+    let mut status = Status::Clean;
+    for (path, errs) in &conflicts {
+        print_xs_errs(path, errs, &HashSet::new(), &HashSet::new());
+        status = Status::Warnings;
+    }
+
+    if !args.write {
+        for inference in &inferences {
+            println!("{}", inference.decl());
+        }
+        return status;
+    }
+
+    // a declaration has to come before the first use of its name
+    let mut decls: Vec<(&PathBuf, Vec<String>)> = vec![];
+    for inference in &inferences {
+        let path = &inference.uses[0].src_loc.file_path;
+        match decls.iter_mut().find(|(decl_path, _decls)| *decl_path == path) {
+            Some((_path, decls)) => decls.push(inference.decl()),
+            None => decls.push((path, vec![inference.decl()])),
+        }
+    }
+    for (path, decls) in decls {
+        let src = match fs::read_to_string(path) {
+            Ok(src) => { src }
+            Err(err) => {
+                println!("Failed to read '{}': {err}", path.display());
+                return Status::Failure;
+            }
+        };
+        if let Err(err) = fs::write(path, format!("{}\n{src}", decls.join("\n"))) {
+            println!("Failed to write '{}': {err}", path.display());
+            return Status::Failure;
+        }
+        println!("Added {} declaration(s) to '{}'", decls.len(), path.display());
+    }
+    status
+}
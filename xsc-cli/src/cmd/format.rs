@@ -0,0 +1,46 @@
+use std::fs;
+
+use xsc_core::formatter::{format_src, FormatOptions};
+
+use crate::cli::{FmtArgs, Status};
+use crate::fmt::print_parse_errs;
+
+/// Formats the given files in place, or only lists the ones that are not formatted when checking
+pub fn format_files(args: &FmtArgs) -> Status {
+    let options = FormatOptions::default();
+
+    let mut status = Status::Clean;
+    for filepath in &args.filepaths {
+        let src = match fs::read_to_string(filepath) {
+            Ok(src) => { src }
+            Err(err) => {
+                println!("Failed to read path '{}', details: {err}", filepath.display());
+                status = Status::Failure;
+                continue;
+            }
+        };
+
+        let formatted = match format_src(&src, &options) {
+            Ok(formatted) => { formatted }
+            Err(errs) => {
+                print_parse_errs(filepath, &errs);
+                status = status.max(Status::Errors);
+                continue;
+            }
+        };
+        if formatted == src {
+            continue;
+        }
+
+        if args.check {
+            println!("File '{}' is not formatted", filepath.display());
+            status = status.max(Status::Errors);
+        } else if let Err(err) = fs::write(filepath, formatted) {
+            println!("Failed to write file '{}', details: {err}", filepath.display());
+            status = Status::Failure;
+        } else {
+            println!("Formatted file '{}'", filepath.display());
+        }
+    }
+    status
+}
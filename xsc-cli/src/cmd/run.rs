@@ -0,0 +1,158 @@
+use std::collections::HashSet;
+use std::path::PathBuf;
+use std::thread;
+
+use xsc_core::config::ProjectConfig;
+use xsc_core::interp::{Interpreter, NativeHost, Natives, RuntimeError, Value};
+use xsc_core::r#static::info::{gen_errs_from_path, gen_errs_from_src, AstCache, AstMap, Error, TypeEnv};
+
+use crate::cli::{Args, RunArgs, Status};
+use crate::fmt::{print_parse_errs, print_runtime_err, print_xs_errs};
+use crate::settings::Settings;
+
+const INTERP_STACK_SIZE: usize = 256 * 1024 * 1024;
+
+pub const PRELUDE_PATH: &str = r"prelude.xs";
+
+/// A file that type checked without errors, along with everything needed to run it
+pub struct Checked {
+    pub settings: Settings,
+    pub ast_cache: AstCache,
+    pub type_env: TypeEnv,
+    /// every prelude the file was checked with, in the order they were loaded
+    pub preludes: Vec<PathBuf>,
+}
+
+impl Checked {
+    /// Creates an interpreter with the preludes and the file loaded
+    pub fn interpreter<N: Natives>(&self, filepath: &PathBuf, natives: N) -> Result<Interpreter<N>, RuntimeError> {
+        let mut interp = Interpreter::new(natives);
+        for prelude in &self.preludes {
+            interp.load_prelude(prelude, &self.ast_cache)?;
+        }
+        interp.load(filepath, &self.ast_cache, &self.settings.include_dirs)?;
+        Ok(interp)
+    }
+}
+
+/// Parses and type checks the file with the prelude of its flavour, the given built-in preludes and the extra
+/// preludes from the settings.
+/// Only file and parse errors are printed, the errors found by the type checker are left in the type environment.
+/// The status says whether any errors were printed
+pub fn load(
+    filepath: &PathBuf,
+    args: &Args,
+    builtin_preludes: &[(&str, &str)],
+) -> Result<(Checked, Status), Status> {
+    let settings = match Settings::load(args, ProjectConfig::find(filepath).as_deref()) {
+        Ok(settings) => { settings }
+        Err(msg) => {
+            println!("{msg}");
+            return Err(Status::Failure);
+        }
+    };
+
+    let ast_cache = &mut AstMap::new();
+    let src_cache = AstMap::new();
+
+    let mut type_env = TypeEnv::new(settings.include_dirs.clone());
+    let mut preludes = vec![];
+    let flavour_prelude = (PRELUDE_PATH, settings.flavour.prelude());
+    for (path, src) in [&flavour_prelude].into_iter().chain(builtin_preludes) {
+        let path = PathBuf::from(path);
+        gen_errs_from_src(&path, src, &mut type_env, ast_cache, &src_cache)
+            .expect("Prelude can't produce parse errors");
+        preludes.push(path);
+    }
+
+    let mut status = Status::Clean;
+    for path in settings.extra_preludes.iter().chain([filepath]) {
+        let Err(errs) = gen_errs_from_path(path, &mut type_env, ast_cache, &src_cache) else {
+            continue;
+        };
+        for err in errs {
+            match err {
+                Error::FileErr(_path, msg) => {
+                    println!("{msg}");
+                    status = Status::Failure;
+                }
+                Error::CircularDep(_path, msg) => {
+                    println!("{msg}");
+                    status = status.max(Status::Errors);
+                }
+                Error::ParseErrs { path, errs } => {
+                    print_parse_errs(&path, &errs);
+                    status = status.max(Status::Errors);
+                }
+            }
+        }
+    }
+
+    preludes.extend(settings.extra_preludes.iter().cloned());
+    Ok((Checked { settings, ast_cache: std::mem::take(ast_cache), type_env, preludes }, status))
+}
+
+/// Type checks the file with the prelude of its flavour, the given built-in preludes and the extra preludes from
+/// the settings. Errors are
+/// printed, warnings are not reported. `action` describes what is not done to the file if it has errors
+pub fn type_check(
+    filepath: &PathBuf,
+    args: &Args,
+    builtin_preludes: &[(&str, &str)],
+    action: &str,
+) -> Result<Checked, Status> {
+    let (checked, mut status) = load(filepath, args, builtin_preludes)?;
+    for (path, errs) in checked.type_env.errs() {
+        let errs = errs.iter()
+            .filter(|err| !err.is_warning())
+            .cloned()
+            .collect::<Vec<_>>();
+        if !errs.is_empty() {
+            print_xs_errs(path, &errs, &HashSet::new(), &HashSet::new());
+            status = status.max(Status::Errors);
+        }
+    }
+    if status != Status::Clean {
+        println!("Not {action} file '{}' because it has errors", filepath.display());
+        return Err(status);
+    }
+    Ok(checked)
+}
+
+/// Deeply recursive scripts need more stack than the main thread has
+pub fn with_interp_stack<T: Send>(f: impl FnOnce() -> T + Send) -> T {
+    thread::scope(|scope| thread::Builder::new()
+        .stack_size(INTERP_STACK_SIZE)
+        .spawn_scoped(scope, f)
+        .expect("Failed to spawn the interpreter thread")
+        .join()
+        .expect("The interpreter does not panic")
+    )
+}
+
+/// Type checks the file and runs its entry point if there are no errors
+pub fn run_file(args: &RunArgs) -> Status {
+    let filepath = &args.check.filepaths[0];
+    let checked = match type_check(filepath, &args.check, &[], "running") {
+        Ok(checked) => { checked }
+        Err(status) => { return status; }
+    };
+
+    let result = with_interp_stack(|| {
+        checked.interpreter(filepath, NativeHost::new().with_chat_output())
+            .map(|interp| interp.with_max_iterations(args.max_iterations))
+            .and_then(|mut interp| interp.call(&args.entry, vec![]))
+    });
+
+    match result {
+        Ok(Value::Void) => { Status::Clean }
+        Ok(value) => {
+            println!("{} returned {value}", args.entry);
+            Status::Clean
+        }
+        Err(err) => {
+            print_runtime_err(&err);
+            Status::Errors
+        }
+    }
+}
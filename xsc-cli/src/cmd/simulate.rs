@@ -0,0 +1,35 @@
+use xsc_core::interp::{NativeHost, RuleNatives, Scheduler};
+
+use crate::cli::{SimulateArgs, Status};
+use crate::cmd::run::{type_check, with_interp_stack};
+use crate::fmt::print_runtime_err;
+
+/// Type checks the file and runs its rules over a simulated game clock, printing which rule fired when
+pub fn simulate_file(args: &SimulateArgs) -> Status {
+    let filepath = &args.check.filepaths[0];
+    let checked = match type_check(filepath, &args.check, &[], "simulating") {
+        Ok(checked) => { checked }
+        Err(status) => { return status; }
+    };
+
+    let (timeline, result) = with_interp_stack(|| {
+        let interp = match checked.interpreter(filepath, RuleNatives::new(NativeHost::new())) {
+            Ok(interp) => { interp.with_max_iterations(args.max_iterations) }
+            Err(err) => { return (vec![], Err(err)); }
+        };
+        let mut scheduler = Scheduler::new(interp, args.tick);
+        let result = scheduler.run_until(args.duration.saturating_mul(1000));
+        (scheduler.timeline().to_vec(), result)
+    });
+
+    for event in &timeline {
+        println!("{:>9.3}s  {}", event.time as f64 / 1000.0, event.kind);
+    }
+    match result {
+        Ok(()) => { Status::Clean }
+        Err(err) => {
+            print_runtime_err(&err);
+            Status::Errors
+        }
+    }
+}
@@ -0,0 +1,118 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+use xsc_core::interp::{NativeHost, Natives, Value};
+use xsc_core::parsing::ast::{Identifier, Type};
+use xsc_core::r#static::info::TypeEnv;
+
+use crate::cli::{Status, TestArgs};
+use crate::cmd::run::{type_check, with_interp_stack, Checked, PRELUDE_PATH};
+use crate::fmt::{junit_xml, print_runtime_err, Sources, TestCase};
+
+const TEST_PRELUDE: (&str, &str) = (r"test_prelude.xs", include_str!(r"../../../xsc-core/test_prelude.xs"));
+
+/// The natives of a test run. The functions of the test prelude are implemented here, everything else is
+/// delegated to a [`NativeHost`]
+struct TestHost {
+    host: NativeHost,
+}
+
+impl Natives for TestHost {
+    fn call(&mut self, name: &str, args: &[Value], return_type: &Type) -> Option<Result<Value, String>> {
+        match (name, args) {
+            ("xsAssert", [Value::Bool(false), Value::Str(msg)]) if msg.is_empty() => {
+                Some(Err("Assertion failed".into()))
+            }
+            ("xsAssert", [Value::Bool(false), Value::Str(msg)]) => {
+                Some(Err(format!("Assertion failed: {msg}")))
+            }
+            ("xsAssert", _) => Some(Ok(Value::Void)),
+            _ => self.host.call(name, args, return_type),
+        }
+    }
+}
+
+/// Test functions are `void` functions without parameters that are named `test_*` or are tagged with `@test`.
+/// Only functions defined in the file itself are returned, in the order they are defined in
+fn find_tests(filepath: &PathBuf, type_env: &TypeEnv) -> Vec<Identifier> {
+    let mut tests = type_env.identifiers.iter()
+        .filter(|(name, info)| {
+            let Type::Fn { type_sign, .. } = &info.type_ else {
+                return false;
+            };
+            info.src_loc.file_path == *filepath
+                && (name.0.starts_with("test_") || info.doc.is_test())
+                && matches!(type_sign.as_slice(), [(_return, Type::Void)])
+        })
+        .map(|(name, info)| (info.src_loc.span.start, name.clone()))
+        .collect::<Vec<_>>();
+    tests.sort_by_key(|(start, _name)| *start);
+    tests.into_iter().map(|(_start, name)| name).collect()
+}
+
+/// Every test gets a fresh interpreter so that globals and arrays don't leak between tests
+fn run_test(filepath: &PathBuf, checked: &Checked, name: &Identifier, max_iterations: u64) -> TestCase {
+    let start = Instant::now();
+    let result = with_interp_stack(|| {
+        checked.interpreter(filepath, TestHost { host: NativeHost::new().with_chat_output() })
+            .map(|interp| interp.with_max_iterations(max_iterations))
+            .and_then(|mut interp| interp.call(&name.0, vec![]))
+    });
+    TestCase { name: name.0.clone(), time: start.elapsed(), failure: result.err() }
+}
+
+/// Type checks each file and runs the tests in it
+pub fn test_files(args: &TestArgs) -> Status {
+    let mut status = Status::Clean;
+    let mut suites = vec![];
+    let mut srcs = Sources::new(vec![
+        (PathBuf::from(TEST_PRELUDE.0), TEST_PRELUDE.1.to_string()),
+    ]);
+    let (mut passed, mut failed) = (0, 0);
+
+    for filepath in &args.check.filepaths {
+        let checked = match type_check(filepath, &args.check, &[TEST_PRELUDE], "testing") {
+            Ok(checked) => { checked }
+            Err(err_status) => {
+                status = status.max(err_status);
+                continue;
+            }
+        };
+
+        srcs.insert_virtual(Path::new(PRELUDE_PATH), checked.settings.flavour.prelude());
+
+        let tests = find_tests(filepath, &checked.type_env);
+        println!("running {} test(s) in '{}'", tests.len(), filepath.display());
+
+        let mut cases = vec![];
+        for name in &tests {
+            let case = run_test(filepath, &checked, name, args.max_iterations);
+            match &case.failure {
+                None => {
+                    println!("test {name} ... ok");
+                    passed += 1;
+                }
+                Some(err) => {
+                    println!("test {name} ... FAILED");
+                    print_runtime_err(err);
+                    failed += 1;
+                    status = status.max(Status::Errors);
+                }
+            }
+            cases.push(case);
+        }
+        suites.push((filepath.clone(), cases));
+    }
+
+    let result = if status == Status::Clean { "ok" } else { "FAILED" };
+    println!("test result: {result}. {passed} passed; {failed} failed");
+
+    if let Some(junit_path) = &args.junit {
+        if let Err(err) = fs::write(junit_path, junit_xml(&suites, &mut srcs)) {
+            println!("Failed to write '{}': {err}", junit_path.display());
+            return Status::Failure;
+        }
+    }
+    status
+}
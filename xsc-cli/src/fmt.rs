@@ -1,5 +1,13 @@
 mod print_errs;
 mod msg_fmt;
+mod err_msg;
+mod pos_info;
+mod json_errs;
+mod sarif;
+mod junit;
 
-pub use print_errs::{print_parse_errs, print_xs_errs};
-
+pub use print_errs::{print_parse_errs, print_runtime_err, print_xs_errs};
+pub use json_errs::{file_err_to_json, parse_errs_to_json, xs_errs_to_json};
+pub use pos_info::Sources;
+pub use sarif::SarifLog;
+pub use junit::{junit_xml, TestCase};
@@ -0,0 +1,93 @@
+use xsc_core::r#static::info::{SrcLoc, XsError};
+
+use crate::fmt::msg_fmt::msg_fmt;
+
+/// What a highlighted part of an error message is
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MsgPart {
+    Keyword,
+    Name,
+    Type,
+}
+
+/// The label message of an error, with `highlight` applied to the keywords, names and types in it. Every output
+/// format uses this so that the messages are worded the same everywhere
+pub fn xs_err_msg_with(error: &XsError, highlight: impl Fn(&str, MsgPart) -> String) -> String {
+    match error {
+        XsError::ExtraArg { fn_name, .. } => {
+            format!("Extra argument provided to function {}", highlight(fn_name, MsgPart::Name))
+        }
+        XsError::TypeMismatch { actual, expected, .. } => {
+            format!(
+                "Expected type {} but found {}",
+                highlight(expected, MsgPart::Type),
+                highlight(actual, MsgPart::Type),
+            )
+        }
+        XsError::NotCallable { name, actual, .. } => {
+            format!(
+                "The variable {} is of type {} and not a function",
+                highlight(name, MsgPart::Name),
+                highlight(actual, MsgPart::Type),
+            )
+        }
+        XsError::OpMismatch { op, type1, type2, .. } => {
+            format!(
+                "Cannot {} types {} and {}",
+                op,
+                highlight(type1, MsgPart::Type),
+                highlight(type2, MsgPart::Type),
+            )
+        }
+        XsError::UndefinedName { name, .. } => {
+            format!("Name {} is not defined", highlight(name, MsgPart::Name))
+        }
+        XsError::PrivateName { name, src_loc, .. } => {
+            format!(
+                "Name {} is private in file {}. Consider using {} if needed",
+                highlight(name, MsgPart::Name),
+                src_loc.file_path.file_name().unwrap_or_default().to_string_lossy(),
+                highlight("extern", MsgPart::Keyword),
+            )
+        }
+        XsError::RedefinedName { name, .. } => {
+            format!("Name {} is already defined", highlight(name, MsgPart::Name))
+        }
+        XsError::UnresolvedInclude { inc_filename, .. } => {
+            format!("Failed to resolve included file {}", highlight(inc_filename, MsgPart::Name))
+        }
+        XsError::Syntax { msg, keywords, .. } => {
+            msg_fmt(msg, keywords, |kw| highlight(kw, MsgPart::Keyword))
+        }
+        XsError::Warning { msg, keywords, .. } => {
+            msg_fmt(msg, keywords, |kw| highlight(kw, MsgPart::Type))
+        }
+    }
+}
+
+/// The uncoloured label message of an error, used for machine-readable output
+pub fn xs_err_msg(error: &XsError) -> String {
+    xs_err_msg_with(error, |text, _part| text.to_string())
+}
+
+pub fn xs_err_note(error: &XsError) -> Option<&str> {
+    match error {
+        XsError::TypeMismatch { note, .. } => { note.as_deref() }
+        XsError::OpMismatch { note, .. } => { note.as_deref() }
+        XsError::RedefinedName { note, .. } => { note.as_deref() }
+        _ => { None }
+    }
+}
+
+/// Other locations relevant to the error, with a message describing each
+pub fn xs_err_related(error: &XsError) -> Vec<(&SrcLoc, String)> {
+    match error {
+        XsError::PrivateName { name, src_loc, .. } => {
+            vec![(src_loc, format!("Name {} is defined here", name))]
+        }
+        XsError::RedefinedName { name, og_src_loc, .. } => {
+            vec![(og_src_loc, format!("Name {} was originally defined here", name))]
+        }
+        _ => { vec![] }
+    }
+}
@@ -0,0 +1,97 @@
+use std::collections::HashSet;
+use std::path::Path;
+
+use ariadne::ReportKind;
+use serde_json::{json, Value};
+
+use xsc_core::parsing::span::Span;
+use xsc_core::r#static::info::{ParseError, XsError};
+
+use crate::fmt::err_msg::{xs_err_msg, xs_err_note, xs_err_related};
+use crate::fmt::pos_info::{range_from_span, Sources};
+
+fn range_json(path: &Path, span: &Span, srcs: &mut Sources) -> Value {
+    let Some(src) = srcs.get(path) else {
+        return Value::Null;
+    };
+    let ((start_line, start_col), (end_line, end_col)) = range_from_span(src, span);
+
+    json!({
+        "start": { "line": start_line, "column": start_col, "offset": span.start },
+        "end": { "line": end_line, "column": end_col, "offset": span.end },
+    })
+}
+
+fn severity(kind: &ReportKind) -> &'static str {
+    match kind {
+        ReportKind::Error => "error",
+        ReportKind::Warning => "warning",
+        _ => "note",
+    }
+}
+
+/// One record per (non-ignored) error, in the format printed by `--format json`. Warnings with codes in `promoted`
+/// are given the error severity
+pub fn xs_errs_to_json(
+    path: &Path,
+    errs: &[XsError],
+    ignores: &HashSet<u32>,
+    promoted: &HashSet<u32>,
+    srcs: &mut Sources,
+) -> Vec<Value> {
+    errs.iter()
+        .filter(|error| !ignores.contains(&error.code()) && !error.is_ignored())
+        .map(|error| {
+            let related = xs_err_related(error).into_iter()
+                .map(|(src_loc, msg)| json!({
+                    "path": src_loc.file_path,
+                    "range": range_json(&src_loc.file_path, &src_loc.span, srcs),
+                    "message": msg,
+                }))
+                .collect::<Vec<_>>();
+            let severity = match promoted.contains(&error.code()) {
+                true => { "error" }
+                false => { severity(&error.report_kind()) }
+            };
+
+            json!({
+                "path": path,
+                "range": range_json(path, error.span(), srcs),
+                "kind": error.kind(),
+                "code": error.code(),
+                "severity": severity,
+                "message": xs_err_msg(error),
+                "note": xs_err_note(error),
+                "related": related,
+            })
+        })
+        .collect()
+}
+
+pub fn parse_errs_to_json(path: &Path, errs: &[ParseError], srcs: &mut Sources) -> Vec<Value> {
+    errs.iter()
+        .map(|error| json!({
+            "path": path,
+            "range": range_json(path, error.span(), srcs),
+            "kind": error.kind(),
+            "code": Value::Null,
+            "severity": "error",
+            "message": error.msg(),
+            "note": Value::Null,
+            "related": [],
+        }))
+        .collect()
+}
+
+pub fn file_err_to_json(path: &Path, msg: &str) -> Value {
+    json!({
+        "path": path,
+        "range": Value::Null,
+        "kind": "FileError",
+        "code": Value::Null,
+        "severity": "error",
+        "message": msg,
+        "note": Value::Null,
+        "related": [],
+    })
+}
@@ -0,0 +1,95 @@
+use std::path::PathBuf;
+use std::time::Duration;
+
+use xsc_core::interp::RuntimeError;
+use xsc_core::r#static::info::SrcLoc;
+
+use crate::fmt::pos_info::{line_col, Sources};
+
+/// The outcome of a single test function
+pub struct TestCase {
+    pub name: String,
+    pub time: Duration,
+    pub failure: Option<RuntimeError>,
+}
+
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// The location of a runtime error as `path:line:col`, or just the path if the file can't be read
+fn location(src_loc: &SrcLoc, srcs: &mut Sources) -> String {
+    let SrcLoc { file_path, span } = src_loc;
+    match srcs.get(file_path) {
+        Some(src) => {
+            let (line, col) = line_col(src, span.start);
+            format!("{}:{line}:{col}", file_path.display())
+        }
+        None => file_path.display().to_string(),
+    }
+}
+
+/// A JUnit XML report with one test suite per file
+pub fn junit_xml(suites: &[(PathBuf, Vec<TestCase>)], srcs: &mut Sources) -> String {
+    let total = suites.iter().map(|(_path, cases)| cases.len()).sum::<usize>();
+    let failures = suites.iter()
+        .flat_map(|(_path, cases)| cases)
+        .filter(|case| case.failure.is_some())
+        .count();
+    let time = suites.iter()
+        .flat_map(|(_path, cases)| cases)
+        .map(|case| case.time)
+        .sum::<Duration>();
+
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    xml.push_str(&format!(
+        "<testsuites name=\"xs-check\" tests=\"{total}\" failures=\"{failures}\" time=\"{:.3}\">\n",
+        time.as_secs_f64(),
+    ));
+    for (path, cases) in suites {
+        let name = escape(&path.display().to_string());
+        let failures = cases.iter().filter(|case| case.failure.is_some()).count();
+        let time = cases.iter().map(|case| case.time).sum::<Duration>();
+        xml.push_str(&format!(
+            "  <testsuite name=\"{name}\" tests=\"{}\" failures=\"{failures}\" errors=\"0\" time=\"{:.3}\">\n",
+            cases.len(),
+            time.as_secs_f64(),
+        ));
+        for case in cases {
+            let open = format!(
+                "    <testcase name=\"{}\" classname=\"{name}\" time=\"{:.3}\"",
+                escape(&case.name),
+                case.time.as_secs_f64(),
+            );
+            let Some(err) = &case.failure else {
+                xml.push_str(&format!("{open}/>\n"));
+                continue;
+            };
+            let text = match &err.src_loc {
+                Some(src_loc) => format!("{} at {}", err.msg, location(src_loc, srcs)),
+                None => err.msg.clone(),
+            };
+            xml.push_str(&format!("{open}>\n"));
+            xml.push_str(&format!(
+                "      <failure message=\"{}\" type=\"RuntimeError\">{}</failure>\n",
+                escape(&err.msg),
+                escape(&text),
+            ));
+            xml.push_str("    </testcase>\n");
+        }
+        xml.push_str("  </testsuite>\n");
+    }
+    xml.push_str("</testsuites>\n");
+    xml
+}
@@ -1,17 +1,16 @@
-use ariadne::{Color, Fmt};
-
-pub fn msg_fmt(mut msg: &str, keywords: &[String], color: &Color) -> String {
+/// Fills the `{0}`, `{1}`, ... placeholders in the message with the keywords, after applying `highlight` to them
+pub fn msg_fmt(mut msg: &str, keywords: &[String], highlight: impl Fn(&str) -> String) -> String {
     let mut result = String::new();
 
     for (idx, kw) in keywords.iter().enumerate() {
         let idx = format!("{{{}}}", idx);
         if let Some(pos) = msg.find(&idx) {
             result.push_str(&msg[..pos]);
-            result.push_str(&kw.fg(*color).to_string());
+            result.push_str(&highlight(kw));
             msg = &msg[pos + idx.len()..];
         }
     }
-    result.push_str(&msg);
+    result.push_str(msg);
 
     result
-}
\ No newline at end of file
+}
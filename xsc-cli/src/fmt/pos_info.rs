@@ -0,0 +1,53 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use xsc_core::parsing::span::Span;
+
+/// Lazily read sources of the files that diagnostics are reported in
+pub struct Sources {
+    srcs: HashMap<PathBuf, Option<String>>,
+}
+
+impl Sources {
+    /// Files that do not exist on disk (like the prelude) must be provided upfront, or with [`Self::insert_virtual`]
+    pub fn new(virtual_srcs: Vec<(PathBuf, String)>) -> Self {
+        Self {
+            srcs: virtual_srcs.into_iter()
+                .map(|(path, src)| (path, Some(src)))
+                .collect()
+        }
+    }
+
+    /// Adds a file that does not exist on disk, replacing any source it already had
+    pub fn insert_virtual(&mut self, path: &Path, src: &str) {
+        self.srcs.insert(path.to_path_buf(), Some(src.to_string()));
+    }
+
+    pub fn get(&mut self, path: &Path) -> Option<&str> {
+        self.srcs
+            .entry(path.to_path_buf())
+            .or_insert_with(|| fs::read_to_string(path).ok())
+            .as_deref()
+    }
+}
+
+/// Converts a byte offset into a 1-based (line, column) pair. Columns are counted in chars
+pub fn line_col(src: &str, offset: usize) -> (usize, usize) {
+    let mut offset = offset.min(src.len());
+    while !src.is_char_boundary(offset) {
+        offset -= 1;
+    }
+    let before = &src[..offset];
+    let line_start = before.rfind('\n').map_or(0, |pos| pos + 1);
+
+    (
+        before.matches('\n').count() + 1,
+        before[line_start..].chars().count() + 1,
+    )
+}
+
+/// 1-based ((start line, start column), (end line, end column)) of a span
+pub fn range_from_span(src: &str, span: &Span) -> ((usize, usize), (usize, usize)) {
+    (line_col(src, span.start), line_col(src, span.end))
+}
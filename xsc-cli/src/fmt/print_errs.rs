@@ -4,10 +4,18 @@ use std::path::PathBuf;
 
 use ariadne::{Color, Fmt, Label, Report, ReportKind, Source};
 
-use crate::fmt::msg_fmt::msg_fmt;
-use xsc_core::r#static::info::{ParseError, XsError};
+use crate::fmt::err_msg::{xs_err_msg_with, xs_err_note, MsgPart};
+use xsc_core::interp::RuntimeError;
+use xsc_core::r#static::info::{ParseError, SrcLoc, XsError};
 
-pub fn print_xs_errs(path: &PathBuf, errs: &Vec<XsError>, ignores: &HashSet<u32>) -> bool {
+/// Prints the given errors and returns the number of (errors, warnings) that were printed. Warnings with codes
+/// in `promoted` are printed and counted as errors
+pub fn print_xs_errs(
+    path: &PathBuf,
+    errs: &Vec<XsError>,
+    ignores: &HashSet<u32>,
+    promoted: &HashSet<u32>,
+) -> (usize, usize) {
     let filename = &path.display().to_string();
     let src = &fs::read_to_string(&path).expect("Infallible: If we are here, the file was read previously");
     
@@ -16,109 +24,36 @@ pub fn print_xs_errs(path: &PathBuf, errs: &Vec<XsError>, ignores: &HashSet<u32>
     let names = Color::Fixed(13);
     let types = Color::Fixed(14);
 
-    let mut found_errs = false;
+    let (mut num_errs, mut num_warnings) = (0, 0);
     for error in errs.iter() {
         if ignores.contains(&error.code()) || error.is_ignored() {
             continue;
         }
-        found_errs = true;
-        let report = Report::build(error.report_kind(), filename, error.span().start)
+        let report_kind = if error.is_warning() && !promoted.contains(&error.code()) {
+            num_warnings += 1;
+            error.report_kind()
+        } else {
+            num_errs += 1;
+            ReportKind::Error
+        };
+        let report = Report::build(report_kind, filename, error.span().start)
             .with_code(error.code())
             .with_message(error.kind());
-        let report = match error {
-            XsError::ExtraArg { fn_name, span } => {
-                report.with_label(
-                    Label::new((filename, span.start..span.end))
-                        .with_message(format!("Extra argument provided to function {}", fn_name.fg(names)))
-                        .with_color(highlight)
-                )
-            }
-            XsError::TypeMismatch { actual, expected, span, note } => {
-                let report = report.with_label(
-                    Label::new((filename, span.start..span.end))
-                        .with_message(format!("Expected type {} but found {}", expected.fg(types), actual.fg(types)))
-                        .with_color(highlight)
-                );
-                match note {
-                    None => { report }
-                    Some(note) => {
-                        report.with_help(note)
-                    }
-                }
-            }
-            XsError::NotCallable { name, actual, span } => {
-                report.with_label(
-                    Label::new((filename, span.start..span.end))
-                        .with_message(format!("The variable {} is of type {} and not a function", name.fg(names), actual.fg(types)))
-                        .with_color(highlight)
-                )
-            }
-            XsError::OpMismatch { op, type1, type2, span, note } => {
-                let report = report.with_label(
-                    Label::new((filename, span.start..span.end))
-                        .with_message(format!("Cannot {} types {} and {}", op, type1.fg(types), type2.fg(types)))
-                        .with_color(highlight)
-                );
-                match note {
-                    None => { report }
-                    Some(note) => {
-                        report.with_help(note)
-                    }
-                }
-            }
-            XsError::UndefinedName { name, span } => {
-                report.with_label(
-                    Label::new((filename, span.start..span.end))
-                        .with_message(format!("Name {} is not defined", name.fg(names)))
-                        .with_color(highlight)
-                )
-            }
-            XsError::PrivateName { name, span, src_loc } => {
-                report.with_label(
-                    Label::new((filename, span.start..span.end))
-                        .with_message(format!(
-                            "Name {} is private in file {}. Consider using {} if needed",
-                            name.fg(names),
-                            src_loc.file_path.file_name().unwrap_or_default().to_string_lossy(),
-                            "extern".fg(kwds),
-                        ))
-                        .with_color(highlight)
-                )
-            }
-            XsError::RedefinedName { name, span, note, .. } => {
-                let report = report.with_label(
-                    Label::new((filename, span.start..span.end))
-                        .with_message(format!("Name {} is already defined", name.fg(names)))
-                        .with_color(highlight)
-                );
-                match note {
-                    None => { report }
-                    Some(note) => {
-                        report.with_help(note)
-                    }
-                }
-            }
-            XsError::UnresolvedInclude { inc_filename, span } => {
-                report.with_label(
-                    Label::new((filename, span.start..span.end))
-                        .with_message(format!("Failed to resolve included file {}", inc_filename.fg(names)))
-                        .with_color(highlight)
-                )
-            }
-            XsError::Syntax { span, msg, keywords } => {
-                report.with_label(
-                    Label::new((filename, span.start..span.end))
-                        .with_message(msg_fmt(msg, keywords, &kwds))
-                        .with_color(highlight)
-                )
-            }
-
-            XsError::Warning { span, msg, keywords, .. } => {
-                report.with_label(
-                    Label::new((filename, span.start..span.end))
-                        .with_message(msg_fmt(msg, keywords, &types))
-                        .with_color(highlight)
-                )
+        let msg = xs_err_msg_with(error, |text, part| match part {
+            MsgPart::Keyword => text.fg(kwds).to_string(),
+            MsgPart::Name => text.fg(names).to_string(),
+            MsgPart::Type => text.fg(types).to_string(),
+        });
+        let span = error.span();
+        let report = report.with_label(
+            Label::new((filename, span.start..span.end))
+                .with_message(msg)
+                .with_color(highlight)
+        );
+        let report = match xs_err_note(error) {
+            None => { report }
+            Some(note) => {
+                report.with_help(note)
             }
         };
         report
@@ -126,7 +61,7 @@ pub fn print_xs_errs(path: &PathBuf, errs: &Vec<XsError>, ignores: &HashSet<u32>
             .print((filename, Source::from(src)))
             .unwrap();
     }
-    found_errs
+    (num_errs, num_warnings)
 }
 
 pub fn print_parse_errs(path: &PathBuf, errs: &Vec<ParseError>) {
@@ -149,4 +84,28 @@ pub fn print_parse_errs(path: &PathBuf, errs: &Vec<ParseError>) {
             .print((filename, Source::from(&src)))
             .unwrap();
     }
-}
\ No newline at end of file
+}
+
+pub fn print_runtime_err(error: &RuntimeError) {
+    let Some(SrcLoc { file_path, span }) = &error.src_loc else {
+        println!("Runtime Error: {}", error.msg);
+        return;
+    };
+    let Ok(src) = fs::read_to_string(file_path) else {
+        println!("Runtime Error: {}", error);
+        return;
+    };
+    let filename = &file_path.display().to_string();
+    let highlight = Color::Fixed(12);
+
+    Report::build(ReportKind::Error, filename, span.start)
+        .with_message("Runtime Error")
+        .with_label(
+            Label::new((filename, span.start..span.end))
+                .with_message(&error.msg)
+                .with_color(highlight)
+        )
+        .finish()
+        .print((filename, Source::from(&src)))
+        .unwrap();
+}
@@ -0,0 +1,208 @@
+use std::collections::{HashMap, HashSet};
+use std::env;
+use std::path::Path;
+
+use serde_json::{json, Value};
+
+use xsc_core::parsing::span::Span;
+use xsc_core::r#static::info::{ParseError, WarningKind, XsError};
+
+use crate::fmt::err_msg::{xs_err_msg, xs_err_note, xs_err_related};
+use crate::fmt::pos_info::{range_from_span, Sources};
+
+const SCHEMA: &str = "https://json.schemastore.org/sarif-2.1.0.json";
+const INFO_URI: &str = "https://github.com/Divy1211/xs-check";
+
+/// (name, help) for errors produced before type checking, which do not have a code
+const PARSE_RULES: [(&str, &str); 2] = [
+    ("LexerError", "The file contains characters that do not form a valid XS token"),
+    ("ParseError", "The tokens in the file do not form valid XS syntax"),
+];
+
+/// Accumulates results of all the checked files into a single SARIF 2.1.0 log
+pub struct SarifLog {
+    rules: Vec<Value>,
+    rule_idxs: HashMap<String, usize>,
+    results: Vec<Value>,
+    notifications: Vec<Value>,
+}
+
+fn rule(id: String, name: &str, help: &str, level: &str) -> Value {
+    json!({
+        "id": id,
+        "name": name,
+        "shortDescription": { "text": name },
+        "fullDescription": { "text": help },
+        "help": { "text": help },
+        "helpUri": INFO_URI,
+        "defaultConfiguration": { "level": level },
+    })
+}
+
+/// Paths inside the working directory are made relative to it so that code scanning dashboards can map them to
+/// files in the repository
+fn artifact_location(path: &Path) -> Value {
+    let rel_path = env::current_dir().ok()
+        .and_then(|cwd| path.strip_prefix(cwd).ok().map(Path::to_path_buf));
+
+    match rel_path {
+        Some(rel_path) => json!({
+            "uri": rel_path.to_string_lossy().replace('\\', "/"),
+            "uriBaseId": "%SRCROOT%",
+        }),
+        None => json!({ "uri": path.to_string_lossy().replace('\\', "/") }),
+    }
+}
+
+fn physical_location(path: &Path, span: &Span, srcs: &mut Sources) -> Value {
+    let mut region = json!({
+        "byteOffset": span.start,
+        "byteLength": span.end - span.start,
+    });
+    if let Some(src) = srcs.get(path) {
+        let ((start_line, start_col), (end_line, end_col)) = range_from_span(src, span);
+        region["startLine"] = json!(start_line);
+        region["startColumn"] = json!(start_col);
+        region["endLine"] = json!(end_line);
+        region["endColumn"] = json!(end_col);
+    }
+
+    json!({
+        "artifactLocation": artifact_location(path),
+        "region": region,
+    })
+}
+
+impl SarifLog {
+    pub fn new() -> Self {
+        let mut rules = vec![];
+
+        for (code, kind, help) in XsError::ERRORS {
+            rules.push(rule(code.to_string(), kind, help, "error"));
+        }
+        for kind in WarningKind::ALL {
+            rules.push(rule(kind.as_u32().to_string(), kind.as_str(), kind.help(), "warning"));
+        }
+        for (name, help) in PARSE_RULES {
+            rules.push(rule(name.to_string(), name, help, "error"));
+        }
+
+        let rule_idxs = rules.iter()
+            .enumerate()
+            .map(|(idx, rule)| (rule["id"].as_str().expect("Infallible").to_string(), idx))
+            .collect();
+
+        Self { rules, rule_idxs, results: vec![], notifications: vec![] }
+    }
+
+    fn result(&self, rule_id: String, level: &str, msg: String, location: Value) -> Value {
+        json!({
+            "ruleId": rule_id,
+            "ruleIndex": self.rule_idxs[&rule_id],
+            "level": level,
+            "message": { "text": msg },
+            "locations": [{ "physicalLocation": location }],
+        })
+    }
+
+    /// Warnings ignored by an `xsc-ignore` comment or the `--ignores` option are recorded as suppressed results.
+    /// Warnings with codes in `promoted` are given the error level. Returns the number of (errors, warnings) added
+    /// that are not suppressed
+    pub fn add_xs_errs(
+        &mut self,
+        path: &Path,
+        errs: &[XsError],
+        ignores: &HashSet<u32>,
+        promoted: &HashSet<u32>,
+        srcs: &mut Sources
+    ) -> (usize, usize) {
+        let (mut num_errs, mut num_warnings) = (0, 0);
+        for error in errs {
+            let is_warning = error.is_warning() && !promoted.contains(&error.code());
+            let level = if is_warning { "warning" } else { "error" };
+            let msg = match xs_err_note(error) {
+                None => { xs_err_msg(error) }
+                Some(note) => { format!("{}. Note: {}", xs_err_msg(error), note) }
+            };
+
+            let location = physical_location(path, error.span(), srcs);
+            let mut result = self.result(error.code().to_string(), level, msg, location);
+
+            let related = xs_err_related(error).into_iter()
+                .enumerate()
+                .map(|(idx, (src_loc, msg))| json!({
+                    "id": idx,
+                    "physicalLocation": physical_location(&src_loc.file_path, &src_loc.span, srcs),
+                    "message": { "text": msg },
+                }))
+                .collect::<Vec<_>>();
+            if !related.is_empty() {
+                result["relatedLocations"] = json!(related);
+            }
+
+            if error.is_ignored() {
+                result["suppressions"] = json!([{ "kind": "inSource" }]);
+            } else if ignores.contains(&error.code()) {
+                result["suppressions"] = json!([{ "kind": "external" }]);
+            } else if is_warning {
+                num_warnings += 1;
+            } else {
+                num_errs += 1;
+            }
+            self.results.push(result);
+        }
+        (num_errs, num_warnings)
+    }
+
+    pub fn add_parse_errs(&mut self, path: &Path, errs: &[ParseError], srcs: &mut Sources) {
+        for error in errs {
+            let location = physical_location(path, error.span(), srcs);
+            let result = self.result(error.kind().to_string(), "error", error.msg().to_string(), location);
+            self.results.push(result);
+        }
+    }
+
+    /// Files that could not be read are reported as tool notifications as there is no location to attach them to
+    pub fn add_file_err(&mut self, path: &Path, msg: &str) {
+        self.notifications.push(json!({
+            "level": "error",
+            "message": { "text": msg },
+            "locations": [{ "physicalLocation": { "artifactLocation": artifact_location(path) } }],
+        }));
+    }
+
+    pub fn to_json(&self) -> Value {
+        let mut run = json!({
+            "tool": {
+                "driver": {
+                    "name": "xs-check",
+                    "version": env!("CARGO_PKG_VERSION"),
+                    "informationUri": INFO_URI,
+                    "rules": self.rules,
+                }
+            },
+            "columnKind": "unicodeCodePoints",
+            "invocations": [{
+                "executionSuccessful": true,
+                "toolExecutionNotifications": self.notifications,
+            }],
+            "results": self.results,
+        });
+        if let Ok(cwd) = env::current_dir() {
+            let mut uri = cwd.to_string_lossy().replace('\\', "/");
+            if !uri.starts_with('/') {
+                uri.insert(0, '/');
+            }
+            if !uri.ends_with('/') {
+                uri.push('/');
+            }
+            run["originalUriBaseIds"] = json!({ "%SRCROOT%": { "uri": format!("file://{uri}") } });
+        }
+
+        json!({
+            "$schema": SCHEMA,
+            "version": "2.1.0",
+            "runs": [run],
+        })
+    }
+}
@@ -1,73 +1,234 @@
-use std::path::PathBuf;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
 
+use xsc_core::config::ProjectConfig;
 use xsc_core::r#static::info::{gen_errs_from_path, gen_errs_from_src, AstCache, AstMap, Error, SrcCache, TypeEnv};
 
-use crate::cli::parse_args;
-use crate::fmt::{print_parse_errs, print_xs_errs};
+use crate::cli::{parse_args, Command, Format, Status};
+use crate::cmd::{cfg_file, emit_pa, externs_file, format_files, run_file, simulate_file, test_files, PRELUDE_PATH};
+use crate::fmt::{file_err_to_json, parse_errs_to_json, print_parse_errs, print_xs_errs, xs_errs_to_json, SarifLog, Sources};
+use crate::settings::Settings;
 
 mod cli;
+mod cmd;
 mod fmt;
+mod settings;
 
-fn main() {
-    let (filepath, ignores, extra_prelude_path, include_dirs) = match parse_args() {
-        Some(filepath) => { filepath }
-        None => { return; },
+fn main() -> ExitCode {
+    let args = match parse_args() {
+        Ok(Command::Check(args)) => { args }
+        Ok(Command::Fmt(args)) => { return format_files(&args).into(); }
+        Ok(Command::Run(args)) => { return run_file(&args).into(); }
+        Ok(Command::Test(args)) => { return test_files(&args).into(); }
+        Ok(Command::Simulate(args)) => { return simulate_file(&args).into(); }
+        Ok(Command::EmitPa(args)) => { return emit_pa(&args).into(); }
+        Ok(Command::Cfg(args)) => { return cfg_file(&args).into(); }
+        Ok(Command::Externs(args)) => { return externs_file(&args).into(); }
+        Err(status) => { return status.into(); },
     };
     
-    let mut type_env= TypeEnv::new(include_dirs);
     let mut ast_cache = AstMap::new();
-    let mut src_cache = AstMap::new();
+    let src_cache = AstMap::new();
     
-    let prelude_path = PathBuf::from(r"prelude.xs");
-    let prelude = include_str!(r"../../xsc-core/prelude.xs");
+    let prelude_path = PathBuf::from(PRELUDE_PATH);
 
-    gen_errs_from_src(&prelude_path, prelude, &mut type_env, &mut ast_cache, &mut src_cache).expect("Prelude can't produce parse errors");
+    let mut reporter = Reporter {
+        format: args.format,
+        io_failed: false,
+        // errors are reported once per file, even if it is included by multiple checked files
+        reported: HashSet::from([prelude_path.clone()]),
+        srcs: Sources::new(vec![]),
+        sarif: SarifLog::new(),
+        num_errs: 0,
+        num_warnings: 0,
+    };
 
-    let mut has_errors = false;
-    if let Some(extra_prelude_path) = extra_prelude_path {
-        let new_errs = check_file(&extra_prelude_path, &mut type_env, &mut ast_cache, &mut src_cache);
-        has_errors = new_errs;
-    }
-    let new_errs = check_file(&filepath, &mut type_env, &mut ast_cache, &mut src_cache);
-    has_errors =  has_errors || new_errs;
+    // config path -> settings and prelude env of all the files that use that config
+    let mut projects: HashMap<Option<PathBuf>, Option<(Settings, TypeEnv)>> = HashMap::new();
 
-    for (filepath, errs) in type_env.errs() {
-        if errs.is_empty() {
-            continue;
-        } else if filepath == &prelude_path {
-            if errs.iter().any(|err| !err.is_warning()) {
-                panic!("Prelude can't produce errors")
-            } else {
-                continue;
+    let human = args.format == Format::Human;
+    for filepath in &args.filepaths {
+        let config_path = ProjectConfig::find(filepath);
+        let project = projects.entry(config_path.clone()).or_insert_with(|| {
+            match Settings::load(&args, config_path.as_deref()) {
+                Ok(settings) => {
+                    let prelude = settings.flavour.prelude();
+                    reporter.srcs.insert_virtual(&prelude_path, prelude);
+
+                    let mut prelude_env = TypeEnv::new(settings.include_dirs.clone());
+                    gen_errs_from_src(&prelude_path, prelude, &mut prelude_env, &mut ast_cache, &src_cache)
+                        .expect("Prelude can't produce parse errors");
+
+                    if let Some(errs) = prelude_env.errs().get(&prelude_path) {
+                        if errs.iter().any(|err| !err.is_warning()) {
+                            panic!("Prelude can't produce errors")
+                        }
+                    }
+
+                    for extra_prelude_path in &settings.extra_preludes {
+                        reporter.check_file(extra_prelude_path, &mut prelude_env, &mut ast_cache, &src_cache);
+                        reporter.report_new_errs(&prelude_env, &settings);
+                    }
+                    Some((settings, prelude_env))
+                }
+                Err(msg) => {
+                    let config_path = config_path.expect("Loading settings without a config can't fail");
+                    reporter.report_file_err(&config_path, &msg);
+                    None
+                }
             }
+        });
+        let Some((settings, prelude_env)) = project else {
+            continue;
+        };
+        
+        // every file gets a fresh copy of the prelude so that definitions don't leak between files
+        let mut type_env = prelude_env.clone();
+
+        let is_parsed = reporter.check_file(filepath, &mut type_env, &mut ast_cache, &src_cache);
+        reporter.report_new_errs(&type_env, settings);
+
+        if !human {
+            continue;
         }
-        let new_errs = print_xs_errs(filepath, errs, &ignores);
-        has_errors = has_errors || new_errs;
+        // the file's errors may have been printed already if an earlier checked file included it
+        let is_clean = is_parsed && type_env.errs()
+            .get(filepath)
+            .is_none_or(|errs| !errs.iter().any(|err| settings.reports(err)));
+        if is_clean {
+            println!(
+                "No errors found in file '{}'! Your code is free of the pitfalls of XS' quirks =)",
+                filepath.display()
+            );
+        }
+        println!("Finished analysing file '{}'.", filepath.display());
     }
-
-    if !has_errors {
+    
+    if human && args.filepaths.len() > 1 {
         println!(
-            "No errors found in file '{}'! Your code is free of the pitfalls of XS' quirks =)",
-            filepath.display()
+            "Checked {} files: {} error(s), {} warning(s)",
+            args.filepaths.len(),
+            reporter.num_errs,
+            reporter.num_warnings,
         );
     }
-    println!("Finished analysing file '{}'.", filepath.display());
+    if args.format == Format::Sarif {
+        println!("{:#}", reporter.sarif.to_json());
+    }
+
+    let status = if reporter.io_failed {
+        Status::Failure
+    } else if reporter.num_errs > 0 {
+        Status::Errors
+    } else if reporter.num_warnings > 0 {
+        Status::Warnings
+    } else {
+        Status::Clean
+    };
+    status.into()
 }
 
-fn check_file(filepath: &PathBuf, type_env: &mut TypeEnv, ast_cache: &mut AstCache, src_cache: &SrcCache) -> bool {
-    let mut has_errors = false;
-    if let Err(errs) = gen_errs_from_path(&filepath, type_env, ast_cache, src_cache) {
-        has_errors = true;
-        for err in errs {
-            match err {
-                Error::FileErr(_path, msg) => {
-                    println!("{}", msg);
+struct Reporter {
+    format: Format,
+    /// set if any file or config could not be read
+    io_failed: bool,
+    reported: HashSet<PathBuf>,
+    srcs: Sources,
+    sarif: SarifLog,
+    num_errs: usize,
+    num_warnings: usize,
+}
+
+impl Reporter {
+    /// Reports the errors of all files in the type env that have not been reported yet
+    fn report_new_errs(&mut self, type_env: &TypeEnv, settings: &Settings) {
+        let Settings { ignores, promoted, .. } = settings;
+        let (mut num_errs, mut num_warnings) = (0, 0);
+        for (filepath, errs) in type_env.errs() {
+            if !self.reported.insert(filepath.clone()) || errs.is_empty() {
+                continue;
+            }
+            match self.format {
+                Format::Human => {
+                    let (errs, warnings) = print_xs_errs(filepath, errs, ignores, promoted);
+                    num_errs += errs;
+                    num_warnings += warnings;
+                }
+                Format::Json => {
+                    for record in xs_errs_to_json(filepath, errs, ignores, promoted, &mut self.srcs) {
+                        if record["severity"] == "warning" {
+                            num_warnings += 1;
+                        } else {
+                            num_errs += 1;
+                        }
+                        println!("{record}");
+                    }
                 }
-                Error::ParseErrs { path, errs } => {
-                    print_parse_errs(&path, &errs);
+                Format::Sarif => {
+                    let (errs, warnings) = self.sarif.add_xs_errs(filepath, errs, ignores, promoted, &mut self.srcs);
+                    num_errs += errs;
+                    num_warnings += warnings;
+                }
+            }
+        }
+        self.num_errs += num_errs;
+        self.num_warnings += num_warnings;
+    }
+
+    fn report_file_err(&mut self, path: &Path, msg: &str) {
+        self.num_errs += 1;
+        self.io_failed = true;
+        self.print_file_err(path, msg);
+    }
+
+    fn print_file_err(&mut self, path: &Path, msg: &str) {
+        match self.format {
+            Format::Human => println!("{}", msg),
+            Format::Json => println!("{}", file_err_to_json(path, msg)),
+            Format::Sarif => self.sarif.add_file_err(path, msg),
+        }
+    }
+
+    /// Type checks the given file and reports any file or parse errors. Returns false if the file itself could not
+    /// be read or parsed
+    fn check_file(
+        &mut self,
+        filepath: &PathBuf,
+        type_env: &mut TypeEnv,
+        ast_cache: &mut AstCache,
+        src_cache: &SrcCache,
+    ) -> bool {
+        let mut is_parsed = true;
+        if let Err(errs) = gen_errs_from_path(filepath, type_env, ast_cache, src_cache) {
+            for err in errs {
+                match err {
+                    Error::FileErr(path, msg) => {
+                        is_parsed &= path != *filepath;
+                        self.report_file_err(&path, &msg);
+                    }
+                    // a cycle is an error in the code, the files themselves could be read
+                    Error::CircularDep(path, msg) => {
+                        is_parsed &= path != *filepath;
+                        self.num_errs += 1;
+                        self.print_file_err(&path, &msg);
+                    }
+                    Error::ParseErrs { path, errs } => {
+                        is_parsed &= path != *filepath;
+                        self.num_errs += errs.len();
+                        match self.format {
+                            Format::Human => print_parse_errs(&path, &errs),
+                            Format::Json => {
+                                for record in parse_errs_to_json(&path, &errs, &mut self.srcs) {
+                                    println!("{record}");
+                                }
+                            }
+                            Format::Sarif => self.sarif.add_parse_errs(&path, &errs, &mut self.srcs),
+                        }
+                    }
                 }
             }
         }
+        is_parsed
     }
-    has_errors
 }
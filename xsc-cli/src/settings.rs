@@ -0,0 +1,47 @@
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+use xsc_core::config::{Flavour, ProjectConfig};
+use xsc_core::r#static::info::XsError;
+
+use crate::cli::Args;
+
+/// The settings a file is checked with: the command line options merged with the closest `xsc.toml`
+pub struct Settings {
+    pub include_dirs: Vec<PathBuf>,
+    pub extra_preludes: Vec<PathBuf>,
+    pub ignores: HashSet<u32>,
+    /// Codes of warnings that are reported as errors
+    pub promoted: HashSet<u32>,
+    pub flavour: Flavour,
+}
+
+impl Settings {
+    /// Command line options take precedence over, and are added to the options in the config file
+    pub fn load(args: &Args, config_path: Option<&Path>) -> Result<Self, String> {
+        let mut settings = Self {
+            include_dirs: args.include_dirs.clone(),
+            extra_preludes: args.extra_prelude_path.iter().cloned().collect(),
+            ignores: args.ignores.clone(),
+            promoted: args.promoted.clone(),
+            flavour: Flavour::default(),
+        };
+        let Some(config_path) = config_path else {
+            return Ok(settings);
+        };
+        let config = ProjectConfig::load(config_path)?;
+
+        settings.include_dirs.extend(config.include_dirs.iter().cloned());
+        settings.extra_preludes.extend(config.extra_preludes.iter().cloned());
+        settings.ignores.extend(config.ignored_codes());
+        settings.promoted.extend(config.promoted_codes());
+        settings.flavour = config.flavour.unwrap_or_default();
+
+        Ok(settings)
+    }
+
+    /// Whether an error is reported, instead of being ignored by these settings or by a comment in the file
+    pub fn reports(&self, err: &XsError) -> bool {
+        !self.ignores.contains(&err.code()) && !err.is_ignored()
+    }
+}
@@ -0,0 +1,182 @@
+use std::fs;
+use std::path::PathBuf;
+use std::process::Command;
+
+/// A fresh directory for the files of one test
+fn test_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("xsc-cli-{name}-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).expect("Temp dir is writable");
+    dir.canonicalize().expect("Dir was just created")
+}
+
+/// Checks a single file with the given source and returns the JSON records printed for it
+fn check_src(name: &str, src: &str) -> String {
+    let dir = test_dir(name);
+    fs::write(dir.join("a.xs"), src).unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_xs-check"))
+        .arg(dir.join("a.xs"))
+        .arg("--format")
+        .arg("json")
+        .output()
+        .expect("xs-check runs");
+
+    fs::remove_dir_all(&dir).ok();
+    String::from_utf8_lossy(&output.stdout).into_owned()
+}
+
+#[test]
+fn included_checked_file_is_not_clean() {
+    let dir = test_dir("included");
+    fs::write(dir.join("a.xs"), "include \"b.xs\";\n").unwrap();
+    fs::write(dir.join("b.xs"), "int b = \"b\";\n").unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_xs-check"))
+        .arg(dir.join("a.xs"))
+        .arg(dir.join("b.xs"))
+        .arg("-I")
+        .arg(&dir)
+        .output()
+        .expect("xs-check runs");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    assert_eq!(output.status.code(), Some(1), "{stdout}");
+    assert_eq!(stdout.matches("TypeError").count(), 1, "{stdout}");
+    assert!(!stdout.contains(&format!("No errors found in file '{}'", dir.join("b.xs").display())), "{stdout}");
+
+    fs::remove_dir_all(&dir).ok();
+}
+
+#[test]
+fn include_cycle_is_an_error() {
+    let dir = test_dir("cycle");
+    fs::write(dir.join("a.xs"), "include \"b.xs\";\n").unwrap();
+    fs::write(dir.join("b.xs"), "include \"a.xs\";\n").unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_xs-check"))
+        .arg(dir.join("a.xs"))
+        .arg("-I")
+        .arg(&dir)
+        .output()
+        .expect("xs-check runs");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    assert!(stdout.contains("Circular dependency detected"), "{stdout}");
+    assert_eq!(output.status.code(), Some(1), "{stdout}");
+
+    fs::remove_dir_all(&dir).ok();
+}
+
+#[test]
+fn statement_ignores_unused_var() {
+    let stdout = check_src("unused", "\
+void f() {
+    // xsc-ignore: UnusedVar
+    int x = 1;
+    int y = 2;
+}
+");
+
+    assert_eq!(stdout.matches(r#""kind":"UnusedVar""#).count(), 1, "{stdout}");
+    assert!(stdout.contains("The variable y is never read"), "{stdout}");
+}
+
+#[test]
+fn function_ignores_missing_return_past_statement_ignores() {
+    let stdout = check_src("returns", "\
+// xsc-ignore: MissingReturn
+int f(int p = 0) {
+    // xsc-ignore: UnusedVar
+    int x = 1;
+    if (p == 1) {
+        return (1);
+    }
+}
+");
+
+    assert!(!stdout.contains(r#""kind":"MissingReturn""#), "{stdout}");
+}
+
+#[test]
+fn statement_ignores_unreachable() {
+    let stdout = check_src("unreachable", "\
+void f() {
+    return;
+    // xsc-ignore: Unreachable
+    xsChatData(\"dead\");
+}
+");
+
+    assert!(!stdout.contains(r#""kind":"Unreachable""#), "{stdout}");
+}
+
+#[test]
+fn statement_ignores_uninit_var() {
+    let stdout = check_src("uninit", "\
+void f() {
+    int x;
+    // xsc-ignore: UninitVar
+    xsChatData(\"%d\", x);
+}
+");
+
+    assert!(!stdout.contains(r#""kind":"UninitVar""#), "{stdout}");
+}
+
+#[test]
+fn statement_ignores_div_by_zero() {
+    let stdout = check_src("sign", "\
+void f() {
+    // xsc-ignore: DivByZero
+    int x = 5 / 0;
+    int y = 5 / 0;
+    xsChatData(\"%d %d\", x, y);
+}
+");
+
+    assert_eq!(stdout.matches(r#""kind":"DivByZero""#).count(), 1, "{stdout}");
+}
+
+#[test]
+fn sarif_rules_are_named_by_error_kind() {
+    let dir = test_dir("sarif");
+    fs::write(dir.join("a.xs"), "void f() {\n    int b = \"b\";\n    xsChatData(\"%d\", b);\n}\n").unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_xs-check"))
+        .arg(dir.join("a.xs"))
+        .arg("--format")
+        .arg("sarif")
+        .output()
+        .expect("xs-check runs");
+    fs::remove_dir_all(&dir).ok();
+
+    let log: serde_json::Value = serde_json::from_slice(&output.stdout).expect("SARIF is JSON");
+    let run = &log["runs"][0];
+    let result = &run["results"][0];
+    let rule = &run["tool"]["driver"]["rules"][result["ruleIndex"].as_u64().unwrap() as usize];
+    assert_eq!(result["ruleId"], "1");
+    assert_eq!(rule["name"], "TypeError");
+}
+
+#[test]
+fn tests_that_never_finish_fail() {
+    let dir = test_dir("spin");
+    fs::write(dir.join("a.xs"), "void test_spin() {\n    while (true) {\n    }\n}\n\nvoid test_ok() {\n}\n").unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_xs-check"))
+        .arg("test")
+        .arg("--max-iterations")
+        .arg("100")
+        .arg(dir.join("a.xs"))
+        .output()
+        .expect("xs-check runs");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    assert!(stdout.contains("test test_spin ... FAILED"), "{stdout}");
+    assert!(stdout.contains("Stopped after 100 loop iterations"), "{stdout}");
+    assert!(stdout.contains("1 passed; 1 failed"), "{stdout}");
+    assert_eq!(output.status.code(), Some(1), "{stdout}");
+
+    fs::remove_dir_all(&dir).ok();
+}
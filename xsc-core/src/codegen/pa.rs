@@ -0,0 +1,282 @@
+mod expression;
+mod instr;
+mod statement;
+
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+
+use crate::parsing::ast::{AstNode, Body, Identifier, Param, Type};
+use crate::r#static::info::{AstCache, TypeEnv};
+
+pub use instr::{
+    BinOp, Cast, Instr, OpType, Operand, Program, Target, R_BP, R_LP, R_RET, R_SP, R_XP, R_ZERO, SAVED_REGS,
+};
+
+/// The parameters and return type of a function
+struct FnSign {
+    params: Vec<Param>,
+    return_type: Type,
+}
+
+/// A loop or switch that `break` can leave
+struct Breakable {
+    /// indices of the jumps that leave it
+    breaks: Vec<usize>,
+    /// indices of the jumps made by `continue`. Switches can't be continued
+    continues: Option<Vec<usize>>,
+}
+
+/// Lowers type checked XS to PA following the maximal munch rules in `maths/docs/code_gen/xs_mm.md`
+struct Lowerer<'a> {
+    ast_cache: &'a AstCache,
+    type_env: &'a TypeEnv,
+    include_dirs: &'a [PathBuf],
+    fns: HashMap<Identifier, FnSign>,
+    program: Program,
+    next_tmp: usize,
+
+    /// Δ for the locals of the procedure being lowered: their operand and type
+    locals: HashMap<Identifier, (Operand, Type)>,
+    proc_name: Option<Identifier>,
+    return_type: Type,
+    num_locals: u32,
+    /// indices of the jumps made by `return`
+    returns: Vec<usize>,
+    breakables: Vec<Breakable>,
+}
+
+/// Lowers a type checked file and the files it includes to PA. Includes are expanded in place like C macros. The
+/// preludes only provide the signatures of the functions they declare
+pub fn lower(
+    path: &PathBuf,
+    preludes: &[PathBuf],
+    ast_cache: &AstCache,
+    type_env: &TypeEnv,
+    include_dirs: &[PathBuf],
+) -> Program {
+    let mut lowerer = Lowerer {
+        ast_cache,
+        type_env,
+        include_dirs,
+        fns: HashMap::new(),
+        program: Program::default(),
+        next_tmp: 0,
+        locals: HashMap::new(),
+        proc_name: None,
+        return_type: Type::Void,
+        num_locals: 0,
+        returns: vec![],
+        breakables: vec![],
+    };
+
+    let mut files = vec![];
+    lowerer.collect_files(path, &mut HashSet::new(), &mut files);
+    for prelude in preludes {
+        let ast = lowerer.ast(prelude);
+        lowerer.add_fns(&ast);
+    }
+    for (_path, ast) in &files {
+        lowerer.add_fns(ast);
+    }
+
+    for (_path, ast) in &files {
+        lowerer.lower_globals(ast);
+    }
+    for (_path, ast) in &files {
+        for (stmt, _span) in ast {
+            lowerer.lower_proc(stmt);
+        }
+    }
+    lowerer.program
+}
+
+impl Lowerer<'_> {
+    fn ast(&self, path: &PathBuf) -> Body {
+        self.ast_cache.get(path).map(|info| info.1.0.clone()).unwrap_or_default()
+    }
+
+    /// Files in the order their code ends up in once includes are expanded
+    fn collect_files(&self, path: &PathBuf, seen: &mut HashSet<PathBuf>, files: &mut Vec<(PathBuf, Body)>) {
+        if !seen.insert(path.clone()) {
+            return;
+        }
+        let ast = self.ast(path);
+        for (stmt, _span) in &ast {
+            let AstNode::Include((filename, _span)) = stmt else {
+                continue;
+            };
+            let filename = &filename[1..(filename.len() - 1)];
+            let inc_path = self.include_dirs.iter()
+                .map(|dir| dir.join(filename))
+                .find(|inc_path| inc_path.is_file());
+            if let Some(inc_path) = inc_path {
+                self.collect_files(&inc_path, seen, files);
+            }
+        }
+        files.push((path.clone(), ast));
+    }
+
+    fn add_fns(&mut self, ast: &Body) {
+        for (stmt, _span) in ast {
+            match stmt {
+                AstNode::FnDef { return_type, name: (name, _span), params, .. } => {
+                    self.fns.insert(name.clone(), FnSign { params: params.clone(), return_type: return_type.clone() });
+                }
+                // rules can be called like void functions without parameters
+                AstNode::RuleDef { name: (name, _span), .. } => {
+                    self.fns.insert(name.clone(), FnSign { params: vec![], return_type: Type::Void });
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Emits an instruction and returns its index
+    fn emit(&mut self, instr: Instr) -> usize {
+        self.program.instrs.push(instr);
+        self.program.instrs.len() - 1
+    }
+
+    /// The address the next instruction will have (`newAddr?`)
+    fn next_addr(&self) -> usize {
+        self.program.instrs.len() + 1
+    }
+
+    /// Points the jump at the given index to an address
+    fn patch(&mut self, idx: usize, addr: usize) {
+        match &mut self.program.instrs[idx] {
+            Instr::Goto(target) | Instr::IfNot { target, .. } => *target = Target::Addr(addr),
+            _ => unreachable!("Only jumps are patched"),
+        }
+    }
+
+    fn new_tmp(&mut self) -> Operand {
+        self.next_tmp += 1;
+        Operand::Tmp(self.next_tmp)
+    }
+
+    /// Static locals live as long as the program, so they are lowered like globals named after their function
+    fn static_name(proc_name: &Identifier, name: &Identifier) -> String {
+        format!("{proc_name}.{name}")
+    }
+
+    /// Δ(X) and the type of X
+    fn var(&self, name: &Identifier) -> (Operand, Type) {
+        if let Some(local) = self.locals.get(name) {
+            return local.clone();
+        }
+        let type_ = self.type_env.identifiers.get(name)
+            .map(|info| info.type_.clone())
+            .unwrap_or(Type::Int);
+        (Operand::Var(name.0.clone()), type_)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{check, PATH, PRELUDE_PATH};
+
+    fn lower_src(src: &str) -> Program {
+        let (type_env, ast_cache) = check(src);
+        lower(&PathBuf::from(PATH), &[PathBuf::from(PRELUDE_PATH)], &ast_cache, &type_env, &[])
+    }
+
+    /// The addressed instructions of a procedure, without the pushes and pops of the saved registers
+    fn proc_lines(program: &Program, name: &str) -> Vec<String> {
+        let pos = program.procs.iter()
+            .position(|(proc_name, _idx)| proc_name.0 == name)
+            .expect("Procedure is lowered");
+        let start = program.procs[pos].1;
+        let end = program.procs.get(pos + 1).map_or(program.instrs.len(), |(_name, idx)| *idx);
+        (start..end)
+            .filter(|&idx| !matches!(
+                &program.instrs[idx],
+                Instr::Push(Operand::Reg(reg)) | Instr::Pop(Operand::Reg(reg)) if SAVED_REGS.contains(reg)
+            ))
+            .map(|idx| format!("{}: {}", idx + 1, program.instrs[idx]))
+            .collect()
+    }
+
+    #[test]
+    fn globals_come_before_procedures() {
+        let program = lower_src("\
+int g = 2;
+void f() {
+    static int s = 1;
+}
+rule r {
+}
+");
+        let text = program.to_string();
+        assert!(text.starts_with("; globals\n  1: g <- 2\n  2: f.s <- 1\n\n; f\n  3: f\n"), "{text}");
+        let procs = program.procs.iter().map(|(name, _idx)| name.0.as_str()).collect::<Vec<_>>();
+        assert_eq!(procs, ["f", "r"]);
+    }
+
+    #[test]
+    fn calls_push_their_arguments_in_reverse() {
+        let program = lower_src("\
+int add(int a = 0, int b = 0) {
+    return (a + b);
+}
+void main() {
+    int x = add(1, 2);
+    float f = x;
+}
+");
+        assert_eq!(proc_lines(&program, "add"), [
+            "1: add",
+            "2: push r_lp",
+            "3: push r_bp",
+            "4: r_bp <- r_sp",
+            "5: alloc 0",
+            "32: r1 <- r_bp -i 3",
+            "33: r1 <- mem[r1]",
+            "34: r2 <- r_bp -i 4",
+            "35: r2 <- mem[r2]",
+            "36: $1 <- r1 +i r2",
+            "37: r_ret <- $1",
+            "38: goto 39",
+            "65: dealloc 0",
+            "66: pop r_bp",
+            "67: pop r_lp",
+            "68: ret",
+        ]);
+        assert_eq!(proc_lines(&program, "main")[5..13], [
+            "100: push 2",
+            "101: push 1",
+            "102: jmp add",
+            "103: dealloc 2",
+            "104: $2 <- r_ret",
+            "105: x <- $2",
+            "106: $3 <- fcast x",
+            "107: f <- $3",
+        ]);
+    }
+
+    #[test]
+    fn loops_jump_back_and_break_out() {
+        let program = lower_src("\
+void main() {
+    int x = 3;
+    while (x > 0) {
+        x--;
+        if (x == 1) {
+            break;
+        }
+    }
+}
+");
+        assert_eq!(proc_lines(&program, "main")[5..13], [
+            "32: x <- 3",
+            "33: $1 <- x >i 0",
+            "34: ifn $1 goto 40",
+            "35: x <- x -i 1",
+            "36: $2 <- x ==i 1",
+            "37: ifn $2 goto 39",
+            "38: goto 40",
+            "39: goto 33",
+        ]);
+    }
+}
@@ -0,0 +1,214 @@
+use crate::codegen::pa::{BinOp, Cast, Instr, Lowerer, OpType, Operand, R_RET};
+use crate::parsing::ast::{Expr, Literal, Type};
+use crate::parsing::span::Spanned;
+
+fn lit_type(lit: &Literal) -> Type {
+    match lit {
+        Literal::Int(_) => Type::Int,
+        Literal::Float(_) => Type::Float,
+        Literal::Bool(_) => Type::Bool,
+        Literal::Str(_) => Type::Str,
+    }
+}
+
+fn is_num(type_: &Type) -> bool {
+    matches!(type_, Type::Int | Type::Float)
+}
+
+impl Lowerer<'_> {
+    /// `M_e(E, T_req)`: lowers an expression and converts its value to the required type (xsMmImplicitCast)
+    pub(super) fn lower_expr_as(&mut self, expr: &Spanned<Expr>, req: &Type) -> Operand {
+        let (src, type_) = self.lower_expr(expr);
+        self.cast(src, &type_, req)
+    }
+
+    /// Emits `d <- tcast s` if the types differ and there is a cast for the required type. Casting into a new
+    /// identifier keeps variables from being modified when they are read
+    pub(super) fn cast(&mut self, src: Operand, type_: &Type, req: &Type) -> Operand {
+        if type_ == req {
+            return src;
+        }
+        let Some(cast) = Cast::to(req) else {
+            return src;
+        };
+        let dst = self.new_tmp();
+        self.emit(Instr::Cast { dst: dst.clone(), cast, src });
+        dst
+    }
+
+    /// `M_e(E)`: lowers an expression, returning the operand that holds its value along with its type
+    pub(super) fn lower_expr(&mut self, (expr, _span): &Spanned<Expr>) -> (Operand, Type) {
+        match expr {
+            // xsMmSrc
+            Expr::Literal(lit) => (Operand::Const(lit.clone()), lit_type(lit)),
+            Expr::Identifier(name) => self.var(name),
+            // xsMmParen
+            Expr::Paren(expr) => self.lower_expr(expr),
+            // vectors are pointers to three consecutive floats
+            Expr::Vec { x, y, z } => {
+                let dst = self.new_tmp();
+                self.emit(Instr::Malloc { dst: dst.clone(), size: 12 });
+                for (offset, component) in [x, y, z].into_iter().enumerate() {
+                    let src = self.lower_expr_as(component, &Type::Float);
+                    let addr = match offset {
+                        0 => dst.clone(),
+                        _ => {
+                            let addr = self.new_tmp();
+                            self.emit(Instr::BinOp {
+                                dst: addr.clone(),
+                                op: BinOp::Add,
+                                type_: OpType::Int,
+                                lhs: dst.clone(),
+                                rhs: Operand::Const(Literal::Int(offset as i64 * 4)),
+                            });
+                            addr
+                        }
+                    };
+                    self.emit(Instr::Move { dst: Operand::Mem(Box::new(addr)), src });
+                }
+                (dst, Type::Vec)
+            }
+            Expr::FnCall { .. } => {
+                let (src, type_) = self.lower_call(expr);
+                // r_ret is overwritten by the next call, so the value is moved out of it when it is used
+                let dst = self.new_tmp();
+                self.emit(Instr::Move { dst: dst.clone(), src });
+                (dst, type_)
+            }
+            Expr::Neg(expr) => {
+                if let (Expr::Literal(lit), _span) = expr.as_ref() {
+                    match lit {
+                        Literal::Int(val) => return (Operand::Const(Literal::Int(-val)), Type::Int),
+                        Literal::Float(val) => return (Operand::Const(Literal::Float(-val)), Type::Float),
+                        _ => {}
+                    }
+                }
+                let (src, type_) = self.lower_expr(expr);
+                let zero = match type_ {
+                    Type::Float => Literal::Float(0.0),
+                    _ => Literal::Int(0),
+                };
+                let dst = self.new_tmp();
+                self.emit(Instr::BinOp {
+                    dst: dst.clone(),
+                    op: BinOp::Sub,
+                    type_: OpType::of(&type_),
+                    lhs: Operand::Const(zero),
+                    rhs: src,
+                });
+                (dst, type_)
+            }
+            Expr::Not(expr) => {
+                let src = self.lower_expr_as(expr, &Type::Bool);
+                let dst = self.new_tmp();
+                self.emit(Instr::BinOp {
+                    dst: dst.clone(),
+                    op: BinOp::Eq,
+                    type_: OpType::Bool,
+                    lhs: src,
+                    rhs: Operand::Const(Literal::Bool(false)),
+                });
+                (dst, Type::Bool)
+            }
+
+            Expr::Star(expr1, expr2) => self.lower_arith(BinOp::Mul, expr1, expr2),
+            Expr::FSlash(expr1, expr2) => self.lower_arith(BinOp::Div, expr1, expr2),
+            Expr::PCent(expr1, expr2) => self.lower_arith(BinOp::Mod, expr1, expr2),
+            Expr::Plus(expr1, expr2) => self.lower_arith(BinOp::Add, expr1, expr2),
+            Expr::Minus(expr1, expr2) => self.lower_arith(BinOp::Sub, expr1, expr2),
+
+            Expr::Lt(expr1, expr2) => self.lower_cmp(BinOp::Lt, expr1, expr2),
+            Expr::Gt(expr1, expr2) => self.lower_cmp(BinOp::Gt, expr1, expr2),
+            Expr::Le(expr1, expr2) => self.lower_cmp(BinOp::Le, expr1, expr2),
+            Expr::Ge(expr1, expr2) => self.lower_cmp(BinOp::Ge, expr1, expr2),
+            Expr::Eq(expr1, expr2) => self.lower_cmp(BinOp::Eq, expr1, expr2),
+            Expr::Ne(expr1, expr2) => self.lower_cmp(BinOp::Ne, expr1, expr2),
+
+            Expr::And(expr1, expr2) => self.lower_logical(BinOp::And, expr1, expr2),
+            Expr::Or(expr1, expr2) => self.lower_logical(BinOp::Or, expr1, expr2),
+        }
+    }
+
+    /// xsMmFncExpr: missing arguments are filled in with the defaults at the call site. The value of the call is
+    /// left in `r_ret`
+    pub(super) fn lower_call(&mut self, expr: &Expr) -> (Operand, Type) {
+        let Expr::FnCall { name: (name, _span), args } = expr else {
+            unreachable!("Only function calls are lowered as calls");
+        };
+        let (params, return_type) = match self.fns.get(name) {
+            Some(sign) => (sign.params.clone(), sign.return_type.clone()),
+            None => (vec![], Type::Void),
+        };
+
+        let mut srcs = args.iter()
+            .zip(&params)
+            .map(|(arg, param)| self.lower_expr_as(arg, &param.type_))
+            .collect::<Vec<_>>();
+        for param in params.iter().skip(args.len()) {
+            let src = self.lower_expr_as(&param.default, &param.type_);
+            srcs.push(src);
+        }
+
+        let num_args = srcs.len();
+        for src in srcs.into_iter().rev() {
+            self.emit(Instr::Push(src));
+        }
+        self.emit(Instr::Jmp(name.clone()));
+        if num_args > 0 {
+            self.emit(Instr::Dealloc(num_args as u32));
+        }
+        (Operand::Reg(R_RET), return_type)
+    }
+
+    /// xsMmOp for arithmetic. The type of the result is the type of the first operand and the second operand is
+    /// converted to it, except for string concatenation and vector operations
+    fn lower_arith(&mut self, op: BinOp, expr1: &Spanned<Expr>, expr2: &Spanned<Expr>) -> (Operand, Type) {
+        let (src1, type1) = self.lower_expr(expr1);
+        let (src2, type2) = self.lower_expr(expr2);
+
+        let (lhs, rhs, type_) = match (&type1, &type2) {
+            (Type::Str, _) | (_, Type::Str) => {
+                let lhs = self.cast(src1, &type1, &Type::Str);
+                let rhs = self.cast(src2, &type2, &Type::Str);
+                (lhs, rhs, Type::Str)
+            }
+            (Type::Vec, Type::Vec) => (src1, src2, Type::Vec),
+            (Type::Vec, type2) if is_num(type2) => {
+                let rhs = self.cast(src2, type2, &Type::Float);
+                (src1, rhs, Type::Vec)
+            }
+            // scaling a vector works both ways around
+            (type1, Type::Vec) if is_num(type1) => {
+                let rhs = self.cast(src1, type1, &Type::Float);
+                (src2, rhs, Type::Vec)
+            }
+            _ => {
+                let rhs = self.cast(src2, &type2, &type1);
+                (src1, rhs, type1)
+            }
+        };
+
+        let dst = self.new_tmp();
+        self.emit(Instr::BinOp { dst: dst.clone(), op, type_: OpType::of(&type_), lhs, rhs });
+        (dst, type_)
+    }
+
+    /// xsMmOp for comparisons. The second operand is converted to the type of the first
+    fn lower_cmp(&mut self, op: BinOp, expr1: &Spanned<Expr>, expr2: &Spanned<Expr>) -> (Operand, Type) {
+        let (lhs, type1) = self.lower_expr(expr1);
+        let rhs = self.lower_expr_as(expr2, &type1);
+
+        let dst = self.new_tmp();
+        self.emit(Instr::BinOp { dst: dst.clone(), op, type_: OpType::of(&type1), lhs, rhs });
+        (dst, Type::Bool)
+    }
+
+    fn lower_logical(&mut self, op: BinOp, expr1: &Spanned<Expr>, expr2: &Spanned<Expr>) -> (Operand, Type) {
+        let (lhs, _type1) = self.lower_expr(expr1);
+        let (rhs, _type2) = self.lower_expr(expr2);
+
+        let dst = self.new_tmp();
+        self.emit(Instr::BinOp { dst: dst.clone(), op, type_: OpType::Bool, lhs, rhs });
+        (dst, Type::Bool)
+    }
+}
@@ -0,0 +1,238 @@
+use std::fmt::{Display, Formatter};
+
+use crate::parsing::ast::{Identifier, Literal, Type};
+
+/// return value register
+pub const R_RET: u8 = 0;
+/// stack pointer
+pub const R_SP: u8 = 27;
+/// base pointer
+pub const R_BP: u8 = 28;
+/// link pointer
+pub const R_LP: u8 = 29;
+/// exception pointer
+pub const R_XP: u8 = 30;
+/// read only, always 0
+pub const R_ZERO: u8 = 31;
+
+/// Registers that a procedure saves before it runs and restores before it returns
+pub const SAVED_REGS: std::ops::RangeInclusive<u8> = 1..=26;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Operand {
+    Const(Literal),
+    Reg(u8),
+    /// a program variable
+    Var(String),
+    /// an identifier generated by the compiler
+    Tmp(usize),
+    Mem(Box<Operand>),
+}
+
+impl Display for Operand {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Operand::Const(Literal::Float(val)) => write!(f, "{val:?}"),
+            Operand::Const(lit) => write!(f, "{lit}"),
+            Operand::Reg(R_RET) => write!(f, "r_ret"),
+            Operand::Reg(R_SP) => write!(f, "r_sp"),
+            Operand::Reg(R_BP) => write!(f, "r_bp"),
+            Operand::Reg(R_LP) => write!(f, "r_lp"),
+            Operand::Reg(R_XP) => write!(f, "r_xp"),
+            Operand::Reg(reg) => write!(f, "r{reg}"),
+            Operand::Var(name) => write!(f, "{name}"),
+            Operand::Tmp(id) => write!(f, "${id}"),
+            Operand::Mem(addr) => write!(f, "mem[{addr}]"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BinOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Mod,
+    Lt,
+    Gt,
+    Le,
+    Ge,
+    Eq,
+    Ne,
+    And,
+    Or,
+}
+
+impl Display for BinOp {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", match self {
+            BinOp::Add => "+",
+            BinOp::Sub => "-",
+            BinOp::Mul => "*",
+            BinOp::Div => "/",
+            BinOp::Mod => "%",
+            BinOp::Lt => "<",
+            BinOp::Gt => ">",
+            BinOp::Le => "<=",
+            BinOp::Ge => ">=",
+            BinOp::Eq => "==",
+            BinOp::Ne => "!=",
+            BinOp::And => "&&",
+            BinOp::Or => "||",
+        })
+    }
+}
+
+/// The type of input an operator variant works on
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OpType {
+    Int,
+    Float,
+    Bool,
+    Str,
+    Vec,
+}
+
+impl OpType {
+    pub fn of(type_: &Type) -> Self {
+        match type_ {
+            Type::Float => OpType::Float,
+            Type::Bool => OpType::Bool,
+            Type::Str => OpType::Str,
+            Type::Vec => OpType::Vec,
+            _ => OpType::Int,
+        }
+    }
+}
+
+impl Display for OpType {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", match self {
+            OpType::Int => "i",
+            OpType::Float => "f",
+            OpType::Bool => "b",
+            OpType::Str => "s",
+            OpType::Vec => "v",
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Cast {
+    Int,
+    Float,
+    Str,
+}
+
+impl Cast {
+    /// The cast that converts a value to the given type, if there is one
+    pub fn to(type_: &Type) -> Option<Self> {
+        match type_ {
+            Type::Int => Some(Cast::Int),
+            Type::Float => Some(Cast::Float),
+            Type::Str => Some(Cast::Str),
+            _ => None,
+        }
+    }
+}
+
+impl Display for Cast {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", match self {
+            Cast::Int => "icast",
+            Cast::Float => "fcast",
+            Cast::Str => "scast",
+        })
+    }
+}
+
+/// Where a jump goes
+#[derive(Debug, Clone, PartialEq)]
+pub enum Target {
+    Addr(usize),
+    /// an XS label
+    Label(Identifier),
+}
+
+impl Display for Target {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Target::Addr(addr) => write!(f, "{addr}"),
+            Target::Label(label) => write!(f, "{label}"),
+        }
+    }
+}
+
+/// A PA instruction, see `maths/docs/code_gen/pa.md`
+#[derive(Debug, Clone, PartialEq)]
+pub enum Instr {
+    /// `d <- s`
+    Move { dst: Operand, src: Operand },
+    /// `d <- s op s`
+    BinOp { dst: Operand, op: BinOp, type_: OpType, lhs: Operand, rhs: Operand },
+    /// `d <- tcast s`
+    Cast { dst: Operand, cast: Cast, src: Operand },
+    Ret,
+    /// `ifn s goto l`
+    IfNot { cond: Operand, target: Target },
+    Goto(Target),
+    Push(Operand),
+    Pop(Operand),
+    Label(Identifier),
+    /// jumps to a label and writes the current address to `r_lp`
+    Jmp(Identifier),
+    Malloc { dst: Operand, size: u32 },
+    Free(Operand),
+    /// alias for `r_sp <- r_sp + n`
+    Alloc(u32),
+    /// alias for `r_sp <- r_sp - n`
+    Dealloc(u32),
+}
+
+impl Display for Instr {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Instr::Move { dst, src } => write!(f, "{dst} <- {src}"),
+            Instr::BinOp { dst, op, type_, lhs, rhs } => write!(f, "{dst} <- {lhs} {op}{type_} {rhs}"),
+            Instr::Cast { dst, cast, src } => write!(f, "{dst} <- {cast} {src}"),
+            Instr::Ret => write!(f, "ret"),
+            Instr::IfNot { cond, target } => write!(f, "ifn {cond} goto {target}"),
+            Instr::Goto(target) => write!(f, "goto {target}"),
+            Instr::Push(src) => write!(f, "push {src}"),
+            Instr::Pop(dst) => write!(f, "pop {dst}"),
+            Instr::Label(label) => write!(f, "{label}"),
+            Instr::Jmp(label) => write!(f, "jmp {label}"),
+            Instr::Malloc { dst, size } => write!(f, "{dst} <- malloc {size}"),
+            Instr::Free(src) => write!(f, "free {src}"),
+            Instr::Alloc(size) => write!(f, "alloc {size}"),
+            Instr::Dealloc(size) => write!(f, "dealloc {size}"),
+        }
+    }
+}
+
+/// The PA of a file and everything it includes. The address of an instruction is its index + 1. Global variables
+/// are initialised first, followed by the procedures
+#[derive(Debug, Clone, Default)]
+pub struct Program {
+    pub instrs: Vec<Instr>,
+    /// the name and index of the first instruction of each function and rule
+    pub procs: Vec<(Identifier, usize)>,
+}
+
+impl Display for Program {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let width = self.instrs.len().to_string().len();
+        let mut procs = self.procs.iter().peekable();
+        if self.procs.first().is_none_or(|(_name, idx)| *idx > 0) {
+            writeln!(f, "; globals")?;
+        }
+        for (idx, instr) in self.instrs.iter().enumerate() {
+            if let Some((name, _idx)) = procs.next_if(|(_name, start)| *start == idx) {
+                writeln!(f, "\n; {name}")?;
+            }
+            writeln!(f, "{:>width$}: {instr}", idx + 1)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,367 @@
+use crate::codegen::pa::{
+    BinOp, Breakable, Instr, Lowerer, OpType, Operand, Target, R_BP, R_LP, R_RET, R_SP, SAVED_REGS,
+};
+use crate::parsing::ast::{AstNode, Body, Expr, Identifier, Literal, Param, Type};
+use crate::parsing::span::{Span, Spanned};
+
+impl Lowerer<'_> {
+    /// Initialises the globals of a file, and the static locals of its functions and rules
+    pub(super) fn lower_globals(&mut self, ast: &Body) {
+        for (stmt, _span) in ast {
+            match stmt {
+                AstNode::VarDef { type_, name: (name, _span), value, .. } => {
+                    let src = self.lower_init(value.as_ref(), type_);
+                    self.emit(Instr::Move { dst: Operand::Var(name.0.clone()), src });
+                }
+                AstNode::FnDef { name: (name, _span), body: (body, _span2), .. }
+                | AstNode::RuleDef { name: (name, _span), body: (body, _span2), .. } => {
+                    self.lower_statics(name, body);
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn lower_statics(&mut self, proc_name: &Identifier, body: &Body) {
+        for (stmt, _span) in body {
+            match stmt {
+                AstNode::VarDef { is_static: true, type_, name: (name, _span), value, .. } => {
+                    let src = self.lower_init(value.as_ref(), type_);
+                    self.emit(Instr::Move { dst: Operand::Var(Self::static_name(proc_name, name)), src });
+                }
+                AstNode::IfElse { consequent: (consequent, _span), alternate, .. } => {
+                    self.lower_statics(proc_name, consequent);
+                    if let Some((alternate, _span)) = alternate {
+                        self.lower_statics(proc_name, alternate);
+                    }
+                }
+                AstNode::While { body: (body, _span), .. } | AstNode::For { body: (body, _span), .. } => {
+                    self.lower_statics(proc_name, body);
+                }
+                AstNode::Switch { cases, .. } => {
+                    for (_expr, (body, _span)) in cases {
+                        self.lower_statics(proc_name, body);
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// The value of a variable definition. Variables without one start with the zero value of their type
+    fn lower_init(&mut self, value: Option<&Spanned<Expr>>, type_: &Type) -> Operand {
+        if let Some(expr) = value {
+            return self.lower_expr_as(expr, type_);
+        }
+        let lit = match type_ {
+            Type::Float => Literal::Float(0.0),
+            Type::Bool => Literal::Bool(false),
+            Type::Str => Literal::str("\"\""),
+            Type::Vec => {
+                let zero = || Box::new((Expr::Literal(Literal::Float(0.0)), Span::new(0, 0)));
+                let vec = (Expr::Vec { x: zero(), y: zero(), z: zero() }, Span::new(0, 0));
+                return self.lower_expr(&vec).0;
+            }
+            _ => Literal::Int(0),
+        };
+        Operand::Const(lit)
+    }
+
+    /// Lowers a function or rule definition, anything else is ignored
+    pub(super) fn lower_proc(&mut self, stmt: &AstNode) {
+        match stmt {
+            AstNode::FnDef { return_type, name: (name, _span), params, body: (body, _span2), .. } => {
+                self.lower_fn(name, params, return_type, body);
+            }
+            // xsMmFnDef without parameters
+            AstNode::RuleDef { name: (name, _span), body: (body, _span2), .. } => {
+                self.lower_fn(name, &[], &Type::Void, body);
+            }
+            _ => {}
+        }
+    }
+
+    /// xsMmFnDef
+    fn lower_fn(&mut self, name: &Identifier, params: &[Param], return_type: &Type, body: &Body) {
+        self.locals.clear();
+        self.proc_name = Some(name.clone());
+        self.return_type = return_type.clone();
+        self.num_locals = 0;
+        self.returns.clear();
+
+        let start = self.emit(Instr::Label(name.clone()));
+        self.program.procs.push((name.clone(), start));
+
+        self.emit(Instr::Push(Operand::Reg(R_LP)));
+        self.emit(Instr::Push(Operand::Reg(R_BP)));
+        self.emit(Instr::Move { dst: Operand::Reg(R_BP), src: Operand::Reg(R_SP) });
+        // the number of locals is only known once the body is lowered
+        let alloc = self.emit(Instr::Alloc(0));
+        for reg in SAVED_REGS {
+            self.emit(Instr::Push(Operand::Reg(reg)));
+        }
+
+        for (i, param) in params.iter().enumerate() {
+            let i = i + 1;
+            let dst = match i <= *SAVED_REGS.end() as usize {
+                true => Operand::Reg(i as u8),
+                false => Operand::Var(param.name.0.0.clone()),
+            };
+            self.emit(Instr::BinOp {
+                dst: dst.clone(),
+                op: BinOp::Sub,
+                type_: OpType::Int,
+                lhs: Operand::Reg(R_BP),
+                rhs: Operand::Const(Literal::Int(2 + i as i64)),
+            });
+            self.emit(Instr::Move { dst: dst.clone(), src: Operand::Mem(Box::new(dst.clone())) });
+            self.locals.insert(param.name.0.clone(), (dst, param.type_.clone()));
+        }
+
+        self.lower_body(body);
+
+        let end = self.next_addr();
+        for idx in std::mem::take(&mut self.returns) {
+            self.patch(idx, end);
+        }
+        for reg in SAVED_REGS.rev() {
+            self.emit(Instr::Pop(Operand::Reg(reg)));
+        }
+        self.program.instrs[alloc] = Instr::Alloc(self.num_locals);
+        self.emit(Instr::Dealloc(self.num_locals));
+        self.emit(Instr::Pop(Operand::Reg(R_BP)));
+        self.emit(Instr::Pop(Operand::Reg(R_LP)));
+        self.emit(Instr::Ret);
+
+        self.proc_name = None;
+        self.locals.clear();
+    }
+
+    /// xsMmSeq
+    fn lower_body(&mut self, body: &Body) {
+        for stmt in body {
+            self.lower_stmt(stmt);
+        }
+    }
+
+    fn lower_stmt(&mut self, (stmt, _span): &Spanned<AstNode>) {
+        match stmt {
+            AstNode::VarDef { is_static: true, type_, name: (name, _span), .. } => {
+                let proc_name = self.proc_name.as_ref().expect("Locals are only lowered in procedures");
+                let dst = Operand::Var(Self::static_name(proc_name, name));
+                self.locals.insert(name.clone(), (dst, type_.clone()));
+            }
+            // xsMmDef
+            AstNode::VarDef { type_, name: (name, _span), value, .. } => {
+                let src = self.lower_init(value.as_ref(), type_);
+                let dst = Operand::Var(name.0.clone());
+                self.locals.insert(name.clone(), (dst.clone(), type_.clone()));
+                self.num_locals += 1;
+                self.emit(Instr::Move { dst, src });
+            }
+            // xsMmAssign
+            AstNode::VarAssign { name: (name, _span), value } => {
+                let (dst, type_) = self.var(name);
+                let src = self.lower_expr_as(value, &type_);
+                self.emit(Instr::Move { dst, src });
+            }
+            // xsMmRet
+            AstNode::Return(value) => {
+                if let Some(expr) = value {
+                    let src = self.lower_expr_as(expr, &self.return_type.clone());
+                    self.emit(Instr::Move { dst: Operand::Reg(R_RET), src });
+                }
+                let idx = self.emit(Instr::Goto(Target::Addr(0)));
+                self.returns.push(idx);
+            }
+            // xsMmIfElse
+            AstNode::IfElse { condition, consequent: (consequent, _span), alternate } => {
+                let (cond, _type) = self.lower_expr(condition);
+                let br = self.emit(Instr::IfNot { cond, target: Target::Addr(0) });
+                self.lower_body(consequent);
+                match alternate {
+                    Some((alternate, _span)) => {
+                        let end_then = self.emit(Instr::Goto(Target::Addr(0)));
+                        self.patch(br, self.next_addr());
+                        self.lower_body(alternate);
+                        self.patch(end_then, self.next_addr());
+                    }
+                    None => self.patch(br, self.next_addr()),
+                }
+            }
+            // xsMmWhile
+            AstNode::While { condition, body: (body, _span) } => {
+                let eval = self.next_addr();
+                let (cond, _type) = self.lower_expr(condition);
+                let br = self.emit(Instr::IfNot { cond, target: Target::Addr(0) });
+                let loop_ = self.lower_loop_body(body, |lowerer| lowerer.emit(Instr::Goto(Target::Addr(eval))));
+                self.close_loop(loop_, br);
+            }
+            // xsMmFor
+            AstNode::For { var, condition, body: (body, _span) } => {
+                let (AstNode::VarAssign { name: (name, _name_span), value }, _span) = var.as_ref() else {
+                    unreachable!("The loop variable of a for is always assigned");
+                };
+                let is_new = !self.locals.contains_key(name) && !self.type_env.identifiers.contains_key(name);
+                if is_new {
+                    self.locals.insert(name.clone(), (Operand::Var(name.0.clone()), Type::Int));
+                    self.num_locals += 1;
+                }
+                let (var, type_) = self.var(name);
+                let src = self.lower_expr_as(value, &type_);
+                self.emit(Instr::Move { dst: var.clone(), src });
+
+                let (op, step, bound) = match &condition.0 {
+                    Expr::Lt(_var, bound) => (BinOp::Lt, BinOp::Add, bound),
+                    Expr::Le(_var, bound) => (BinOp::Le, BinOp::Add, bound),
+                    Expr::Gt(_var, bound) => (BinOp::Gt, BinOp::Sub, bound),
+                    Expr::Ge(_var, bound) => (BinOp::Ge, BinOp::Sub, bound),
+                    _ => unreachable!("The condition of a for is always a comparison"),
+                };
+                let eval = self.next_addr();
+                let bound = self.lower_expr_as(bound, &type_);
+                let cmp = self.new_tmp();
+                self.emit(Instr::BinOp { dst: cmp.clone(), op, type_: OpType::of(&type_), lhs: var.clone(), rhs: bound });
+                let br = self.emit(Instr::IfNot { cond: cmp, target: Target::Addr(0) });
+
+                let loop_ = self.lower_loop_body(body, |lowerer| {
+                    lowerer.emit(Instr::BinOp {
+                        dst: var.clone(),
+                        op: step,
+                        type_: OpType::Int,
+                        lhs: var.clone(),
+                        rhs: Operand::Const(Literal::Int(1)),
+                    });
+                    lowerer.emit(Instr::Goto(Target::Addr(eval)))
+                });
+                // a continue steps the loop variable before the condition is checked again
+                self.close_loop(loop_, br);
+
+                if is_new {
+                    self.locals.remove(name);
+                }
+            }
+            // xsMmSwitch. Cases are compared as ints
+            AstNode::Switch { clause, cases } => {
+                let clause = self.lower_expr_as(clause, &Type::Int);
+                let mut brs = vec![];
+                for (expr, _body) in cases {
+                    let Some(expr) = expr else {
+                        continue;
+                    };
+                    let value = self.lower_expr_as(expr, &Type::Int);
+                    let cmp = self.new_tmp();
+                    self.emit(Instr::BinOp {
+                        dst: cmp.clone(),
+                        op: BinOp::Ne,
+                        type_: OpType::Int,
+                        lhs: clause.clone(),
+                        rhs: value,
+                    });
+                    brs.push(self.emit(Instr::IfNot { cond: cmp, target: Target::Addr(0) }));
+                }
+                let br_default = self.emit(Instr::Goto(Target::Addr(0)));
+
+                self.breakables.push(Breakable { breaks: vec![], continues: None });
+                let mut brs = brs.into_iter();
+                let mut default = None;
+                for (expr, (body, _span)) in cases {
+                    if expr.is_none() {
+                        default = Some(body);
+                        continue;
+                    }
+                    let br = brs.next().expect("One branch per case");
+                    self.patch(br, self.next_addr());
+                    self.lower_body(body);
+                    let end = self.emit(Instr::Goto(Target::Addr(0)));
+                    self.breakables.last_mut().expect("Pushed above").breaks.push(end);
+                }
+                self.patch(br_default, self.next_addr());
+                if let Some(body) = default {
+                    self.lower_body(body);
+                }
+                let switch = self.breakables.pop().expect("Pushed above");
+                for idx in switch.breaks {
+                    self.patch(idx, self.next_addr());
+                }
+            }
+            // xsMmPostfix
+            AstNode::PostDPlus((name, _span)) | AstNode::PostDMinus((name, _span)) => {
+                let op = match stmt {
+                    AstNode::PostDPlus(_) => BinOp::Add,
+                    _ => BinOp::Sub,
+                };
+                let (var, type_) = self.var(name);
+                let one = match type_ {
+                    Type::Float => Literal::Float(1.0),
+                    _ => Literal::Int(1),
+                };
+                self.emit(Instr::BinOp {
+                    dst: var.clone(),
+                    op,
+                    type_: OpType::of(&type_),
+                    lhs: var,
+                    rhs: Operand::Const(one),
+                });
+            }
+            // xsMmBr
+            AstNode::Break => {
+                let idx = self.emit(Instr::Goto(Target::Addr(0)));
+                if let Some(breakable) = self.breakables.last_mut() {
+                    breakable.breaks.push(idx);
+                }
+            }
+            // xsMmCo
+            AstNode::Continue => {
+                let idx = self.emit(Instr::Goto(Target::Addr(0)));
+                let loop_ = self.breakables.iter_mut()
+                    .rev()
+                    .find_map(|breakable| breakable.continues.as_mut());
+                if let Some(continues) = loop_ {
+                    continues.push(idx);
+                }
+            }
+            // xsMmLabel
+            AstNode::LabelDef((label, _span)) => {
+                self.emit(Instr::Label(label.clone()));
+            }
+            // xsMmGoto
+            AstNode::Goto((label, _span)) => {
+                self.emit(Instr::Goto(Target::Label(label.clone())));
+            }
+            AstNode::Discarded((expr @ Expr::FnCall { .. }, _span)) => {
+                self.lower_call(expr);
+            }
+            AstNode::Discarded(expr) => {
+                self.lower_expr(expr);
+            }
+            // xsMmBrPt, xsMmDbg and xsMmClsDef don't generate code
+            AstNode::Breakpoint | AstNode::Debug(_) | AstNode::Class { .. } => {}
+            AstNode::Error | AstNode::Include(_) | AstNode::FnDef { .. } | AstNode::RuleDef { .. } => {}
+        }
+    }
+
+    /// Lowers the body of a loop followed by the jumps back to its condition, which `lower_jump` emits. Returns
+    /// the index of the first instruction emitted by `lower_jump`, which is where a `continue` goes
+    fn lower_loop_body(&mut self, body: &Body, lower_jump: impl FnOnce(&mut Self) -> usize) -> usize {
+        self.breakables.push(Breakable { breaks: vec![], continues: Some(vec![]) });
+        self.lower_body(body);
+        let start = self.program.instrs.len();
+        lower_jump(self);
+        start
+    }
+
+    /// Points the `continue`s of the innermost loop at the instruction with the given index, and its `break`s and
+    /// the branch on its condition at the end of the loop
+    fn close_loop(&mut self, continue_idx: usize, br: usize) {
+        let loop_ = self.breakables.pop().expect("Pushed by lower_loop_body");
+        let end = self.next_addr();
+        for idx in loop_.continues.unwrap_or_default() {
+            self.patch(idx, continue_idx + 1);
+        }
+        for idx in loop_.breaks {
+            self.patch(idx, end);
+        }
+        self.patch(br, end);
+    }
+}
@@ -0,0 +1,5 @@
+mod flavour;
+mod project_config;
+
+pub use flavour::Flavour;
+pub use project_config::{ProjectConfig, Severity, CONFIG_FILENAME};
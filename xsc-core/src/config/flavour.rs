@@ -0,0 +1,27 @@
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Flavour {
+    #[default]
+    AoE2DE,
+}
+
+impl Flavour {
+    /// The built-in prelude declaring the functions and constants that this flavour of XS provides
+    pub fn prelude(&self) -> &'static str {
+        match self {
+            Flavour::AoE2DE => include_str!(r"../../prelude.xs"),
+        }
+    }
+}
+
+impl FromStr for Flavour {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name {
+            "AoE2:DE" => Ok(Flavour::AoE2DE),
+            _ => Err(format!("Unknown flavour '{name}'")),
+        }
+    }
+}
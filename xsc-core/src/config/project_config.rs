@@ -0,0 +1,143 @@
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+
+use crate::config::Flavour;
+use crate::r#static::info::WarningKind;
+
+pub const CONFIG_FILENAME: &str = "xsc.toml";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Error,
+    Warning,
+    Ignore,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+struct TomlConfig {
+    #[serde(default)]
+    include_dirs: Vec<PathBuf>,
+    #[serde(default)]
+    extra_preludes: Vec<PathBuf>,
+    #[serde(default)]
+    ignores: Vec<String>,
+    #[serde(default)]
+    severity: HashMap<String, Severity>,
+    flavour: Option<String>,
+    #[serde(default)]
+    entry_points: Vec<PathBuf>,
+}
+
+/// Settings from an `xsc.toml` file that apply to all XS files in its directory and subdirectories. All paths are
+/// relative to the directory containing the config file
+#[derive(Debug, Clone)]
+pub struct ProjectConfig {
+    pub path: PathBuf,
+    pub include_dirs: Vec<PathBuf>,
+    pub extra_preludes: Vec<PathBuf>,
+    pub ignores: HashSet<u32>,
+    /// warning code -> severity to report it with
+    pub severities: HashMap<u32, Severity>,
+    /// `None` when the file does not set one, so the flavour configured elsewhere is kept
+    pub flavour: Option<Flavour>,
+    pub entry_points: Vec<PathBuf>,
+}
+
+fn warning_code(name: &str) -> Result<u32, String> {
+    WarningKind::from_name(name)
+        .map(|kind| kind.as_u32())
+        .ok_or_else(|| format!("Unrecognised warning name '{name}'"))
+}
+
+impl ProjectConfig {
+    /// Finds the closest config file in the directory of the given path or any of its ancestors
+    pub fn find(path: &Path) -> Option<PathBuf> {
+        let start = if path.is_dir() { path } else { path.parent()? };
+
+        start.ancestors()
+            .map(|dir| dir.join(CONFIG_FILENAME))
+            .find(|config_path| config_path.is_file())
+    }
+
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let src = fs::read_to_string(path)
+            .map_err(|err| format!("Failed to read config '{}', details: {err}", path.display()))?;
+
+        Self::from_src(path, &src)
+    }
+
+    pub fn from_src(path: &Path, src: &str) -> Result<Self, String> {
+        let config: TomlConfig = toml::from_str(src)
+            .map_err(|err| format!("Invalid config '{}', details: {err}", path.display()))?;
+
+        let root = path.parent().unwrap_or(Path::new(""));
+        let resolve = |paths: Vec<PathBuf>| -> Vec<PathBuf> {
+            paths.into_iter()
+                .map(|rel_path| {
+                    let path = root.join(rel_path);
+                    dunce::canonicalize(&path).unwrap_or(path)
+                })
+                .collect()
+        };
+
+        let ignores = config.ignores.iter()
+            .map(|name| warning_code(name))
+            .collect::<Result<HashSet<_>, _>>()?;
+
+        let severities = config.severity.iter()
+            .map(|(name, severity)| Ok((warning_code(name)?, *severity)))
+            .collect::<Result<HashMap<_, _>, String>>()?;
+
+        let flavour = config.flavour
+            .map(|name| name.parse())
+            .transpose()?;
+
+        Ok(Self {
+            path: path.to_path_buf(),
+            include_dirs: resolve(config.include_dirs),
+            extra_preludes: resolve(config.extra_preludes),
+            ignores,
+            severities,
+            flavour,
+            entry_points: resolve(config.entry_points),
+        })
+    }
+
+    /// Codes of warnings that are ignored, either directly or through a severity override
+    pub fn ignored_codes(&self) -> HashSet<u32> {
+        self.severities.iter()
+            .filter(|(_code, severity)| **severity == Severity::Ignore)
+            .map(|(code, _severity)| *code)
+            .chain(self.ignores.iter().copied())
+            .collect()
+    }
+
+    /// Codes of warnings that are reported as errors through a severity override
+    pub fn promoted_codes(&self) -> HashSet<u32> {
+        self.severities.iter()
+            .filter(|(_code, severity)| **severity == Severity::Error)
+            .map(|(code, _severity)| *code)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn flavour_is_only_set_when_given() {
+        let path = Path::new("proj/xsc.toml");
+
+        let config = ProjectConfig::from_src(path, "ignores = [\"UnusedVar\"]\n").unwrap();
+        assert_eq!(config.flavour, None);
+
+        let config = ProjectConfig::from_src(path, "flavour = \"AoE2:DE\"\n").unwrap();
+        assert_eq!(config.flavour, Some(Flavour::AoE2DE));
+    }
+}
@@ -15,6 +15,7 @@ pub enum Doc {
         deprecated: Option<String>,
         nodiscard: bool,
         no_num_promo: bool,
+        is_test: bool,
     },
 }
 
@@ -30,6 +31,11 @@ impl Doc {
         }
     }
 
+    /// Functions tagged with `@test` are run by `xs-check test`
+    pub fn is_test(&self) -> bool {
+        matches!(self, Doc::FnDesc { is_test: true, .. })
+    }
+
     pub fn is_none(&self) -> bool {
         matches!(self, Doc::None)
     }
@@ -76,6 +82,7 @@ impl Doc {
         let mut idx = 0usize;
         let mut nodiscard = true;
         let mut no_num_promo = true;
+        let mut is_test = false;
 
         for line in content {
             if let Some(line) = line.strip_prefix("@param") {
@@ -103,6 +110,8 @@ impl Doc {
                 nodiscard = false;
             } else if line.starts_with("@allow_no_num_promo") {
                 no_num_promo = false;
+            } else if line.trim_end() == "@test" {
+                is_test = true;
             } else if line.starts_with('@') {
                 mode = Mode::Desc;
                 desc_lines.push(line);
@@ -136,8 +145,8 @@ impl Doc {
             Some(deprecated_lines.join("\n").trim().to_string())
         };
 
-        if !params.is_empty() || returns.is_some() || deprecated.is_some() || !nodiscard || !no_num_promo {
-            Ok(Doc::FnDesc { desc, params, returns, nodiscard, no_num_promo, deprecated })
+        if !params.is_empty() || returns.is_some() || deprecated.is_some() || !nodiscard || !no_num_promo || is_test {
+            Ok(Doc::FnDesc { desc, params, returns, nodiscard, no_num_promo, deprecated, is_test })
         } else {
             Ok(Doc::Desc(desc))
         }
@@ -149,12 +158,13 @@ impl Doc {
                 let Some(init) = &info.init else {
                     break 'sign format!("```xs\n{} {}\n```", info.type_, id.0);
                 };
-                format!(
-                    "```xs\nconst {} {} = {}\n```",
-                    info.type_,
-                    id.0,
-                    init.lit_str().expect("Non-literal value found in const init")
-                )
+                let init = init.lit_str().expect("Non-literal value found in const init");
+                // consts initialised with other consts also show the value they end up with
+                let value = match &info.value {
+                    Some(value) if value.lit_str() != init => format!(" // {}", value.lit_str()),
+                    _ => String::new(),
+                };
+                format!("```xs\nconst {} {} = {}{}\n```", info.type_, id.0, init, value)
             }
             Type::Rule => {
                 let opts = info.modifiers.get_rule_opts().expect("Rule missing opts");
@@ -0,0 +1,91 @@
+mod expression;
+mod printer;
+mod statement;
+
+use crate::parsing::span::Span;
+use crate::r#static::info::{parse_src, ParseError};
+use printer::Printer;
+
+#[derive(Debug, Clone)]
+pub struct FormatOptions {
+    /// The string used for one level of indentation
+    pub indent: String,
+}
+
+impl Default for FormatOptions {
+    fn default() -> Self {
+        FormatOptions::new(4, true)
+    }
+}
+
+impl FormatOptions {
+    pub fn new(tab_size: usize, insert_spaces: bool) -> Self {
+        FormatOptions {
+            indent: if insert_spaces { " ".repeat(tab_size) } else { "\t".into() },
+        }
+    }
+}
+
+/// Formats an entire XS file. Files with syntax errors are not formatted
+pub fn format_src(src: &str, options: &FormatOptions) -> Result<String, Vec<ParseError>> {
+    let ((ast, comments), errs) = parse_src(src);
+    if !errs.is_empty() {
+        return Err(errs);
+    }
+
+    let mut printer = Printer::new(src, &comments, &options.indent, 0);
+    printer.stmts(&ast);
+    Ok(printer.finish(src.len()))
+}
+
+/// Formats the top level statements that overlap with the given range. Returns the span of the source that
+/// needs to be replaced along with its formatted text, or None if the range contains no statements
+pub fn format_range(
+    src: &str,
+    range: Span,
+    options: &FormatOptions,
+) -> Result<Option<(Span, String)>, Vec<ParseError>> {
+    let ((ast, comments), errs) = parse_src(src);
+    if !errs.is_empty() {
+        return Err(errs);
+    }
+
+    let stmts = ast.iter()
+        .skip_while(|(_stmt, span)| span.end <= range.start)
+        .take_while(|(_stmt, span)| span.start < range.end.max(range.start + 1))
+        .cloned()
+        .collect::<Vec<_>>();
+
+    let (Some((_first, first)), Some((_last, last))) = (stmts.first(), stmts.last()) else {
+        return Ok(None);
+    };
+
+    // any indentation before the first statement is replaced too
+    let mut start = src[..first.start]
+        .rfind('\n')
+        .map_or(0, |idx| idx + 1);
+    if !src[start..first.start].trim().is_empty() {
+        start = first.start;
+    }
+
+    // so are comments trailing the last statement on the same line
+    let line_end = src[last.end..]
+        .find('\n')
+        .map_or(src.len(), |idx| last.end + idx);
+    let end = comments.iter()
+        .filter(|(_cmt, span)| last.end <= span.start && span.start < line_end)
+        .map(|(_cmt, span)| span.end)
+        .max()
+        .unwrap_or(last.end);
+
+    let comments = comments.into_iter()
+        .filter(|(_cmt, span)| start <= span.start && span.end <= end)
+        .collect::<Vec<_>>();
+
+    let mut printer = Printer::new(src, &comments, &options.indent, start);
+    printer.stmts(&stmts);
+    let mut text = printer.finish(end);
+    text.pop();
+
+    Ok(Some(((start..end).into(), text)))
+}
@@ -0,0 +1,120 @@
+use crate::formatter::printer::Printer;
+use crate::parsing::ast::Expr;
+use crate::parsing::span::Spanned;
+
+/// The text of an expression whose parts have comments in between them
+struct Parts {
+    text: String,
+    /// Where the line is continued after a line comment
+    continuation: String,
+    /// Whether the next token is separated from the previous one by a space
+    space: bool,
+    /// Set after a line comment, so the next token goes on a new line
+    line_broken: bool,
+}
+
+impl Parts {
+    fn push(&mut self, token: &str) {
+        if self.line_broken {
+            self.text.push_str(&self.continuation);
+        } else if self.space {
+            self.text.push(' ');
+        }
+        self.text.push_str(token);
+        self.line_broken = false;
+    }
+
+    fn comment(&mut self, comment: &str) {
+        self.space = !self.text.is_empty();
+        self.push(comment);
+        self.space = true;
+        self.line_broken = comment.starts_with("//");
+    }
+
+    fn sep(&mut self, sep: &str) {
+        self.space = sep.starts_with(' ');
+        self.push(sep.trim());
+        self.space = sep.ends_with(' ');
+    }
+}
+
+impl Printer<'_> {
+    /// Prints the parts of an expression separated by `sep`. The comments between `start` and `end` are kept
+    /// after the part they follow, before its separator unless they are line comments
+    fn parts(&self, start: usize, parts: &[&Spanned<Expr>], sep: &str, end: usize) -> String {
+        let mut out = Parts {
+            text: String::new(),
+            continuation: self.continuation(1),
+            space: false,
+            line_broken: false,
+        };
+
+        let first = parts.first().map_or(end, |(_expr, span)| span.start);
+        for comment in self.attach_comments(start, first) {
+            out.comment(comment);
+        }
+        for (idx, part) in parts.iter().enumerate() {
+            out.push(&self.expr(part));
+
+            let next = parts.get(idx + 1);
+            let mut sep_done = next.is_none();
+            for comment in self.attach_comments(part.1.end, next.map_or(end, |(_expr, span)| span.start)) {
+                if !sep_done && comment.starts_with("//") {
+                    out.sep(sep);
+                    sep_done = true;
+                }
+                out.comment(comment);
+            }
+            if !sep_done {
+                out.sep(sep);
+            }
+        }
+
+        if out.line_broken {
+            out.text.push_str(&self.continuation(0));
+        }
+        out.text
+    }
+
+
+    pub fn expr(&self, (expr, span): &Spanned<Expr>) -> String {
+        let bin_op = |op: &str, expr1: &Spanned<Expr>, expr2: &Spanned<Expr>| {
+            self.parts(expr1.1.start, &[expr1, expr2], &format!(" {op} "), expr2.1.end)
+        };
+
+        match expr {
+            // literals are printed as written, floats in particular would lose their formatting otherwise
+            Expr::Literal(_) => self.slice(span).to_string(),
+            Expr::Identifier(id) => id.0.clone(),
+            Expr::Paren(expr) => format!("({})", self.parts(span.start + 1, &[expr], "", span.end - 1)),
+            Expr::Vec { x, y, z } => {
+                format!("vector({})", self.parts(span.start, &[x, y, z], ", ", span.end - 1))
+            }
+            Expr::FnCall { name: (name, name_span), args } => {
+                let args = args.iter().collect::<Vec<_>>();
+                format!("{name}({})", self.parts(name_span.end, &args, ", ", span.end - 1))
+            }
+
+            Expr::Neg(expr) => format!("-{}", self.expr(expr)),
+            Expr::Not(expr) => format!("!{}", self.expr(expr)),
+
+            Expr::Star(expr1, expr2) => bin_op("*", expr1, expr2),
+            Expr::FSlash(expr1, expr2) => bin_op("/", expr1, expr2),
+            Expr::PCent(expr1, expr2) => bin_op("%", expr1, expr2),
+
+            Expr::Plus(expr1, expr2) => bin_op("+", expr1, expr2),
+            Expr::Minus(expr1, expr2) => bin_op("-", expr1, expr2),
+
+            Expr::Lt(expr1, expr2) => bin_op("<", expr1, expr2),
+            Expr::Gt(expr1, expr2) => bin_op(">", expr1, expr2),
+            Expr::Le(expr1, expr2) => bin_op("<=", expr1, expr2),
+            Expr::Ge(expr1, expr2) => bin_op(">=", expr1, expr2),
+
+            Expr::Eq(expr1, expr2) => bin_op("==", expr1, expr2),
+            Expr::Ne(expr1, expr2) => bin_op("!=", expr1, expr2),
+
+            Expr::And(expr1, expr2) => bin_op("&&", expr1, expr2),
+            Expr::Or(expr1, expr2) => bin_op("||", expr1, expr2),
+        }
+    }
+}
@@ -0,0 +1,181 @@
+use std::cell::Cell;
+
+use crate::parsing::span::{Span, Spanned};
+
+pub struct Printer<'src> {
+    pub(super) src: &'src str,
+    comments: &'src [Spanned<String>],
+    next_comment: usize,
+    /// Comments that were printed inside an expression, next to the part they follow
+    attached: Vec<Cell<bool>>,
+    indent: &'src str,
+    pub(super) level: usize,
+    out: String,
+    /// The source position up to which everything has been printed
+    pub(super) last_end: usize,
+    /// Blank lines are not kept right after an opening or before a closing brace
+    no_blank: bool,
+    /// Set when the current line ends in a line comment, so nothing more can be put on it
+    line_comment: bool,
+}
+
+impl<'src> Printer<'src> {
+    pub fn new(src: &'src str, comments: &'src [Spanned<String>], indent: &'src str, start: usize) -> Self {
+        Printer {
+            src,
+            comments,
+            next_comment: 0,
+            attached: comments.iter().map(|_cmt| Cell::new(false)).collect(),
+            indent,
+            level: 0,
+            out: String::new(),
+            last_end: start,
+            no_blank: true,
+            line_comment: false,
+        }
+    }
+
+    pub fn slice(&self, span: &Span) -> &'src str {
+        &self.src[span.start..span.end]
+    }
+
+    pub fn push(&mut self, text: &str) {
+        self.out.push_str(text);
+    }
+
+    /// Starts a new line for code that begins at the given source position, printing the comments that come
+    /// before it first
+    pub fn line(&mut self, pos: usize) {
+        self.flush_comments(pos);
+        self.new_line(pos);
+    }
+
+    /// Starts a new line without keeping a blank line before it
+    pub fn tight_line(&mut self, pos: usize) {
+        self.flush_comments(pos);
+        self.no_blank = true;
+        self.new_line(pos);
+    }
+
+    fn new_line(&mut self, pos: usize) {
+        if !self.out.is_empty() {
+            self.out.push('\n');
+            let gap = &self.src[self.last_end.min(pos)..pos];
+            if !self.no_blank && gap.matches('\n').count() > 1 {
+                self.out.push('\n');
+            }
+        }
+        for _ in 0..self.level {
+            self.out.push_str(self.indent);
+        }
+        self.no_blank = false;
+        self.line_comment = false;
+    }
+
+    /// Prints all comments that start before the given source position. Comments that were on the same line as
+    /// the preceding code are kept there, the others are put on their own lines
+    fn flush_comments(&mut self, pos: usize) {
+        while let Some((comment, span)) = self.comments.get(self.next_comment) {
+            if span.start >= pos {
+                break;
+            }
+            self.next_comment += 1;
+            if self.attached[self.next_comment - 1].get() {
+                continue;
+            }
+
+            let gap = &self.src[self.last_end.min(span.start)..span.start];
+            let same_line = span.start < self.last_end || !gap.contains('\n');
+            if same_line && !self.out.is_empty() && !self.line_comment {
+                // keeps trailing comments aligned if they were
+                if span.start > self.last_end && gap.chars().all(|c| c == ' ') {
+                    self.out.push_str(gap);
+                } else {
+                    self.out.push(' ');
+                }
+            } else {
+                self.new_line(span.start);
+            }
+            self.push_comment(comment);
+            self.line_comment = comment.starts_with("//");
+            self.last_end = self.last_end.max(span.end);
+        }
+    }
+
+    fn push_comment(&mut self, comment: &str) {
+        let mut lines = comment.lines();
+        self.out.push_str(lines.next().unwrap_or_default().trim_end());
+
+        // the continuation lines of doc comments are re-aligned with the indentation of the comment,
+        // other block comments are kept as they are
+        let rest = lines.collect::<Vec<_>>();
+        let is_doc = rest.iter().all(|line| line.trim_start().starts_with('*'));
+        for line in rest {
+            self.out.push('\n');
+            if is_doc {
+                for _ in 0..self.level {
+                    self.out.push_str(self.indent);
+                }
+                self.out.push(' ');
+                self.out.push_str(line.trim());
+            } else {
+                self.out.push_str(line.trim_end());
+            }
+        }
+    }
+
+    /// Opens a block, `pos` is the source position after its opening brace
+    pub fn open_block(&mut self, pos: usize) {
+        self.push("{");
+        self.level += 1;
+        self.last_end = pos;
+        self.no_blank = true;
+    }
+
+    /// Closes a block, `brace` is the source position of its closing brace
+    pub fn close_block(&mut self, brace: usize) {
+        self.flush_comments(brace);
+        self.level -= 1;
+        self.no_blank = true;
+        self.new_line(brace);
+        self.push("}");
+        self.last_end = brace + 1;
+    }
+
+    /// Whether there are any comments left before the given source position
+    pub fn has_comments_before(&self, pos: usize) -> bool {
+        self.comments[self.next_comment..].iter()
+            .zip(&self.attached[self.next_comment..])
+            .take_while(|((_cmt, span), _attached)| span.start < pos)
+            .any(|(_cmt, attached)| !attached.get())
+    }
+
+    /// The comments that start between the given source positions, which are then printed by the expression
+    /// they are in instead of after its statement
+    pub fn attach_comments(&self, start: usize, end: usize) -> Vec<&'src str> {
+        let first = self.comments.partition_point(|(_cmt, span)| span.start < start);
+        let last = self.comments.partition_point(|(_cmt, span)| span.start < end);
+        (first..last.max(first))
+            .filter(|&idx| !self.attached[idx].replace(true))
+            .map(|idx| self.comments[idx].0.as_str())
+            .collect()
+    }
+
+    /// The start of a new line that continues the current statement, indented `extra_level` levels further
+    pub fn continuation(&self, extra_level: usize) -> String {
+        let mut line = String::from("\n");
+        for _ in 0..self.level + extra_level {
+            line.push_str(self.indent);
+        }
+        line
+    }
+
+    /// Prints the remaining comments before `end` and returns the formatted text
+    pub fn finish(mut self, end: usize) -> String {
+        self.flush_comments(end);
+        if !self.out.is_empty() {
+            self.out.push('\n');
+        }
+        self.out
+    }
+}
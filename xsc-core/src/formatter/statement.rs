@@ -0,0 +1,225 @@
+use crate::formatter::printer::Printer;
+use crate::parsing::ast::{AstNode, Body, Expr, RuleOpt};
+use crate::parsing::span::Spanned;
+
+const MODIFIERS: [&str; 4] = ["export", "extern", "const", "static"];
+
+/// Rule options are always printed in this order
+fn rule_opt_order(opt: &RuleOpt) -> u8 {
+    match opt {
+        RuleOpt::Active | RuleOpt::Inactive => 0,
+        RuleOpt::RunImmediately => 1,
+        RuleOpt::HighFrequency => 2,
+        RuleOpt::MinInterval(_) => 3,
+        RuleOpt::MaxInterval(_) => 4,
+        RuleOpt::Priority(_) => 5,
+        RuleOpt::Group(_) => 6,
+    }
+}
+
+/// A body is a block if it has braces, or a single statement otherwise
+fn is_block((stmts, span): &Spanned<Body>) -> bool {
+    stmts.len() != 1 || stmts[0].1 != *span
+}
+
+impl Printer<'_> {
+    pub fn stmts(&mut self, stmts: &[Spanned<AstNode>]) {
+        for stmt in stmts {
+            self.stmt(stmt);
+        }
+    }
+
+    fn stmt(&mut self, stmt: &Spanned<AstNode>) {
+        self.line(stmt.1.start);
+        self.node(stmt);
+    }
+
+    fn node(&mut self, (node, span): &Spanned<AstNode>) {
+        match node {
+            // only formatted without syntax errors, so this is printed as is
+            AstNode::Error => {
+                self.push(self.slice(span));
+            }
+            AstNode::Include((_path, path_span)) => {
+                self.push(&format!("include {};", self.slice(path_span)));
+            }
+            AstNode::VarDef { type_, name: (name, name_span), value, .. } => {
+                // modifiers are kept in the order they were written in
+                let mods = self.src[span.start..name_span.start]
+                    .split_whitespace()
+                    .filter(|word| MODIFIERS.contains(word));
+                for modifier in mods {
+                    self.push(&format!("{modifier} "));
+                }
+                self.push(&format!("{type_} {name}"));
+                if let Some(value) = value {
+                    self.push(&format!(" = {}", self.expr(value)));
+                }
+                self.push(";");
+            }
+            AstNode::VarAssign { name: (name, _span), value } => {
+                self.push(&format!("{name} = {};", self.expr(value)));
+            }
+            AstNode::RuleDef { name: (name, _span), rule_opts, body } => {
+                self.push(&format!("rule {name}"));
+
+                let mut rule_opts = rule_opts.iter().collect::<Vec<_>>();
+                rule_opts.sort_by_key(|(opt, _span)| rule_opt_order(opt));
+
+                self.level += 1;
+                for (opt, opt_span) in rule_opts {
+                    self.tight_line(opt_span.start);
+                    self.push(&opt.render());
+                    self.last_end = self.last_end.max(opt_span.end);
+                }
+                self.level -= 1;
+
+                if is_block(body) {
+                    self.tight_line(body.1.start);
+                    self.block(body);
+                } else {
+                    self.body(body);
+                }
+            }
+            AstNode::FnDef { is_mutable, return_type, name: (name, _span), params, body } => {
+                let params = params.iter()
+                    .map(|param| format!("{} {} = {}", param.type_, param.name.0, self.expr(&param.default)))
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                let mutable = if *is_mutable { "mutable " } else { "" };
+                self.push(&format!("{mutable}{return_type} {name}({params})"));
+                self.body(body);
+            }
+            AstNode::Return(value) => {
+                match value {
+                    None => self.push("return;"),
+                    Some(value) => self.push(&format!("return {};", self.expr(value))),
+                }
+            }
+            AstNode::IfElse { condition, consequent, alternate } => {
+                self.push(&format!("if ({})", self.expr(condition)));
+                let braced = self.body(consequent);
+
+                let Some(alternate) = alternate else {
+                    return;
+                };
+                if braced {
+                    self.push(" else");
+                } else {
+                    self.tight_line(alternate.1.start);
+                    self.push("else");
+                }
+                match &alternate.0[..] {
+                    [else_if @ (AstNode::IfElse { .. }, _)] if !is_block(alternate) => {
+                        self.push(" ");
+                        self.node(else_if);
+                    }
+                    _ => {
+                        self.body(alternate);
+                    }
+                }
+            }
+            AstNode::While { condition, body } => {
+                self.push(&format!("while ({})", self.expr(condition)));
+                self.body(body);
+            }
+            AstNode::For { var, condition, body } => {
+                let (AstNode::VarAssign { name: (name, _name_span), value }, _span) = &**var else {
+                    unreachable!("The variable of a for loop is always an assignment");
+                };
+                let (op, bound) = match &condition.0 {
+                    Expr::Lt(_var, bound) => ("<", bound),
+                    Expr::Le(_var, bound) => ("<=", bound),
+                    Expr::Gt(_var, bound) => (">", bound),
+                    Expr::Ge(_var, bound) => (">=", bound),
+                    _ => unreachable!("The condition of a for loop is always a comparison"),
+                };
+                self.push(&format!("for ({name} = {}; {op} {})", self.expr(value), self.expr(bound)));
+                self.body(body);
+            }
+            AstNode::Switch { clause, cases } => {
+                self.push(&format!("switch ({}) ", self.expr(clause)));
+                self.open_block(clause.1.end);
+                for (expr, body) in cases {
+                    match expr {
+                        Some(expr) => {
+                            self.line(expr.1.start);
+                            self.push(&format!("case {}:", self.expr(expr)));
+                        }
+                        None => {
+                            self.line(body.1.start);
+                            self.push("default:");
+                        }
+                    }
+                    self.body(body);
+                }
+                self.close_block(span.end - 1);
+            }
+            AstNode::PostDPlus((name, _span)) => {
+                self.push(&format!("{name}++;"));
+            }
+            AstNode::PostDMinus((name, _span)) => {
+                self.push(&format!("{name}--;"));
+            }
+            AstNode::Break => {
+                self.push("break;");
+            }
+            AstNode::Continue => {
+                self.push("continue;");
+            }
+            AstNode::LabelDef((name, _span)) => {
+                self.push(&format!("label {name};"));
+            }
+            AstNode::Goto((name, _span)) => {
+                self.push(&format!("goto {name};"));
+            }
+            AstNode::Discarded(expr) => {
+                self.push(&format!("{};", self.expr(expr)));
+            }
+            AstNode::Debug((name, _span)) => {
+                self.push(&format!("dbg {name};"));
+            }
+            AstNode::Breakpoint => {
+                self.push("breakpoint;");
+            }
+            AstNode::Class { name: (name, name_span), member_vars } => {
+                let brace = self.src[..span.end].rfind('}').expect("Classes end with a brace");
+                self.push(&format!("class {name} "));
+                self.open_block(name_span.end);
+                self.stmts(member_vars);
+                self.close_block(brace);
+                self.push(";");
+            }
+        }
+        self.last_end = self.last_end.max(span.end);
+    }
+
+    /// Prints the body of a statement. Blocks are put on the same line as the statement, single statements
+    /// on their own indented line. Returns whether the body was a block
+    fn body(&mut self, body: &Spanned<Body>) -> bool {
+        if is_block(body) {
+            self.push(" ");
+            self.block(body);
+            return true;
+        }
+        let stmt = &body.0[0];
+        self.level += 1;
+        self.tight_line(stmt.1.start);
+        self.node(stmt);
+        self.level -= 1;
+        false
+    }
+
+    fn block(&mut self, (stmts, span): &Spanned<Body>) {
+        let brace = span.end - 1;
+        if stmts.is_empty() && !self.has_comments_before(brace) {
+            self.push("{}");
+            self.last_end = span.end;
+            return;
+        }
+        self.open_block(span.start + 1);
+        self.stmts(stmts);
+        self.close_block(brace);
+    }
+}
+
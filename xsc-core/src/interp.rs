@@ -0,0 +1,474 @@
+mod expression;
+mod host;
+mod natives;
+mod ops;
+mod runtime_error;
+mod scheduler;
+mod statement;
+mod value;
+
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+use crate::parsing::ast::{AstNode, Body, Identifier, RuleOpt, Type};
+use crate::parsing::span::{Span, Spanned};
+use crate::r#static::info::{AstCache, SrcLoc};
+
+pub use host::{NativeCall, NativeHost};
+pub use natives::Natives;
+pub(crate) use ops::{arith, compare, ArithOp, CmpOp};
+pub use runtime_error::RuntimeError;
+pub use scheduler::{Event, EventKind, Rule, RuleNatives, Scheduler, DEFAULT_PRIORITY};
+pub use value::Value;
+
+/// XS crashes long before this, it only keeps deep recursion from overflowing the interpreter's own stack
+const MAX_CALL_DEPTH: usize = 400;
+
+struct Function {
+    name: Identifier,
+    path: PathBuf,
+    return_type: Type,
+    /// name, type and default value of each parameter
+    params: Vec<(Identifier, Type, Value)>,
+    body: Body,
+    /// functions declared in a prelude are run by the native bindings
+    is_native: bool,
+}
+
+struct Frame {
+    path: PathBuf,
+    locals: HashMap<Identifier, Value>,
+    /// static locals defined so far in this call, along with the position of their definition. Their values are
+    /// written back when the call returns
+    statics: Vec<(Identifier, usize)>,
+}
+
+impl Frame {
+    fn new(path: &Path) -> Self {
+        Self { path: path.to_path_buf(), locals: HashMap::new(), statics: vec![] }
+    }
+}
+
+/// A tree walking interpreter for type checked XS programs. It follows the operational semantics in
+/// `maths/docs/dynamic/xs_op_sem.md`, along with XS' quirks: arithmetic yields the type of its first operand,
+/// floats are truncated when stored in ints, and loops and recursion are bounded by `infiniteLoopLimit` and
+/// `infiniteRecursionLimit`
+pub struct Interpreter<N: Natives> {
+    natives: N,
+    globals: HashMap<Identifier, Value>,
+    fns: HashMap<Identifier, Rc<Function>>,
+    /// values of static locals by the file and position of their definition
+    statics: HashMap<(PathBuf, usize), Value>,
+    loaded: HashSet<PathBuf>,
+    frames: Vec<Frame>,
+    /// rules in the order they are defined in
+    rules: Vec<Rule>,
+    /// the number of loop iterations and gotos a call may take before it is stopped, 0 for no limit
+    max_iterations: u64,
+    iterations: u64,
+}
+
+impl<N: Natives> Interpreter<N> {
+    pub fn new(natives: N) -> Self {
+        Self {
+            natives,
+            globals: HashMap::new(),
+            fns: HashMap::new(),
+            statics: HashMap::new(),
+            loaded: HashSet::new(),
+            frames: vec![],
+            rules: vec![],
+            max_iterations: 0,
+            iterations: 0,
+        }
+    }
+
+    /// Stops every call after `max` loop iterations and gotos in total, so code that never finishes reports an
+    /// error instead of hanging. 0 means no limit, which is the default
+    pub fn with_max_iterations(mut self, max: u64) -> Self {
+        self.max_iterations = max;
+        self
+    }
+
+    pub fn natives(&self) -> &N {
+        &self.natives
+    }
+
+    pub fn natives_mut(&mut self) -> &mut N {
+        &mut self.natives
+    }
+
+    pub fn global(&self, name: &str) -> Option<&Value> {
+        self.globals.get(&Identifier::new(name))
+    }
+
+    pub fn has_fn(&self, name: &str) -> bool {
+        self.fns.contains_key(&Identifier::new(name))
+    }
+
+    pub fn rules(&self) -> &[Rule] {
+        &self.rules
+    }
+
+    /// Loads the definitions of a prelude. Its functions are bound to the natives
+    pub fn load_prelude(&mut self, path: &PathBuf, ast_cache: &AstCache) -> Result<(), RuntimeError> {
+        self.load_file(path, ast_cache, &[], true)
+    }
+
+    /// Loads the definitions of a file and the files it includes, and initialises its top level variables. The
+    /// files must already be parsed into the cache, as the type checker does
+    pub fn load(
+        &mut self,
+        path: &PathBuf,
+        ast_cache: &AstCache,
+        include_dirs: &[PathBuf],
+    ) -> Result<(), RuntimeError> {
+        self.load_file(path, ast_cache, include_dirs, false)
+    }
+
+    fn load_file(
+        &mut self,
+        path: &PathBuf,
+        ast_cache: &AstCache,
+        include_dirs: &[PathBuf],
+        is_prelude: bool,
+    ) -> Result<(), RuntimeError> {
+        if !self.loaded.insert(path.clone()) {
+            return Ok(());
+        }
+        let Some(ast) = ast_cache.get(path).map(|info| info.1.0.clone()) else {
+            return Err(RuntimeError::no_loc(format!("File '{}' has not been parsed", path.display())));
+        };
+
+        self.frames.push(Frame::new(path));
+        let result = ast.into_iter()
+            .try_for_each(|stmt| self.load_stmt(path, stmt, ast_cache, include_dirs, is_prelude));
+        self.frames.pop();
+        result
+    }
+
+    fn load_stmt(
+        &mut self,
+        path: &PathBuf,
+        (stmt, span): Spanned<AstNode>,
+        ast_cache: &AstCache,
+        include_dirs: &[PathBuf],
+        is_prelude: bool,
+    ) -> Result<(), RuntimeError> {
+        match stmt {
+            AstNode::Include((filename, _span)) => {
+                let filename = &filename[1..(filename.len() - 1)];
+                let Some(inc_path) = include_dirs.iter()
+                    .map(|dir| dir.join(filename))
+                    .find(|inc_path| inc_path.is_file()) else {
+                    return Err(RuntimeError::new(
+                        format!("Failed to resolve included file {filename}"),
+                        SrcLoc::from(path, &span),
+                    ));
+                };
+                self.load_file(&inc_path, ast_cache, include_dirs, is_prelude)
+            }
+            AstNode::VarDef { type_, name: (name, _span), value, .. } => {
+                let value = match &value {
+                    Some(expr) => self.eval(expr)?.cast(&type_),
+                    None => Value::default_of(&type_),
+                };
+                self.globals.insert(name, value);
+                Ok(())
+            }
+            AstNode::FnDef { return_type, name: (name, _span), params, body: (body, _span2), .. } => {
+                let params = params.iter()
+                    .map(|param| {
+                        let value = self.eval(&param.default)?.cast(&param.type_);
+                        Ok((param.name.0.clone(), param.type_.clone(), value))
+                    }).collect::<Result<_, _>>()?;
+                self.fns.insert(name.clone(), Rc::new(Function {
+                    name,
+                    path: path.clone(),
+                    return_type,
+                    params,
+                    body,
+                    is_native: is_prelude,
+                }));
+                Ok(())
+            }
+            // running a rule is the same as calling a void function without parameters
+            AstNode::RuleDef { name: (name, name_span), rule_opts, body: (body, _span2) } => {
+                let mut rule = Rule {
+                    name: name.clone(),
+                    src_loc: SrcLoc::from(path, &name_span),
+                    is_active: true,
+                    run_immediately: false,
+                    high_frequency: false,
+                    min_interval: None,
+                    max_interval: None,
+                    priority: None,
+                    group: None,
+                };
+                for (opt, _span) in &rule_opts {
+                    match opt {
+                        RuleOpt::Active => rule.is_active = true,
+                        RuleOpt::Inactive => rule.is_active = false,
+                        RuleOpt::RunImmediately => rule.run_immediately = true,
+                        RuleOpt::HighFrequency => rule.high_frequency = true,
+                        RuleOpt::MinInterval(expr) => rule.min_interval = Some(self.eval(expr)?.as_int()),
+                        RuleOpt::MaxInterval(expr) => rule.max_interval = Some(self.eval(expr)?.as_int()),
+                        RuleOpt::Priority(expr) => rule.priority = Some(self.eval(expr)?.as_int()),
+                        RuleOpt::Group((group, _span)) => rule.group = Some(group.clone()),
+                    }
+                }
+                self.rules.push(rule);
+
+                self.fns.insert(name.clone(), Rc::new(Function {
+                    name,
+                    path: path.clone(),
+                    return_type: Type::Void,
+                    params: vec![],
+                    body,
+                    is_native: false,
+                }));
+                Ok(())
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Calls the given function or rule. Parameters that are not given take their default values
+    pub fn call(&mut self, name: &str, args: Vec<Value>) -> Result<Value, RuntimeError> {
+        let Some(fn_) = self.fns.get(&Identifier::new(name)).cloned() else {
+            return Err(RuntimeError::no_loc(format!("Function '{name}' is not defined")));
+        };
+        self.iterations = 0;
+        self.call_fn(&fn_, args, None)
+    }
+
+    fn call_fn(&mut self, fn_: &Function, args: Vec<Value>, call_loc: Option<SrcLoc>) -> Result<Value, RuntimeError> {
+        let err = |msg: String| RuntimeError { msg, src_loc: call_loc.clone() };
+
+        if args.len() > fn_.params.len() {
+            return Err(err(format!("Extra argument provided to function {}", fn_.name)));
+        }
+        let limit = self.global_int("infiniteRecursionLimit");
+        if limit >= 0 && self.frames.len() > limit as usize {
+            return Err(err(format!(
+                "Exceeded the infiniteRecursionLimit of {limit}. This crashes XS silently"
+            )));
+        }
+        if self.frames.len() >= MAX_CALL_DEPTH {
+            return Err(err(format!("Exceeded the maximum call depth of {MAX_CALL_DEPTH}")));
+        }
+
+        let mut args = args.into_iter();
+        let params = fn_.params.iter()
+            .map(|(name, type_, default)| {
+                let value = args.next().unwrap_or_else(|| default.clone()).cast(type_);
+                (name.clone(), value)
+            });
+
+        if fn_.is_native {
+            let args = params.map(|(_name, value)| value).collect::<Vec<_>>();
+            return match self.natives.call(&fn_.name.0, &args, &fn_.return_type) {
+                Some(Ok(value)) => Ok(value.cast(&fn_.return_type)),
+                Some(Err(msg)) => Err(err(msg)),
+                None => Err(err(format!("No native binding for the prelude function {}", fn_.name))),
+            };
+        }
+
+        let mut frame = Frame::new(&fn_.path);
+        frame.locals.extend(params);
+        self.frames.push(frame);
+        let flow = self.exec_body(&fn_.body);
+        let frame = self.frames.pop().expect("Pushed above");
+        for (name, pos) in frame.statics {
+            if let Some(value) = frame.locals.get(&name) {
+                self.statics.insert((frame.path.clone(), pos), value.clone());
+            }
+        }
+
+        match flow? {
+            statement::Flow::Return(value) => Ok(value.cast(&fn_.return_type)),
+            statement::Flow::Goto((label, span)) => Err(RuntimeError::new(
+                format!("Label {label} is not defined"),
+                SrcLoc::from(&fn_.path, &span),
+            )),
+            _ => Ok(Value::default_of(&fn_.return_type)),
+        }
+    }
+
+    fn frame(&mut self) -> &mut Frame {
+        self.frames.last_mut().expect("Code only runs inside a frame")
+    }
+
+    fn err(&self, msg: impl Into<String>, span: &Span) -> RuntimeError {
+        let path = &self.frames.last().expect("Code only runs inside a frame").path;
+        RuntimeError::new(msg, SrcLoc::from(path, span))
+    }
+
+    fn get_var(&self, name: &Identifier) -> Option<&Value> {
+        self.frames.last()
+            .and_then(|frame| frame.locals.get(name))
+            .or_else(|| self.globals.get(name))
+    }
+
+    /// Assigns to an existing variable, converting the value to the variable's type. Returns false if there is
+    /// no such variable
+    fn set_var(&mut self, name: &Identifier, value: Value) -> bool {
+        let slot = match self.frames.last_mut().and_then(|frame| frame.locals.get_mut(name)) {
+            Some(slot) => slot,
+            None => match self.globals.get_mut(name) {
+                Some(slot) => slot,
+                None => return false,
+            },
+        };
+        *slot = value.cast(&slot.type_());
+        true
+    }
+
+    fn global_int(&self, name: &str) -> i32 {
+        self.global(name).map_or(-1, Value::as_int)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::interp;
+
+    #[test]
+    fn floats_are_truncated_in_ints() {
+        let mut interp = interp("\
+int f(int p = 0) {
+    int x = 3.9;
+    int y = -3.9;
+    return (x * 10 + y + p);
+}
+");
+        assert_eq!(interp.call("f", vec![Value::Float(2.7)]), Ok(Value::Int(29)));
+    }
+
+    #[test]
+    fn first_operand_decides_the_type() {
+        let mut interp = interp("\
+int f() {
+    return (5 * 2.5);
+}
+float g() {
+    return (2.5 * 5);
+}
+float h() {
+    return (7 / 2 + 0.5);
+}
+");
+        assert_eq!(interp.call("f", vec![]), Ok(Value::Int(10)));
+        assert_eq!(interp.call("g", vec![]), Ok(Value::Float(12.5)));
+        assert_eq!(interp.call("h", vec![]), Ok(Value::Float(3.0)));
+    }
+
+    #[test]
+    fn vector_arithmetic() {
+        let mut interp = interp("\
+vector f() {
+    vector v = vector(1, 2, 3) + vector(1, 1, 1);
+    v = v * 2;
+    v = v - vector(0, 0, 8);
+    return (v / 2);
+}
+");
+        assert_eq!(interp.call("f", vec![]), Ok(Value::Vec(2.0, 3.0, 0.0)));
+    }
+
+    #[test]
+    fn switch_runs_one_case_without_fall_through() {
+        let mut interp = interp("\
+int f(int x = 0) {
+    int r = 0;
+    switch (x) {
+        case 1: {
+            r = r + 1;
+        }
+        case 2: {
+            r = r + 10;
+            break;
+        }
+        default: {
+            r = r + 100;
+        }
+    }
+    return (r);
+}
+");
+        assert_eq!(interp.call("f", vec![Value::Int(1)]), Ok(Value::Int(1)));
+        assert_eq!(interp.call("f", vec![Value::Int(2)]), Ok(Value::Int(10)));
+        assert_eq!(interp.call("f", vec![Value::Int(3)]), Ok(Value::Int(100)));
+    }
+
+    #[test]
+    fn loops_run_one_extra_iteration_past_the_limit() {
+        let mut interp = interp("\
+int f(int n = 0) {
+    infiniteLoopLimit = 3;
+    int i = 0;
+    while (i < n) {
+        i++;
+    }
+    return (i);
+}
+");
+        assert_eq!(interp.call("f", vec![Value::Int(4)]), Ok(Value::Int(4)));
+        let err = interp.call("f", vec![Value::Int(5)]).unwrap_err();
+        assert_eq!(err.msg, "Exceeded the infiniteLoopLimit of 3");
+    }
+
+    #[test]
+    fn max_iterations_stop_loops_and_gotos_that_never_end() {
+        let mut interp = interp("\
+void spin() {
+    while (true) {
+    }
+}
+void jump() {
+    label start;
+    goto start;
+}
+int count(int n = 0) {
+    for (i = 0; < n) {
+    }
+    return (n);
+}
+").with_max_iterations(10);
+        let err = interp.call("spin", vec![]).unwrap_err();
+        assert_eq!(err.msg, "Stopped after 10 loop iterations, this may never finish");
+        assert!(interp.call("jump", vec![]).is_err());
+        // every call starts with a fresh budget
+        assert_eq!(interp.call("count", vec![Value::Int(10)]), Ok(Value::Int(10)));
+        assert_eq!(interp.call("count", vec![Value::Int(10)]), Ok(Value::Int(10)));
+    }
+
+    #[test]
+    fn statics_keep_their_value_between_calls() {
+        let mut interp = interp("\
+int f() {
+    static int count = 0;
+    count++;
+    return (count);
+}
+");
+        assert_eq!(interp.call("f", vec![]), Ok(Value::Int(1)));
+        assert_eq!(interp.call("f", vec![]), Ok(Value::Int(2)));
+    }
+
+    #[test]
+    fn goto_jumps_to_label() {
+        let mut interp = interp("\
+int f() {
+    int x = 0;
+    goto skip;
+    x = 1;
+    label skip;
+    return (x);
+}
+");
+        assert_eq!(interp.call("f", vec![]), Ok(Value::Int(0)));
+    }
+}
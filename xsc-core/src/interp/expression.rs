@@ -0,0 +1,72 @@
+use crate::interp::ops::{arith, compare, ArithOp, CmpOp};
+use crate::interp::{Interpreter, Natives, RuntimeError, Value};
+use crate::parsing::ast::Expr;
+use crate::parsing::span::Spanned;
+use crate::r#static::info::SrcLoc;
+
+impl<N: Natives> Interpreter<N> {
+    /// Both operands of every operator are always evaluated, XS does not short circuit `&&` and `||`
+    pub(super) fn eval(&mut self, (expr, span): &Spanned<Expr>) -> Result<Value, RuntimeError> {
+        let arith_op = |op: ArithOp, expr1: &Spanned<Expr>, expr2: &Spanned<Expr>, interp: &mut Self| {
+            let (val1, val2) = (interp.eval(expr1)?, interp.eval(expr2)?);
+            arith(op, val1, val2).map_err(|msg| interp.err(msg, span))
+        };
+        let cmp_op = |op: CmpOp, expr1: &Spanned<Expr>, expr2: &Spanned<Expr>, interp: &mut Self| {
+            let (val1, val2) = (interp.eval(expr1)?, interp.eval(expr2)?);
+            compare(op, &val1, &val2)
+                .map(Value::Bool)
+                .map_err(|msg| interp.err(msg, span))
+        };
+
+        match expr {
+            Expr::Literal(lit) => Ok(Value::from_lit(lit)),
+            Expr::Identifier(name) => match self.get_var(name) {
+                Some(value) => Ok(value.clone()),
+                None => Err(self.err(format!("Name {name} is not defined"), span)),
+            },
+            Expr::Paren(expr) => self.eval(expr),
+            Expr::Vec { x, y, z } => {
+                let (x, y, z) = (self.eval(x)?, self.eval(y)?, self.eval(z)?);
+                Ok(Value::Vec(x.as_float(), y.as_float(), z.as_float()))
+            }
+            Expr::FnCall { name: (name, name_span), args } => {
+                let Some(fn_) = self.fns.get(name).cloned() else {
+                    return Err(self.err(format!("Function {name} is not defined"), name_span));
+                };
+                let args = args.iter()
+                    .map(|arg| self.eval(arg))
+                    .collect::<Result<Vec<_>, _>>()?;
+                let path = self.frames.last().expect("Code only runs inside a frame").path.clone();
+                self.call_fn(&fn_, args, Some(SrcLoc::from(&path, span)))
+            }
+            Expr::Neg(expr) => match self.eval(expr)? {
+                Value::Int(val) => Ok(Value::Int(val.wrapping_neg())),
+                Value::Float(val) => Ok(Value::Float(-val)),
+                value => Err(self.err(format!("Cannot negate a {}", value.type_()), span)),
+            },
+            Expr::Not(expr) => Ok(Value::Bool(!self.eval(expr)?.as_bool())),
+
+            Expr::Star(expr1, expr2) => arith_op(ArithOp::Mul, expr1, expr2, self),
+            Expr::FSlash(expr1, expr2) => arith_op(ArithOp::Div, expr1, expr2, self),
+            Expr::PCent(expr1, expr2) => arith_op(ArithOp::Mod, expr1, expr2, self),
+            Expr::Plus(expr1, expr2) => arith_op(ArithOp::Add, expr1, expr2, self),
+            Expr::Minus(expr1, expr2) => arith_op(ArithOp::Sub, expr1, expr2, self),
+
+            Expr::Lt(expr1, expr2) => cmp_op(CmpOp::Lt, expr1, expr2, self),
+            Expr::Gt(expr1, expr2) => cmp_op(CmpOp::Gt, expr1, expr2, self),
+            Expr::Le(expr1, expr2) => cmp_op(CmpOp::Le, expr1, expr2, self),
+            Expr::Ge(expr1, expr2) => cmp_op(CmpOp::Ge, expr1, expr2, self),
+            Expr::Eq(expr1, expr2) => cmp_op(CmpOp::Eq, expr1, expr2, self),
+            Expr::Ne(expr1, expr2) => cmp_op(CmpOp::Ne, expr1, expr2, self),
+
+            Expr::And(expr1, expr2) => {
+                let (val1, val2) = (self.eval(expr1)?, self.eval(expr2)?);
+                Ok(Value::Bool(val1.as_bool() && val2.as_bool()))
+            }
+            Expr::Or(expr1, expr2) => {
+                let (val1, val2) = (self.eval(expr1)?, self.eval(expr2)?);
+                Ok(Value::Bool(val1.as_bool() || val2.as_bool()))
+            }
+        }
+    }
+}
@@ -0,0 +1,149 @@
+mod array;
+mod maths;
+mod vector;
+
+use std::collections::{HashMap, VecDeque};
+
+use crate::interp::natives::Natives;
+use crate::interp::value::Value;
+use crate::parsing::ast::Type;
+
+use array::Array;
+
+/// A call made to a prelude function
+#[derive(Debug, Clone, PartialEq)]
+pub struct NativeCall {
+    pub name: String,
+    pub args: Vec<Value>,
+}
+
+/// Runs prelude functions outside the game. Deterministic functions (arrays, vectors and maths) behave like they do
+/// in XS. Every other prelude function is a stub that returns a configured value, or the zero value of its return
+/// type. All calls are recorded so that tests can assert on them
+#[derive(Debug, Default)]
+pub struct NativeHost {
+    arrays: Vec<Array>,
+    returns: HashMap<String, Value>,
+    queued_returns: HashMap<String, VecDeque<Value>>,
+    calls: Vec<NativeCall>,
+    print_chat: bool,
+}
+
+impl NativeHost {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Print the messages of `xsChatData` to stdout
+    pub fn with_chat_output(mut self) -> Self {
+        self.print_chat = true;
+        self
+    }
+
+    /// Makes every call to the stub `name` return `value`
+    pub fn set_return(&mut self, name: &str, value: Value) {
+        self.returns.insert(name.to_string(), value);
+    }
+
+    /// Makes the next call to the stub `name` return `value`. Queued values are returned in order before the value
+    /// given to [`NativeHost::set_return`]
+    pub fn queue_return(&mut self, name: &str, value: Value) {
+        self.queued_returns.entry(name.to_string()).or_default().push_back(value);
+    }
+
+    pub fn calls(&self) -> &[NativeCall] {
+        &self.calls
+    }
+
+    pub fn calls_to<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a NativeCall> {
+        self.calls.iter().filter(move |call| call.name == name)
+    }
+
+    /// The number of times `name` was called with exactly these arguments
+    pub fn call_count(&self, name: &str, args: &[Value]) -> usize {
+        self.calls_to(name).filter(|call| call.args == args).count()
+    }
+
+    pub fn clear_calls(&mut self) {
+        self.calls.clear();
+    }
+
+    fn stub(&mut self, name: &str, return_type: &Type) -> Value {
+        if let Some(value) = self.queued_returns.get_mut(name).and_then(VecDeque::pop_front) {
+            return value;
+        }
+        match self.returns.get(name) {
+            Some(value) => value.clone(),
+            None => Value::default_of(return_type),
+        }
+    }
+}
+
+impl Natives for NativeHost {
+    fn call(&mut self, name: &str, args: &[Value], return_type: &Type) -> Option<Result<Value, String>> {
+        self.calls.push(NativeCall { name: name.to_string(), args: args.to_vec() });
+
+        if let ("xsChatData", [Value::Str(msg), value]) = (name, args) && self.print_chat {
+            println!("{}", msg.replace("%d", &value.to_string()));
+        }
+
+        let result = array::call(&mut self.arrays, name, args)
+            .or_else(|| vector::call(name, args))
+            .or_else(|| maths::call(name, args))
+            .unwrap_or_else(|| Ok(self.stub(name, return_type)));
+        Some(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::interp;
+
+    #[test]
+    fn arrays_behave_like_in_game() {
+        let mut interp = interp("\
+int f() {
+    int arr = xsArrayCreateInt(2, 7, \"arr\");
+    xsArraySetInt(arr, 1, 5);
+    xsArrayResizeInt(arr, 3);
+    return (xsArrayGetInt(arr, 0) * 100 + xsArrayGetInt(arr, 1) * 10 + xsArrayGetSize(arr));
+}
+int g() {
+    int arr = xsArrayCreateInt(2, 0, \"arr\");
+    return (xsArrayGetInt(arr, 2));
+}
+");
+        assert_eq!(interp.call("f", vec![]), Ok(Value::Int(753)));
+        let err = interp.call("g", vec![]).unwrap_err();
+        assert_eq!(err.msg, "Index 2 is out of bounds for array 1 of size 2");
+    }
+
+    #[test]
+    fn vectors_and_maths() {
+        let mut interp = interp("\
+float f() {
+    vector v = xsVectorSetX(vector(0, 4, 0), 3.0);
+    return (xsVectorLength(v) + round(2.345, 1));
+}
+");
+        assert_eq!(interp.call("f", vec![]), Ok(Value::Float(7.3)));
+    }
+
+    #[test]
+    fn stubs_return_configured_values_and_record_calls() {
+        let mut interp = interp("\
+int f() {
+    xsChatData(\"time %d\", xsGetTime());
+    return (xsGetTime() + xsGetGameTime());
+}
+");
+        interp.natives_mut().queue_return("xsGetTime", Value::Int(1));
+        interp.natives_mut().set_return("xsGetTime", Value::Int(10));
+
+        assert_eq!(interp.call("f", vec![]), Ok(Value::Int(10)));
+        assert_eq!(interp.natives().calls_to("xsGetTime").count(), 2);
+        assert_eq!(interp.natives().call_count("xsChatData", &[Value::Str("time %d".into()), Value::Int(1)]), 1);
+        assert_eq!(interp.natives().calls().last().map(|call| call.name.as_str()), Some("xsGetGameTime"));
+    }
+}
@@ -0,0 +1,79 @@
+use crate::interp::value::Value;
+use crate::parsing::ast::Type;
+
+/// All array types share the same ids
+#[derive(Debug)]
+pub struct Array {
+    type_: Type,
+    values: Vec<Value>,
+}
+
+fn elem_type(suffix: &str) -> Option<Type> {
+    Some(match suffix {
+        "Bool" => Type::Bool,
+        "Float" => Type::Float,
+        "Int" => Type::Int,
+        "String" => Type::Str,
+        "Vector" => Type::Vec,
+        _ => return None,
+    })
+}
+
+fn get<'a>(arrays: &'a mut [Array], id: i32, type_: &Type) -> Result<&'a mut Array, String> {
+    let array = usize::try_from(id).ok()
+        .and_then(|id| arrays.get_mut(id))
+        .ok_or_else(|| format!("Array {id} does not exist"))?;
+    if array.type_ != *type_ {
+        return Err(format!("Array {id} is an array of {}s, not {}s", array.type_, type_));
+    }
+    Ok(array)
+}
+
+fn index(array: &Array, id: i32, idx: i32) -> Result<usize, String> {
+    usize::try_from(idx).ok()
+        .filter(|idx| *idx < array.values.len())
+        .ok_or_else(|| format!("Index {idx} is out of bounds for array {id} of size {}", array.values.len()))
+}
+
+pub fn call(arrays: &mut Vec<Array>, name: &str, args: &[Value]) -> Option<Result<Value, String>> {
+    let name = name.strip_prefix("xsArray")?;
+
+    if let ("GetSize", [Value::Int(id)]) = (name, args) {
+        let size = usize::try_from(*id).ok()
+            .and_then(|id| arrays.get(id))
+            .map(|array| array.values.len() as i32)
+            .ok_or_else(|| format!("Array {id} does not exist"));
+        return Some(size.map(Value::Int));
+    }
+
+    let (op, type_) = ["Create", "Get", "Set", "Resize"].into_iter()
+        .find_map(|op| Some((op, elem_type(name.strip_prefix(op)?)?)))?;
+
+    let result = match (op, args) {
+        ("Create", [Value::Int(size), default, _unique_name]) => {
+            let Ok(size) = usize::try_from(*size) else {
+                return Some(Err(format!("Cannot create an array of size {size}")));
+            };
+            arrays.push(Array { type_, values: vec![default.clone(); size] });
+            Ok(Value::Int(arrays.len() as i32 - 1))
+        }
+        ("Get", [Value::Int(id), Value::Int(idx)]) => get(arrays, *id, &type_).and_then(|array| {
+            let idx = index(array, *id, *idx)?;
+            Ok(array.values[idx].clone())
+        }),
+        ("Set", [Value::Int(id), Value::Int(idx), value]) => get(arrays, *id, &type_).and_then(|array| {
+            let idx = index(array, *id, *idx)?;
+            array.values[idx] = value.clone();
+            Ok(Value::Bool(true))
+        }),
+        ("Resize", [Value::Int(id), Value::Int(size)]) => get(arrays, *id, &type_).map(|array| {
+            let Ok(size) = usize::try_from(*size) else {
+                return Value::Bool(false);
+            };
+            array.values.resize(size, Value::default_of(&type_));
+            Value::Bool(true)
+        }),
+        _ => return None,
+    };
+    Some(result)
+}
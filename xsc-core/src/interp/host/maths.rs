@@ -0,0 +1,49 @@
+use std::f32::consts::PI;
+
+use crate::interp::value::Value;
+
+pub fn call(name: &str, args: &[Value]) -> Option<Result<Value, String>> {
+    let value = match (name, args) {
+        ("abs", [Value::Float(x)]) => Value::Float(x.abs()),
+        ("acos", [Value::Float(x)]) => Value::Float(x.acos()),
+        ("asin", [Value::Float(x)]) => Value::Float(x.asin()),
+        ("atan", [Value::Float(x)]) => Value::Float(x.atan()),
+        ("atan2", [Value::Float(y), Value::Float(x)]) => Value::Float(y.atan2(*x)),
+        ("atan2v", [Value::Vec(x, y, _z)]) => Value::Float(y.atan2(*x)),
+        ("cos", [Value::Float(x)]) => Value::Float(x.cos()),
+        ("sin", [Value::Float(x)]) => Value::Float(x.sin()),
+        ("tan", [Value::Float(x)]) => Value::Float(x.tan()),
+        ("degrees", [Value::Float(x)]) => Value::Float(x * 180.0 / PI),
+        ("radians", [Value::Float(x)]) => Value::Float(x * PI / 180.0),
+
+        ("bitAnd", [Value::Int(x), Value::Int(y)]) => Value::Int(x & y),
+        ("bitOr", [Value::Int(x), Value::Int(y)]) => Value::Int(x | y),
+        ("bitXor", [Value::Int(x), Value::Int(y)]) => Value::Int(x ^ y),
+        ("bitNot", [Value::Int(x)]) => Value::Int(!x),
+        ("bitCastToFloat", [Value::Int(x)]) => Value::Float(f32::from_bits(*x as u32)),
+        ("bitCastToInt", [Value::Float(x)]) => Value::Int(x.to_bits() as i32),
+
+        ("ceil", [Value::Float(x)]) => Value::Float(x.ceil()),
+        ("floor", [Value::Float(x)]) => Value::Float(x.floor()),
+        ("xsCeilToInt", [Value::Float(x)]) => Value::Int(x.ceil() as i32),
+        ("round", [Value::Float(x), Value::Int(places)]) => {
+            // places are capped between 0 and 8, the default is 2
+            let places = if *places < 0 { 2 } else { (*places).min(8) };
+            let scale = 10f32.powi(places);
+            Value::Float((x * scale).round() / scale)
+        }
+
+        ("dist", [Value::Vec(x1, y1, z1), Value::Vec(x2, y2, z2)]) => {
+            let (dx, dy, dz) = (x1 - x2, y1 - y2, z1 - z2);
+            Value::Float((dx * dx + dy * dy + dz * dz).sqrt())
+        }
+        ("exp", [Value::Float(x)]) => Value::Float(x.exp()),
+        ("ln", [Value::Float(x)]) => Value::Float(x.ln()),
+        ("log10", [Value::Float(x)]) => Value::Float(x.log10()),
+        ("log2", [Value::Float(x)]) => Value::Float(x.log2()),
+        ("pow", [Value::Float(x), Value::Float(y)]) => Value::Float(x.powf(*y)),
+        ("sqrt", [Value::Float(x)]) => Value::Float(x.sqrt()),
+        _ => return None,
+    };
+    Some(Ok(value))
+}
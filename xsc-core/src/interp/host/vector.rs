@@ -0,0 +1,24 @@
+use crate::interp::value::Value;
+
+pub fn call(name: &str, args: &[Value]) -> Option<Result<Value, String>> {
+    let value = match (name, args) {
+        ("xsVectorGetX", [Value::Vec(x, _y, _z)]) => Value::Float(*x),
+        ("xsVectorGetY", [Value::Vec(_x, y, _z)]) => Value::Float(*y),
+        ("xsVectorGetZ", [Value::Vec(_x, _y, z)]) => Value::Float(*z),
+        ("xsVectorLength", [Value::Vec(x, y, z)]) => Value::Float((x * x + y * y + z * z).sqrt()),
+        ("xsVectorNormalize", [Value::Vec(x, y, z)]) => {
+            let len = (x * x + y * y + z * z).sqrt();
+            if len == 0.0 {
+                Value::Vec(0.0, 0.0, 0.0)
+            } else {
+                Value::Vec(x / len, y / len, z / len)
+            }
+        }
+        ("xsVectorSet", [Value::Float(x), Value::Float(y), Value::Float(z)]) => Value::Vec(*x, *y, *z),
+        ("xsVectorSetX", [Value::Vec(_x, y, z), Value::Float(x)]) => Value::Vec(*x, *y, *z),
+        ("xsVectorSetY", [Value::Vec(x, _y, z), Value::Float(y)]) => Value::Vec(*x, *y, *z),
+        ("xsVectorSetZ", [Value::Vec(x, y, _z), Value::Float(z)]) => Value::Vec(*x, *y, *z),
+        _ => return None,
+    };
+    Some(Ok(value))
+}
@@ -0,0 +1,10 @@
+use crate::interp::value::Value;
+use crate::parsing::ast::Type;
+
+/// Bindings for the functions declared in a prelude, which have no XS bodies to run
+pub trait Natives {
+    /// Runs the native function `name`, whose prelude signature returns `return_type`. Arguments have already been
+    /// converted to the types of the parameters and missing ones are filled in with their defaults. Returns `None`
+    /// if there is no binding for the function
+    fn call(&mut self, name: &str, args: &[Value], return_type: &Type) -> Option<Result<Value, String>>;
+}
@@ -0,0 +1,131 @@
+use std::cmp::Ordering;
+
+use crate::interp::value::Value;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArithOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Mod,
+}
+
+impl ArithOp {
+    /// Same wording as the type checker uses in its operand mismatch errors
+    fn name(&self) -> &'static str {
+        match self {
+            ArithOp::Add => "add",
+            ArithOp::Sub => "subtract",
+            ArithOp::Mul => "multiply",
+            ArithOp::Div => "divide",
+            ArithOp::Mod => "reduce modulo",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CmpOp {
+    Lt,
+    Gt,
+    Le,
+    Ge,
+    Eq,
+    Ne,
+}
+
+fn int_op(op: ArithOp, val1: i32, val2: i32) -> Result<i32, String> {
+    Ok(match op {
+        ArithOp::Add => val1.wrapping_add(val2),
+        ArithOp::Sub => val1.wrapping_sub(val2),
+        ArithOp::Mul => val1.wrapping_mul(val2),
+        ArithOp::Div | ArithOp::Mod if val2 == 0 => {
+            return Err("Division by zero".into());
+        }
+        ArithOp::Div => val1.wrapping_div(val2),
+        ArithOp::Mod => val1.wrapping_rem(val2),
+    })
+}
+
+fn float_op(op: ArithOp, val1: f32, val2: f32) -> Result<f32, String> {
+    Ok(match op {
+        ArithOp::Add => val1 + val2,
+        ArithOp::Sub => val1 - val2,
+        ArithOp::Mul => val1 * val2,
+        ArithOp::Div => val1 / val2,
+        // yES, modulo with floats does not return the fractional part
+        ArithOp::Mod => int_op(op, val1 as i32, val2 as i32)? as f32,
+    })
+}
+
+/// Applies an arithmetic operator. The result has the type of the first operand, so the second operand is
+/// converted to it first (`5 * 2.5` is `10`)
+pub fn arith(op: ArithOp, val1: Value, val2: Value) -> Result<Value, String> {
+    Ok(match (val1, val2) {
+        (Value::Int(val1), val2 @ (Value::Int(_) | Value::Float(_))) => {
+            Value::Int(int_op(op, val1, val2.as_int())?)
+        }
+        (Value::Float(val1), val2 @ (Value::Int(_) | Value::Float(_))) => {
+            Value::Float(float_op(op, val1, val2.as_float())?)
+        }
+
+        (Value::Str(val1), val2) if op == ArithOp::Add => Value::Str(format!("{val1}{val2}")),
+        (val1, Value::Str(val2)) if op == ArithOp::Add => Value::Str(format!("{val1}{val2}")),
+
+        (Value::Vec(x1, y1, z1), Value::Vec(x2, y2, z2)) if op == ArithOp::Add => {
+            Value::Vec(x1 + x2, y1 + y2, z1 + z2)
+        }
+        (Value::Vec(x1, y1, z1), Value::Vec(x2, y2, z2)) if op == ArithOp::Sub => {
+            Value::Vec(x1 - x2, y1 - y2, z1 - z2)
+        }
+        (Value::Vec(x, y, z), val @ (Value::Int(_) | Value::Float(_))) if op == ArithOp::Mul => {
+            let val = val.as_float();
+            Value::Vec(x * val, y * val, z * val)
+        }
+        (val @ (Value::Int(_) | Value::Float(_)), Value::Vec(x, y, z)) if op == ArithOp::Mul => {
+            let val = val.as_float();
+            Value::Vec(val * x, val * y, val * z)
+        }
+        (Value::Vec(x, y, z), val @ (Value::Int(_) | Value::Float(_))) if op == ArithOp::Div => {
+            let val = val.as_float();
+            Value::Vec(x / val, y / val, z / val)
+        }
+
+        (val1, val2) => {
+            return Err(format!("Cannot {} types {} and {}", op.name(), val1.type_(), val2.type_()));
+        }
+    })
+}
+
+/// Applies a relational operator. Like arithmetic, numbers are compared as the type of the first operand
+pub fn compare(op: CmpOp, val1: &Value, val2: &Value) -> Result<bool, String> {
+    let ord = match (val1, val2) {
+        (Value::Int(val1), Value::Int(_) | Value::Float(_)) => val1.partial_cmp(&val2.as_int()),
+        (Value::Float(val1), Value::Int(_) | Value::Float(_)) => val1.partial_cmp(&val2.as_float()),
+        (Value::Str(val1), Value::Str(val2)) => val1.partial_cmp(val2),
+        (Value::Vec(..), Value::Vec(..)) | (Value::Bool(_), Value::Bool(_)) => {
+            return match op {
+                CmpOp::Eq => Ok(val1 == val2),
+                CmpOp::Ne => Ok(val1 != val2),
+                _ => Err(format!("Comparing {}s with this operator crashes XS silently", val1.type_())),
+            };
+        }
+        (val1, val2) => {
+            return Err(format!("Cannot compare types {} and {}", val1.type_(), val2.type_()));
+        }
+    };
+
+    // NaN compares unequal to everything
+    let Some(ord) = ord else {
+        return Ok(op == CmpOp::Ne);
+    };
+
+    Ok(match op {
+        CmpOp::Lt => ord == Ordering::Less,
+        CmpOp::Gt => ord == Ordering::Greater,
+        CmpOp::Le => ord != Ordering::Greater,
+        CmpOp::Ge => ord != Ordering::Less,
+        CmpOp::Eq => ord == Ordering::Equal,
+        CmpOp::Ne => ord != Ordering::Equal,
+    })
+}
@@ -0,0 +1,31 @@
+use std::fmt::{Display, Formatter};
+
+use crate::r#static::info::SrcLoc;
+
+/// An error that stops the execution of a script
+#[derive(Debug, Clone, PartialEq)]
+pub struct RuntimeError {
+    pub msg: String,
+    /// Where the error happened. Errors not caused by any particular piece of code (like a missing entry point)
+    /// have no location
+    pub src_loc: Option<SrcLoc>,
+}
+
+impl RuntimeError {
+    pub fn new(msg: impl Into<String>, src_loc: SrcLoc) -> Self {
+        Self { msg: msg.into(), src_loc: Some(src_loc) }
+    }
+
+    pub fn no_loc(msg: impl Into<String>) -> Self {
+        Self { msg: msg.into(), src_loc: None }
+    }
+}
+
+impl Display for RuntimeError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match &self.src_loc {
+            None => write!(f, "{}", self.msg),
+            Some(SrcLoc { file_path, span }) => write!(f, "{} at {}:{}", self.msg, file_path.display(), span),
+        }
+    }
+}
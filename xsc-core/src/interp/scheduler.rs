@@ -0,0 +1,420 @@
+use std::cmp::Reverse;
+use std::fmt::{Display, Formatter};
+
+use crate::interp::{Interpreter, Natives, RuntimeError, Value};
+use crate::parsing::ast::{Identifier, Type};
+use crate::r#static::info::SrcLoc;
+
+/// The priority of rules that don't set one. Rules with a higher priority run first
+pub const DEFAULT_PRIORITY: i32 = 0;
+
+/// The options a rule is defined with. Intervals are in seconds
+#[derive(Debug, Clone)]
+pub struct Rule {
+    pub name: Identifier,
+    pub src_loc: SrcLoc,
+    pub is_active: bool,
+    pub run_immediately: bool,
+    pub high_frequency: bool,
+    pub min_interval: Option<i32>,
+    pub max_interval: Option<i32>,
+    pub priority: Option<i32>,
+    pub group: Option<String>,
+}
+
+/// Something that happened while simulating the rules of a script
+#[derive(Debug, Clone, PartialEq)]
+pub enum EventKind {
+    /// `main` ran before the first tick
+    Main,
+    Fired(String),
+    /// `by` is the rule that enabled it, or none if it was enabled from `main`. `group` is set when it was enabled
+    /// along with its group
+    Enabled { rule: String, by: Option<String>, group: Option<String> },
+    Disabled { rule: String, by: Option<String>, group: Option<String> },
+    /// a message sent with `xsChatData`
+    Chat(String),
+}
+
+impl Display for EventKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let change = |f: &mut Formatter<'_>, rule: &String, action, by: &Option<String>, group: &Option<String>| {
+            match group {
+                Some(group) => write!(f, "rule {rule} {action} with group {group}")?,
+                None => write!(f, "rule {rule} {action}")?,
+            }
+            match by {
+                Some(by) if by == rule && group.is_none() => write!(f, " itself"),
+                Some(by) if by == rule => write!(f, " by itself"),
+                Some(by) => write!(f, " by rule {by}"),
+                None => write!(f, " by main"),
+            }
+        };
+        match self {
+            EventKind::Main => write!(f, "main ran"),
+            EventKind::Fired(rule) => write!(f, "rule {rule} fired"),
+            EventKind::Enabled { rule, by, group } => change(f, rule, "enabled", by, group),
+            EventKind::Disabled { rule, by, group } => change(f, rule, "disabled", by, group),
+            EventKind::Chat(msg) => write!(f, "chat: {msg}"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Event {
+    /// game time in milliseconds
+    pub time: u32,
+    pub kind: EventKind,
+}
+
+/// The state of a rule during a simulation. Times are in milliseconds
+struct RuleState {
+    name: String,
+    group: Option<String>,
+    is_active: bool,
+    run_immediately: bool,
+    min_interval: u32,
+    priority: i32,
+    /// when the rule was last enabled
+    activated_at: u32,
+    last_run: Option<u32>,
+}
+
+impl RuleState {
+    fn is_due(&self, time: u32) -> bool {
+        if !self.is_active {
+            return false;
+        }
+        match self.last_run {
+            Some(last_run) => time - last_run >= self.min_interval,
+            None => self.run_immediately || time - self.activated_at >= self.min_interval,
+        }
+    }
+}
+
+fn secs_to_ms(secs: i32) -> u32 {
+    (secs.max(0) as u32).saturating_mul(1000)
+}
+
+/// Implements the rule functions of the prelude (`xsEnableRule`, `xsDisableSelf`, etc.) and the game clock. Every
+/// other call is passed on to the wrapped natives
+pub struct RuleNatives<N: Natives> {
+    natives: N,
+    rules: Vec<RuleState>,
+    /// the rule that is running right now
+    current: Option<usize>,
+    time: u32,
+    timeline: Vec<Event>,
+}
+
+impl<N: Natives> RuleNatives<N> {
+    pub fn new(natives: N) -> Self {
+        Self { natives, rules: vec![], current: None, time: 0, timeline: vec![] }
+    }
+
+    pub fn natives(&self) -> &N {
+        &self.natives
+    }
+
+    pub fn natives_mut(&mut self) -> &mut N {
+        &mut self.natives
+    }
+
+    fn record(&mut self, kind: EventKind) {
+        self.timeline.push(Event { time: self.time, kind });
+    }
+
+    fn rule_idx(&self, name: &str) -> Result<usize, String> {
+        self.rules.iter()
+            .position(|rule| rule.name == name)
+            .ok_or_else(|| format!("Rule {name} is not defined"))
+    }
+
+    fn current_idx(&self, fn_name: &str) -> Result<usize, String> {
+        self.current.ok_or_else(|| format!("{fn_name} can only be called inside a rule"))
+    }
+
+    /// `group` is the group that was enabled or disabled, if the rule wasn't set on its own
+    fn set_active(&mut self, idx: usize, is_active: bool, group: Option<&str>) {
+        let rule = &mut self.rules[idx];
+        if rule.is_active == is_active {
+            return;
+        }
+        rule.is_active = is_active;
+        if is_active {
+            rule.activated_at = self.time;
+            rule.last_run = None;
+        }
+
+        let rule = rule.name.clone();
+        let by = self.current.map(|current| self.rules[current].name.clone());
+        let group = group.map(str::to_string);
+        self.record(match is_active {
+            true => EventKind::Enabled { rule, by, group },
+            false => EventKind::Disabled { rule, by, group },
+        });
+    }
+
+    fn set_group_active(&mut self, group: &str, is_active: bool) {
+        for idx in 0..self.rules.len() {
+            if self.rules[idx].group.as_deref() == Some(group) {
+                self.set_active(idx, is_active, Some(group));
+            }
+        }
+    }
+
+    fn call_rule_fn(&mut self, name: &str, args: &[Value]) -> Option<Result<Value, String>> {
+        let result = match (name, args) {
+            ("xsGetGameTime" | "xsGetTime", []) => Ok(Value::Int((self.time / 1000) as i32)),
+            ("xsEnableRule" | "xsDisableRule", [Value::Str(rule)]) => self.rule_idx(rule).map(|idx| {
+                self.set_active(idx, name == "xsEnableRule", None);
+                Value::Void
+            }),
+            ("xsEnableRuleGroup" | "xsDisableRuleGroup", [Value::Str(group)]) => {
+                self.set_group_active(group, name == "xsEnableRuleGroup");
+                Ok(Value::Void)
+            }
+            ("xsDisableSelf", []) => self.current_idx(name).map(|idx| {
+                self.set_active(idx, false, None);
+                Value::Void
+            }),
+            ("xsIsRuleEnabled", [Value::Str(rule)]) => self.rule_idx(rule)
+                .map(|idx| Value::Bool(self.rules[idx].is_active)),
+            ("xsIsRuleGroupEnabled", [Value::Str(group)]) => Ok(Value::Bool(self.rules.iter()
+                .filter(|rule| rule.group.as_deref() == Some(group.as_str()))
+                .all(|rule| rule.is_active)
+            )),
+            ("xsSetRuleMinInterval", [Value::Str(rule), interval]) => self.rule_idx(rule).map(|idx| {
+                self.rules[idx].min_interval = secs_to_ms(interval.as_int());
+                Value::Void
+            }),
+            ("xsSetRuleMinIntervalSelf", [interval]) => self.current_idx(name).map(|idx| {
+                self.rules[idx].min_interval = secs_to_ms(interval.as_int());
+                Value::Void
+            }),
+            ("xsSetRulePriority", [Value::Str(rule), priority]) => self.rule_idx(rule).map(|idx| {
+                self.rules[idx].priority = priority.as_int();
+                Value::Void
+            }),
+            ("xsSetRulePrioritySelf", [priority]) => self.current_idx(name).map(|idx| {
+                self.rules[idx].priority = priority.as_int();
+                Value::Void
+            }),
+            // there is no time budget in a simulation, so rules never wait long enough for a max interval to matter
+            ("xsSetRuleMaxInterval", [Value::Str(rule), _interval]) => self.rule_idx(rule).map(|_idx| Value::Void),
+            ("xsSetRuleMaxIntervalSelf", [_interval]) => self.current_idx(name).map(|_idx| Value::Void),
+            _ => return None,
+        };
+        Some(result)
+    }
+}
+
+impl<N: Natives> Natives for RuleNatives<N> {
+    fn call(&mut self, name: &str, args: &[Value], return_type: &Type) -> Option<Result<Value, String>> {
+        if let ("xsChatData", [Value::Str(msg), value]) = (name, args) {
+            self.record(EventKind::Chat(msg.replace("%d", &value.to_string())));
+        }
+        self.call_rule_fn(name, args)
+            .or_else(|| self.natives.call(name, args, return_type))
+    }
+}
+
+/// Runs the rules of a script over a simulated game clock. The clock advances in ticks, and in every tick the
+/// active rules whose min interval has passed run in order of priority, then definition. `highFrequency` rules run
+/// every tick. A rule without `runImmediately` first runs one min interval after it is enabled
+pub struct Scheduler<N: Natives> {
+    interp: Interpreter<RuleNatives<N>>,
+    /// length of a tick in milliseconds
+    tick: u32,
+    started: bool,
+}
+
+impl<N: Natives> Scheduler<N> {
+    /// Takes an interpreter with the script already loaded
+    pub fn new(mut interp: Interpreter<RuleNatives<N>>, tick: u32) -> Self {
+        let rules = interp.rules().iter()
+            .map(|rule| RuleState {
+                name: rule.name.0.clone(),
+                group: rule.group.clone(),
+                is_active: rule.is_active,
+                run_immediately: rule.run_immediately,
+                min_interval: match rule.high_frequency {
+                    true => 0,
+                    false => secs_to_ms(rule.min_interval.unwrap_or(0)),
+                },
+                priority: rule.priority.unwrap_or(DEFAULT_PRIORITY),
+                activated_at: 0,
+                last_run: None,
+            }).collect();
+        interp.natives_mut().rules = rules;
+        Self { interp, tick: tick.max(1), started: false }
+    }
+
+    pub fn interp(&self) -> &Interpreter<RuleNatives<N>> {
+        &self.interp
+    }
+
+    pub fn timeline(&self) -> &[Event] {
+        &self.interp.natives().timeline
+    }
+
+    /// The current game time in milliseconds
+    pub fn time(&self) -> u32 {
+        self.interp.natives().time
+    }
+
+    /// Runs `main` (if the script has one) and then every tick up to and including the given game time in
+    /// milliseconds. Stops at the first runtime error
+    pub fn run_until(&mut self, end: u32) -> Result<(), RuntimeError> {
+        if !self.started {
+            self.started = true;
+            if self.interp.has_fn("main") {
+                self.interp.natives_mut().record(EventKind::Main);
+                self.interp.call("main", vec![])?;
+            }
+            self.run_tick()?;
+        }
+        while self.time() + self.tick <= end {
+            self.interp.natives_mut().time += self.tick;
+            self.run_tick()?;
+        }
+        Ok(())
+    }
+
+    fn run_tick(&mut self) -> Result<(), RuntimeError> {
+        // priorities changed during a tick only take effect in the next one
+        let mut order = (0..self.interp.natives().rules.len()).collect::<Vec<_>>();
+        order.sort_by_key(|&idx| Reverse(self.interp.natives().rules[idx].priority));
+
+        for idx in order {
+            let natives = self.interp.natives_mut();
+            let time = natives.time;
+            if !natives.rules[idx].is_due(time) {
+                continue;
+            }
+            let name = natives.rules[idx].name.clone();
+            natives.rules[idx].last_run = Some(time);
+            natives.current = Some(idx);
+            natives.record(EventKind::Fired(name.clone()));
+
+            let result = self.interp.call(&name, vec![]);
+            self.interp.natives_mut().current = None;
+            result?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::interp::NativeHost;
+    use crate::test_utils::interp_with;
+
+    /// The timeline of simulating a source up to the given game time in seconds, one line per event
+    fn simulate(src: &str, secs: u32) -> Vec<String> {
+        let interp = interp_with(src, RuleNatives::new(NativeHost::new()));
+        let mut scheduler = Scheduler::new(interp, 1000);
+        scheduler.run_until(secs * 1000).expect("Simulation runs");
+        scheduler.timeline().iter()
+            .map(|event| format!("{}: {}", event.time / 1000, event.kind))
+            .collect()
+    }
+
+    #[test]
+    fn rules_run_by_priority_then_definition() {
+        let timeline = simulate("\
+rule a active runImmediately minInterval 10 {
+}
+rule b active runImmediately minInterval 10 priority 5 {
+}
+rule c active runImmediately minInterval 10 {
+}
+rule d active runImmediately minInterval 10 {
+}
+void main() {
+    xsSetRulePriority(\"d\", -2147483647 - 1);
+}
+", 0);
+        assert_eq!(timeline, [
+            "0: main ran",
+            "0: rule b fired",
+            "0: rule a fired",
+            "0: rule c fired",
+            "0: rule d fired",
+        ]);
+    }
+
+    #[test]
+    fn rules_wait_for_their_min_interval() {
+        let timeline = simulate("\
+rule later active minInterval 2 {
+}
+rule now active runImmediately minInterval 3 {
+}
+rule fast active highFrequency {
+}
+rule never active minInterval 2147483647 {
+}
+", 4);
+        assert_eq!(timeline, [
+            "0: rule now fired",
+            "0: rule fast fired",
+            "1: rule fast fired",
+            "2: rule later fired",
+            "2: rule fast fired",
+            "3: rule now fired",
+            "3: rule fast fired",
+            "4: rule later fired",
+            "4: rule fast fired",
+        ]);
+    }
+
+    #[test]
+    fn enabled_rules_wait_a_min_interval_from_then() {
+        let timeline = simulate("\
+rule starter active runImmediately minInterval 1 {
+    xsEnableRule(\"waiter\");
+    xsDisableSelf();
+}
+rule waiter inactive minInterval 2 {
+    xsDisableRule(\"waiter\");
+}
+void main() {
+    xsDisableRule(\"waiter\");
+}
+", 3);
+        assert_eq!(timeline, [
+            "0: main ran",
+            "0: rule starter fired",
+            "0: rule waiter enabled by rule starter",
+            "0: rule starter disabled itself",
+            "2: rule waiter fired",
+            "2: rule waiter disabled itself",
+        ]);
+    }
+
+    #[test]
+    fn group_changes_name_the_group() {
+        let timeline = simulate("\
+rule a active runImmediately group g {
+    xsDisableRuleGroup(\"g\");
+}
+rule b active runImmediately group g {
+}
+void main() {
+    xsEnableRuleGroup(\"h\");
+}
+rule c inactive group h {
+}
+", 0);
+        assert_eq!(timeline, [
+            "0: main ran",
+            "0: rule c enabled with group h by main",
+            "0: rule a fired",
+            "0: rule a disabled with group g by itself",
+            "0: rule b disabled with group g by rule a",
+            "0: rule c fired",
+        ]);
+    }
+}
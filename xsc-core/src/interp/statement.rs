@@ -0,0 +1,204 @@
+use crate::interp::{Interpreter, Natives, RuntimeError, Value};
+use crate::parsing::ast::{AstNode, Expr, Identifier, Type};
+use crate::parsing::span::{Span, Spanned};
+
+/// What happens after a statement has run
+pub enum Flow {
+    Next,
+    Break,
+    Continue,
+    Return(Value),
+    /// jumps to the label with this name in the current or an enclosing block
+    Goto(Spanned<Identifier>),
+}
+
+fn find_label(body: &[Spanned<AstNode>], label: &Identifier) -> Option<usize> {
+    body.iter().position(|(stmt, _span)| matches!(stmt, AstNode::LabelDef((name, _span)) if name == label))
+}
+
+impl<N: Natives> Interpreter<N> {
+    pub(super) fn exec_body(&mut self, body: &[Spanned<AstNode>]) -> Result<Flow, RuntimeError> {
+        let mut pos = 0;
+        while pos < body.len() {
+            match self.exec_stmt(&body[pos])? {
+                Flow::Next => pos += 1,
+                Flow::Goto((label, span)) => match find_label(body, &label) {
+                    Some(label_pos) => {
+                        self.count_iteration(&span)?;
+                        pos = label_pos + 1;
+                    }
+                    None => return Ok(Flow::Goto((label, span))),
+                },
+                flow => return Ok(flow),
+            }
+        }
+        Ok(Flow::Next)
+    }
+
+    fn exec_stmt(&mut self, (stmt, span): &Spanned<AstNode>) -> Result<Flow, RuntimeError> {
+        match stmt {
+            AstNode::VarDef { is_static, type_, name: (name, _name_span), value, .. } => {
+                let key = (self.frame().path.clone(), span.start);
+                let value = match self.statics.get(&key) {
+                    Some(value) if *is_static => value.clone(),
+                    _ => match value {
+                        Some(expr) => self.eval(expr)?.cast(type_),
+                        None => Value::default_of(type_),
+                    },
+                };
+                if *is_static {
+                    self.statics.insert(key, value.clone());
+                    self.frame().statics.push((name.clone(), span.start));
+                }
+                self.frame().locals.insert(name.clone(), value);
+            }
+            AstNode::VarAssign { name: (name, name_span), value } => {
+                let value = self.eval(value)?;
+                if !self.set_var(name, value) {
+                    return Err(self.err(format!("Name {name} is not defined"), name_span));
+                }
+            }
+            AstNode::Return(value) => {
+                let value = match value {
+                    Some(expr) => self.eval(expr)?,
+                    None => Value::Void,
+                };
+                return Ok(Flow::Return(value));
+            }
+            AstNode::IfElse { condition, consequent: (consequent, _span), alternate } => {
+                if self.eval(condition)?.as_bool() {
+                    return self.exec_body(consequent);
+                }
+                if let Some((alternate, _span)) = alternate {
+                    return self.exec_body(alternate);
+                }
+            }
+            AstNode::While { condition, body: (body, _span) } => {
+                let mut iterations = 0;
+                while self.eval(condition)?.as_bool() {
+                    iterations += 1;
+                    self.chk_loop_limit(iterations, span)?;
+                    self.count_iteration(span)?;
+                    match self.exec_body(body)? {
+                        Flow::Next | Flow::Continue => {}
+                        Flow::Break => break,
+                        flow => return Ok(flow),
+                    }
+                }
+            }
+            AstNode::For { var, condition, body: (body, _span) } => {
+                let (AstNode::VarAssign { name: (name, _name_span), value }, _span) = var.as_ref() else {
+                    unreachable!("The loop variable of a for is always assigned");
+                };
+                let step = match condition.0 {
+                    Expr::Lt(..) | Expr::Le(..) => 1,
+                    _ => -1,
+                };
+
+                // the loop variable only lives as long as the loop, unless it was defined before
+                let init = self.eval(value)?.cast(&Type::Int);
+                let is_new = !self.set_var(name, init.clone());
+                if is_new {
+                    self.frame().locals.insert(name.clone(), init);
+                }
+
+                let mut iterations = 0;
+                let mut flow = Flow::Next;
+                while self.eval(condition)?.as_bool() {
+                    iterations += 1;
+                    self.chk_loop_limit(iterations, span)?;
+                    self.count_iteration(span)?;
+                    match self.exec_body(body)? {
+                        Flow::Next | Flow::Continue => {}
+                        Flow::Break => break,
+                        other => {
+                            flow = other;
+                            break;
+                        }
+                    }
+                    let value = self.get_var(name).map_or(0, Value::as_int).wrapping_add(step);
+                    self.set_var(name, Value::Int(value));
+                }
+
+                if is_new {
+                    self.frame().locals.remove(name);
+                }
+                return Ok(flow);
+            }
+            AstNode::Switch { clause, cases } => {
+                // case values are compared as ints, the first matching case runs. There is no fall through
+                let clause = self.eval(clause)?.as_int();
+                let mut matched = None;
+                for (expr, body) in cases {
+                    let Some(expr) = expr else {
+                        continue;
+                    };
+                    let value = self.eval(expr)?;
+                    if let Value::Bool(_) = value {
+                        return Err(self.err("Using a bool in a case expression crashes XS silently", &expr.1));
+                    }
+                    if value.as_int() == clause {
+                        matched = Some(body);
+                        break;
+                    }
+                }
+                let matched = matched.or_else(|| cases.iter()
+                    .find(|(expr, _body)| expr.is_none())
+                    .map(|(_expr, body)| body)
+                );
+                if let Some((body, _span)) = matched {
+                    return match self.exec_body(body)? {
+                        Flow::Break => Ok(Flow::Next),
+                        flow => Ok(flow),
+                    };
+                }
+            }
+            AstNode::PostDPlus((name, name_span)) | AstNode::PostDMinus((name, name_span)) => {
+                let step = if let AstNode::PostDPlus(_) = stmt { 1 } else { -1 };
+                let value = match self.get_var(name) {
+                    Some(Value::Int(val)) => Value::Int(val.wrapping_add(step)),
+                    Some(Value::Float(val)) => Value::Float(val + step as f32),
+                    Some(value) => {
+                        return Err(self.err(format!("Cannot increment or decrement a {}", value.type_()), name_span));
+                    }
+                    None => return Err(self.err(format!("Name {name} is not defined"), name_span)),
+                };
+                self.set_var(name, value);
+            }
+            AstNode::Break => return Ok(Flow::Break),
+            AstNode::Continue => return Ok(Flow::Continue),
+            AstNode::Goto(label) => return Ok(Flow::Goto(label.clone())),
+            AstNode::Discarded(expr) => {
+                self.eval(expr)?;
+            }
+            AstNode::Breakpoint => {
+                return Err(self.err("Hit a breakpoint, XS pauses here irrecoverably", span));
+            }
+            AstNode::LabelDef(_) | AstNode::Debug(_) => {}
+            // these only appear at the top level
+            AstNode::Error | AstNode::Include(_) | AstNode::FnDef { .. } | AstNode::RuleDef { .. }
+            | AstNode::Class { .. } => {}
+        }
+        Ok(Flow::Next)
+    }
+
+    /// The first iteration of a loop does not count as a repeat
+    fn chk_loop_limit(&self, iterations: u32, span: &Span) -> Result<(), RuntimeError> {
+        let limit = self.global_int("infiniteLoopLimit");
+        if limit >= 0 && iterations > limit as u32 + 1 {
+            return Err(self.err(format!("Exceeded the infiniteLoopLimit of {limit}"), span));
+        }
+        Ok(())
+    }
+
+    fn count_iteration(&mut self, span: &Span) -> Result<(), RuntimeError> {
+        self.iterations += 1;
+        if self.max_iterations > 0 && self.iterations > self.max_iterations {
+            return Err(self.err(
+                format!("Stopped after {} loop iterations, this may never finish", self.max_iterations),
+                span,
+            ));
+        }
+        Ok(())
+    }
+}
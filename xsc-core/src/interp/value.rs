@@ -0,0 +1,134 @@
+use std::fmt::{Display, Formatter};
+
+use crate::parsing::ast::{Literal, Type};
+
+/// A runtime XS value
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Int(i32),
+    Float(f32),
+    Bool(bool),
+    Str(String),
+    Vec(f32, f32, f32),
+    Void,
+}
+
+impl Value {
+    /// The value an uninitialised variable of the given type holds
+    pub fn default_of(type_: &Type) -> Self {
+        match type_ {
+            Type::Int => Value::Int(0),
+            Type::Float => Value::Float(0.0),
+            Type::Bool => Value::Bool(false),
+            Type::Str => Value::Str(String::new()),
+            Type::Vec => Value::Vec(0.0, 0.0, 0.0),
+            _ => Value::Void,
+        }
+    }
+
+    pub fn from_lit(lit: &Literal) -> Self {
+        match lit {
+            // int literals are at most 9 digits long, so they always fit
+            Literal::Int(val) => Value::Int(*val as i32),
+            Literal::Float(val) => Value::Float(*val as f32),
+            Literal::Bool(val) => Value::Bool(*val),
+            Literal::Str(val) => Value::Str(unescape(val)),
+        }
+    }
+
+    pub fn type_(&self) -> Type {
+        match self {
+            Value::Int(_) => Type::Int,
+            Value::Float(_) => Type::Float,
+            Value::Bool(_) => Type::Bool,
+            Value::Str(_) => Type::Str,
+            Value::Vec(..) => Type::Vec,
+            Value::Void => Type::Void,
+        }
+    }
+
+    /// Converts this value to the given type the way XS does when assigning it to a variable or passing it as a
+    /// parameter. Floats are truncated when converted to ints and bools become 0 or 1. Values that can't be
+    /// converted are returned as is, the type checker rejects those programs
+    pub fn cast(self, type_: &Type) -> Self {
+        match (self, type_) {
+            (Value::Float(val), Type::Int) => Value::Int(val as i32),
+            (Value::Bool(val), Type::Int) => Value::Int(val as i32),
+            (Value::Int(val), Type::Float) => Value::Float(val as f32),
+            (Value::Bool(val), Type::Float) => Value::Float(val as i32 as f32),
+            (value, _) => value,
+        }
+    }
+
+    pub fn as_int(&self) -> i32 {
+        match self {
+            Value::Int(val) => *val,
+            Value::Float(val) => *val as i32,
+            Value::Bool(val) => *val as i32,
+            _ => 0,
+        }
+    }
+
+    pub fn as_float(&self) -> f32 {
+        match self {
+            Value::Int(val) => *val as f32,
+            Value::Float(val) => *val,
+            Value::Bool(val) => *val as i32 as f32,
+            _ => 0.0,
+        }
+    }
+
+    /// Writes this value as an XS literal, unlike [`Display`] which formats it the way XS prints it
+    pub fn lit_str(&self) -> String {
+        match self {
+            Value::Int(val) => format!("{}", val),
+            Value::Float(val) => format!("{}", val),
+            Value::Bool(val) => format!("{}", val),
+            Value::Str(val) => format!("{:?}", val),
+            Value::Vec(x, y, z) => format!("vector({}, {}, {})", x, y, z),
+            Value::Void => String::new(),
+        }
+    }
+
+    pub fn as_bool(&self) -> bool {
+        match self {
+            Value::Bool(val) => *val,
+            Value::Int(val) => *val != 0,
+            Value::Float(val) => *val != 0.0,
+            _ => false,
+        }
+    }
+}
+
+/// String literals keep their quotes and escape sequences in the AST
+fn unescape(lit: &str) -> String {
+    let mut val = String::with_capacity(lit.len());
+    let mut chars = lit[1..(lit.len() - 1)].chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            val.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => val.push('\n'),
+            Some('t') => val.push('\t'),
+            Some(c) => val.push(c),
+            None => {}
+        }
+    }
+    val
+}
+
+/// Formats a value the way XS does when it is concatenated with a string
+impl Display for Value {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Value::Int(val) => write!(f, "{}", val),
+            Value::Float(val) => write!(f, "{:.2}", val),
+            Value::Bool(val) => write!(f, "{}", val),
+            Value::Str(val) => write!(f, "{}", val),
+            Value::Vec(x, y, z) => write!(f, "({:.2}, {:.2}, {:.2})", x, y, z),
+            Value::Void => Ok(()),
+        }
+    }
+}
@@ -1,4 +1,11 @@
 pub mod parsing;
 pub mod r#static;
 pub mod utils;
-pub mod doxygen;
\ No newline at end of file
+pub mod doxygen;
+pub mod config;
+pub mod formatter;
+pub mod interp;
+pub mod codegen;
+
+#[cfg(test)]
+mod test_utils;
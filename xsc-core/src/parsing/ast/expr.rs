@@ -1,3 +1,5 @@
+use std::fmt::{Display, Formatter};
+
 use crate::parsing::ast::identifier::Identifier;
 use crate::parsing::ast::literal::Literal;
 use crate::parsing::span::Spanned;
@@ -55,4 +57,47 @@ impl Expr {
 fn render(v: &Spanned<Expr>) -> String {
     let (v, _span) = v;
     v.lit_str().unwrap_or("???".into())
-}
\ No newline at end of file
+}
+/// Prints the expression back as XS code, used to refer to expressions in diagnostics. Literals are printed from
+/// their values, so `1.50` is printed as `1.5`
+impl Display for Expr {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let bin_op = |f: &mut Formatter<'_>, op: &str, (expr1, _span1): &Spanned<Expr>, (expr2, _span2): &Spanned<Expr>| {
+            write!(f, "{} {} {}", expr1, op, expr2)
+        };
+        match self {
+            Expr::Literal(lit) => { write!(f, "{}", lit) }
+            Expr::Identifier(id) => { write!(f, "{}", id) }
+            Expr::Paren(expr) => { write!(f, "({})", expr.0) }
+            Expr::Vec { x, y, z } => { write!(f, "vector({}, {}, {})", x.0, y.0, z.0) }
+            Expr::FnCall { name: (name, _span), args } => {
+                let args = args.iter()
+                    .map(|(arg, _span)| arg.to_string())
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                write!(f, "{}({})", name, args)
+            }
+
+            Expr::Neg(expr) => { write!(f, "-{}", expr.0) }
+            Expr::Not(expr) => { write!(f, "!{}", expr.0) }
+
+            Expr::Star(expr1, expr2) => { bin_op(f, "*", expr1, expr2) }
+            Expr::FSlash(expr1, expr2) => { bin_op(f, "/", expr1, expr2) }
+            Expr::PCent(expr1, expr2) => { bin_op(f, "%", expr1, expr2) }
+
+            Expr::Plus(expr1, expr2) => { bin_op(f, "+", expr1, expr2) }
+            Expr::Minus(expr1, expr2) => { bin_op(f, "-", expr1, expr2) }
+
+            Expr::Lt(expr1, expr2) => { bin_op(f, "<", expr1, expr2) }
+            Expr::Gt(expr1, expr2) => { bin_op(f, ">", expr1, expr2) }
+            Expr::Le(expr1, expr2) => { bin_op(f, "<=", expr1, expr2) }
+            Expr::Ge(expr1, expr2) => { bin_op(f, ">=", expr1, expr2) }
+
+            Expr::Eq(expr1, expr2) => { bin_op(f, "==", expr1, expr2) }
+            Expr::Ne(expr1, expr2) => { bin_op(f, "!=", expr1, expr2) }
+
+            Expr::And(expr1, expr2) => { bin_op(f, "&&", expr1, expr2) }
+            Expr::Or(expr1, expr2) => { bin_op(f, "||", expr1, expr2) }
+        }
+    }
+}
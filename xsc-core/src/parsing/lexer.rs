@@ -4,7 +4,9 @@ mod operator;
 mod punctuation;
 mod keyword;
 mod comment;
+mod invalid;
 
+pub use keyword::is_keyword;
 pub use token::Token;
 
 use chumsky::prelude::*;
@@ -12,6 +14,7 @@ use chumsky::prelude::*;
 use crate::parsing::span::{Span, Spanned};
 
 use comment::comment;
+use invalid::invalid;
 use keyword::keyword;
 use literal::literal;
 use operator::operator;
@@ -26,12 +29,56 @@ pub fn lexer<'src>() -> impl Parser<
         keyword(),
         operator(),
         punctuation(),
-        // any().ignore_then(none_of(" \t\n(){};,").repeated()).to_slice().map(|_src| Token::Error)
+        invalid(),
     ))
         .map_with(|tok, info| (tok, info.span()))
         .padded()
-        // .recover_with(skip_then_retry_until(any().ignored(), end()))
         .repeated()
         .collect()
         .padded()
 }
+
+/// Whether a string is lexed as a single identifier, and so can be used as a name. Keywords, literals like `true`
+/// and names that start with a digit are not
+pub fn is_identifier(name: &str) -> bool {
+    matches!(
+        lexer().parse(name).into_output().as_deref(),
+        Some([(Token::Identifier(id), _span)]) if id.0 == name
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parsing::ast::AstNode;
+    use crate::r#static::info::parse_src;
+
+    #[test]
+    fn unterminated_string_stops_at_semicolon() {
+        let src = "int v = \"p;\nint w = \"q\";\n";
+        let (tokens, errs) = lexer().parse(src).into_output_errors();
+        let tokens = tokens.expect("Lexing recovers");
+
+        assert_eq!(errs.len(), 1);
+        assert_eq!(tokens[3], (Token::Error, Span::new(8, 10)));
+        assert!(matches!(tokens[4].0, Token::SColon));
+
+        let ((ast, _comments), errs) = parse_src(src);
+        assert_eq!(errs.len(), 1);
+        assert!(ast.iter().any(|(stmt, _span)| matches!(stmt, AstNode::VarDef { name, .. } if name.0.0 == "w")));
+    }
+
+    #[test]
+    fn strings_end_on_their_line() {
+        let (_tokens, errs) = lexer().parse("\"a\nb\"").into_output_errors();
+        assert_eq!(errs.len(), 2);
+    }
+
+    #[test]
+    fn invalid_escapes_are_reported() {
+        let (tokens, errs) = lexer().parse(r#""a\qb""#).into_output_errors();
+        assert!(matches!(tokens.as_deref(), Some([(Token::Literal(_), _)])));
+        assert_eq!(errs.len(), 1);
+        assert_eq!(errs[0].to_string(), "Invalid escape sequence '\\q'");
+    }
+}
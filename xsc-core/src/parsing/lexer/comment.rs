@@ -13,7 +13,13 @@ pub fn comment<'src>() -> impl Parser<
             any().and_is(just("*/").not()).repeated()
         ).then_ignore(just("*/"));
     
-    line_comment.or(block_comment)
+    let unterminated_block_comment = just("/*")
+        .then(any().repeated())
+        .validate(|_, info, emitter| {
+            emitter.emit(Rich::custom(info.span(), "Unterminated block comment, expected '*/'"));
+        });
+
+    choice((line_comment, block_comment, unterminated_block_comment.ignored()))
         .to_slice()
         .map(|val: &str| Token::Comment(String::from(val)))
 }
\ No newline at end of file
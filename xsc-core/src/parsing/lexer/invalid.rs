@@ -0,0 +1,18 @@
+use chumsky::error::Rich;
+use chumsky::prelude::*;
+
+use crate::parsing::lexer::token::Token;
+use crate::parsing::span::Span;
+
+/// Anything that isn't a valid token is lexed as an error up to the next whitespace or delimiter
+pub fn invalid<'src>() -> impl Parser<
+    'src, &'src str, Token, extra::Err<Rich<'src, char, Span>>
+> {
+    any()
+        .then(none_of(" \t\r\n(){};,").repeated())
+        .to_slice()
+        .validate(|val: &str, info, emitter| {
+            emitter.emit(Rich::custom(info.span(), format!("Invalid token '{val}'")));
+            Token::Error
+        })
+}
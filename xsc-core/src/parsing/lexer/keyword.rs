@@ -4,6 +4,11 @@ use crate::parsing::ast::Identifier;
 use crate::parsing::lexer::token::Token;
 use crate::parsing::span::Span;
 
+/// Whether a name is reserved, and so cannot be used for a variable, function, rule or label
+pub fn is_keyword(name: &str) -> bool {
+    !matches!(keyword().parse(name).into_output(), Some(Token::Identifier(_)) | None)
+}
+
 pub fn keyword<'src>() -> impl Parser<
     'src, &'src str, Token, extra::Err<Rich<'src, char, Span>>
 > {
@@ -24,24 +24,37 @@ pub fn literal<'src>() -> impl Parser<
     let string = just('"')
         .ignore_then(
             choice((
-                none_of("\\\""),
-                just("\\").ignore_then(any()).map(|c| match c {
+                none_of("\\\"\r\n"),
+                just("\\").ignore_then(none_of("\r\n")).validate(|c, info, emitter| match c {
                     'n' => '\n',
                     't' => '\t',
                     '\\' => '\\',
                     '"' => '"',
-                    _ => c,
+                    _ => {
+                        emitter.emit(Rich::custom(info.span(), format!("Invalid escape sequence '\\{c}'")));
+                        c
+                    },
                 })
             )).repeated()
         )
         .then_ignore(just('"'))
         .to_slice()
         .map(|val: &str| Token::Literal(Literal::str(val)));
+
+    // the rest of the line is skipped so that the following lines can still be lexed. It stops at a `;` so that the
+    // parser can recover at the end of the statement instead of swallowing the next one
+    let unterminated_string = just('"')
+        .then(none_of("\r\n;").repeated())
+        .validate(|_, info, emitter| {
+            emitter.emit(Rich::custom(info.span(), "Unterminated string, expected '\"'"));
+            Token::Error
+        });
     
     choice((
         float,
         int,
         bool,
         string,
+        unterminated_string,
     ))
 }
\ No newline at end of file
@@ -17,7 +17,8 @@ pub fn parser<'tokens>() -> impl Parser<
     extra::Err<Rich<'tokens, Token, Span>>,
 > + Clone {
     statement()
-        // .recover_with(skip_then_retry_until(any().ignored(), end()))
+        // stray closing braces at the top level
+        .recover_with(skip_then_retry_until(any().ignored(), end()))
         .repeated()
         .collect()
 }
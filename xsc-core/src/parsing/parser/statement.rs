@@ -14,6 +14,7 @@ mod postfix;
 mod label_def_or_goto_or_dbg;
 mod discarded_expr;
 mod class_def;
+mod recovery;
 
 use chumsky::prelude::*;
 
@@ -29,6 +30,7 @@ use class_def::class_def;
 use discarded_expr::discarded_expr;
 use label_def_or_goto_or_dbg::label_def_or_goto_or_dbg;
 use postfix::postfix;
+use recovery::recovery;
 use return_::return_;
 use rule_def::rule_def;
 use switch::switch;
@@ -63,6 +65,6 @@ pub fn statement<'tokens>() -> impl Parser<
             // select! { Token::Comment(msg) => msg, }
             //     .map_with(|msg, info| AstNode::Comment((msg, info.span())))
             //     .map_with(|node, info| (node, info.span())),
-        ))
+        )).recover_with(via_parser(recovery()))
     })
 }
\ No newline at end of file
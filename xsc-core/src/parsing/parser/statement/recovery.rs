@@ -0,0 +1,30 @@
+use chumsky::prelude::*;
+
+use crate::parsing::ast::AstNode;
+use crate::parsing::lexer::Token;
+use crate::parsing::parser::parser_input::ParserInput;
+use crate::parsing::span::{Span, Spanned};
+
+/// Skips an invalid statement up to and including the next `;` or `{...}` block, whichever comes first. A `}` is
+/// never skipped so that the enclosing block can still be closed
+pub fn recovery<'tokens>() -> impl Parser<
+    'tokens,
+    ParserInput<'tokens>,
+    Spanned<AstNode>,
+    extra::Err<Rich<'tokens, Token, Span>>,
+> + Clone {
+    let block = nested_delimiters(Token::LBrace, Token::RBrace, [], |_span| ());
+    let end = choice((
+        just(Token::SColon).ignored(),
+        block,
+    ));
+
+    choice((
+        none_of([Token::SColon, Token::LBrace, Token::RBrace])
+            .repeated()
+            .at_least(1)
+            .then(end.clone().or_not())
+            .ignored(),
+        end,
+    )).map_with(|_, info| (AstNode::Error, info.span()))
+}
@@ -43,8 +43,8 @@ pub fn rule_def<'tokens>(
         .ignore_then(choice((
             select! { Token::Literal(Literal::Str(val)) => val },
             select! { Token::Identifier(Identifier(val)) => val },
-        )))
-        .map_with(|val, info| (RuleOpt::Group((val, info.span())), info.span()));
+        )).map_with(|val, info| (val, info.span())))
+        .map_with(|grp, info| (RuleOpt::Group(grp), info.span()));
     
     let rule_opt = choice((no_args, int_arg, grp));
     
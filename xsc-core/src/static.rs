@@ -1,2 +1,11 @@
+pub mod cfg;
+pub mod const_eval;
 pub mod info;
+pub mod liveness;
+pub mod lookup;
+pub mod returns;
+pub mod sign;
 pub mod type_check;
+pub mod type_inf;
+pub mod uninit;
+pub mod unreachable;
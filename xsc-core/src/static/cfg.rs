@@ -0,0 +1,637 @@
+use std::collections::HashMap;
+use std::fmt::Write;
+
+use crate::parsing::ast::{AstNode, Body, Expr, Identifier, Literal};
+use crate::parsing::span::{Span, Spanned};
+
+pub type BlockId = usize;
+
+/// A piece of code that runs as a whole inside a basic block
+#[derive(Debug, Clone, Copy)]
+pub enum Elem<'a> {
+    /// A statement that does not branch (variable definitions, assignments, discarded expressions, postfix
+    /// operations and labels). `return`, `break`, `continue` and `goto` are also statements, but always end their
+    /// block
+    Stmt(&'a Spanned<AstNode>),
+    /// The condition of an `if`, `while` or `for`, or the clause of a `switch`. The block branches on it
+    Cond(&'a Spanned<Expr>),
+    /// A case value of a `switch` that is compared to its clause. The block branches on the result
+    Case(&'a Spanned<Expr>),
+    /// Steps the loop variable of a `for` at the end of every iteration. Holds the `VarAssign` of the loop
+    ForStep(&'a Spanned<AstNode>),
+}
+
+impl<'a> Elem<'a> {
+    pub fn span(&self) -> Span {
+        match self {
+            Elem::Stmt((_stmt, span)) | Elem::ForStep((_stmt, span)) => *span,
+            Elem::Cond((_expr, span)) | Elem::Case((_expr, span)) => *span,
+        }
+    }
+
+    /// The identifiers whose values this element reads, in the order they are read. Names of called functions are
+    /// not included
+    pub fn reads(&self) -> Vec<Spanned<&'a Identifier>> {
+        let mut reads = vec![];
+        match self {
+            Elem::Cond(expr) | Elem::Case(expr) => expr_reads(expr, &mut reads),
+            // the loop variable is read to step it
+            Elem::ForStep((stmt, _span)) => {
+                if let AstNode::VarAssign { name: (name, span), .. } = stmt {
+                    reads.push((name, *span));
+                }
+            }
+            Elem::Stmt((stmt, _span)) => match stmt {
+                AstNode::VarDef { value: Some(expr), .. } | AstNode::VarAssign { value: expr, .. }
+                | AstNode::Return(Some(expr)) | AstNode::Discarded(expr) => {
+                    expr_reads(expr, &mut reads);
+                }
+                AstNode::PostDPlus((name, span)) | AstNode::PostDMinus((name, span)) | AstNode::Debug((name, span)) => {
+                    reads.push((name, *span));
+                }
+                _ => {}
+            },
+        }
+        reads
+    }
+
+    /// The identifier that this element assigns a value to, if any. Declarations without an initial value don't
+    /// assign one
+    pub fn write(&self) -> Option<Spanned<&'a Identifier>> {
+        let (stmt, _span) = match self {
+            Elem::Stmt(stmt) | Elem::ForStep(stmt) => *stmt,
+            Elem::Cond(_) | Elem::Case(_) => return None,
+        };
+        match stmt {
+            AstNode::VarDef { name: (name, span), value: Some(_), .. } | AstNode::VarAssign { name: (name, span), .. }
+            | AstNode::PostDPlus((name, span)) | AstNode::PostDMinus((name, span)) => Some((name, *span)),
+            _ => None,
+        }
+    }
+}
+
+/// The value of a condition that is made up of only `true` or `false` literals
+pub fn const_cond((expr, _span): &Spanned<Expr>) -> Option<bool> {
+    match expr {
+        Expr::Literal(Literal::Bool(val)) => Some(*val),
+        Expr::Paren(expr) => const_cond(expr),
+        Expr::Not(expr) => const_cond(expr).map(|val| !val),
+        _ => None,
+    }
+}
+
+/// Collects the identifiers read by an expression
+pub fn expr_reads<'a>((expr, span): &'a Spanned<Expr>, reads: &mut Vec<Spanned<&'a Identifier>>) {
+    match expr {
+        Expr::Literal(_) => {}
+        Expr::Identifier(name) => reads.push((name, *span)),
+        Expr::FnCall { args, .. } => {
+            for arg in args {
+                expr_reads(arg, reads);
+            }
+        }
+        Expr::Vec { x, y, z } => {
+            expr_reads(x, reads);
+            expr_reads(y, reads);
+            expr_reads(z, reads);
+        }
+        Expr::Paren(expr) | Expr::Neg(expr) | Expr::Not(expr) => expr_reads(expr, reads),
+        Expr::Star(expr1, expr2) | Expr::FSlash(expr1, expr2) | Expr::PCent(expr1, expr2)
+        | Expr::Plus(expr1, expr2) | Expr::Minus(expr1, expr2)
+        | Expr::Lt(expr1, expr2) | Expr::Gt(expr1, expr2) | Expr::Le(expr1, expr2) | Expr::Ge(expr1, expr2)
+        | Expr::Eq(expr1, expr2) | Expr::Ne(expr1, expr2)
+        | Expr::And(expr1, expr2) | Expr::Or(expr1, expr2) => {
+            expr_reads(expr1, reads);
+            expr_reads(expr2, reads);
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EdgeKind {
+    Next,
+    /// taken when the condition of the block holds, or the case matches
+    True,
+    False,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Block<'a> {
+    pub elems: Vec<Elem<'a>>,
+    pub succs: Vec<(BlockId, EdgeKind)>,
+    pub preds: Vec<BlockId>,
+}
+
+impl Block<'_> {
+    /// The successors that control can actually flow into. A condition that is always `true` never takes its
+    /// `false` edge, and vice versa
+    pub fn taken_succs(&self) -> impl Iterator<Item = (BlockId, EdgeKind)> + '_ {
+        let cond = match self.elems.last() {
+            Some(Elem::Cond(expr)) => const_cond(expr),
+            _ => None,
+        };
+        self.succs.iter()
+            .copied()
+            .filter(move |(_to, kind)| match (cond, kind) {
+                (Some(val), EdgeKind::True) => val,
+                (Some(val), EdgeKind::False) => !val,
+                _ => true,
+            })
+    }
+
+    /// The code this block covers. Blocks without code (like the exit block or the end of an `if`) have no span
+    pub fn span(&self) -> Option<Span> {
+        let first = self.elems.first()?.span();
+        let last = self.elems.last()?.span();
+        Some(Span::new(first.start, last.end.max(first.end)))
+    }
+}
+
+/// The control flow graph of a function or rule body. Block [`Cfg::ENTRY`] is where the body starts and the empty
+/// block [`Cfg::EXIT`] is where it ends, both by returning and by running off the end of the body. Blocks that
+/// can't be reached from the entry are kept if they contain code, so that analyses can find unreachable code.
+///
+/// The cases of a `switch` don't fall through in XS: the clause is compared to each case value in order, and the
+/// body of the first match (or the default) runs, after which control leaves the `switch`
+#[derive(Debug, Clone)]
+pub struct Cfg<'a> {
+    pub blocks: Vec<Block<'a>>,
+}
+
+struct Loop {
+    break_to: BlockId,
+    /// switches can be broken out of but not continued
+    continue_to: Option<BlockId>,
+}
+
+struct Builder<'a> {
+    blocks: Vec<Block<'a>>,
+    current: BlockId,
+    loops: Vec<Loop>,
+    labels: HashMap<&'a Identifier, BlockId>,
+    gotos: Vec<(BlockId, &'a Identifier)>,
+}
+
+impl<'a> Builder<'a> {
+    fn new_block(&mut self) -> BlockId {
+        self.blocks.push(Block::default());
+        self.blocks.len() - 1
+    }
+
+    fn edge(&mut self, from: BlockId, to: BlockId, kind: EdgeKind) {
+        self.blocks[from].succs.push((to, kind));
+        self.blocks[to].preds.push(from);
+    }
+
+    fn push(&mut self, elem: Elem<'a>) {
+        self.blocks[self.current].elems.push(elem);
+    }
+
+    /// Ends the current block with a jump. Code after it starts a new block that nothing flows into
+    fn jump(&mut self, stmt: &'a Spanned<AstNode>, to: Option<BlockId>) {
+        self.push(Elem::Stmt(stmt));
+        if let Some(to) = to {
+            self.edge(self.current, to, EdgeKind::Next);
+        }
+        self.current = self.new_block();
+    }
+
+    /// Starts a new block that the current one flows into
+    fn fall_into(&mut self, block: BlockId) {
+        self.edge(self.current, block, EdgeKind::Next);
+        self.current = block;
+    }
+
+    fn body(&mut self, body: &'a Body) {
+        for stmt in body {
+            self.stmt(stmt);
+        }
+    }
+
+    fn stmt(&mut self, spanned_stmt: &'a Spanned<AstNode>) {
+        let (stmt, _span) = spanned_stmt;
+        match stmt {
+            AstNode::Return(_) => self.jump(spanned_stmt, Some(Cfg::EXIT)),
+            AstNode::Break => {
+                let to = self.loops.last().map(|loop_| loop_.break_to);
+                self.jump(spanned_stmt, to);
+            }
+            AstNode::Continue => {
+                let to = self.loops.iter().rev().find_map(|loop_| loop_.continue_to);
+                self.jump(spanned_stmt, to);
+            }
+            AstNode::Goto((label, _span)) => {
+                self.gotos.push((self.current, label));
+                self.jump(spanned_stmt, None);
+            }
+            AstNode::LabelDef((label, _span)) => {
+                let block = self.new_block();
+                self.fall_into(block);
+                self.labels.insert(label, block);
+                self.push(Elem::Stmt(spanned_stmt));
+            }
+            AstNode::IfElse { condition, consequent: (consequent, _span), alternate } => {
+                self.push(Elem::Cond(condition));
+                let cond = self.current;
+                let join = self.new_block();
+
+                self.current = self.new_block();
+                self.edge(cond, self.current, EdgeKind::True);
+                self.body(consequent);
+                self.edge(self.current, join, EdgeKind::Next);
+
+                match alternate {
+                    Some((alternate, _span)) => {
+                        self.current = self.new_block();
+                        self.edge(cond, self.current, EdgeKind::False);
+                        self.body(alternate);
+                        self.edge(self.current, join, EdgeKind::Next);
+                    }
+                    None => self.edge(cond, join, EdgeKind::False),
+                }
+                self.current = join;
+            }
+            AstNode::While { condition, body: (body, _span) } => {
+                let header = self.new_block();
+                self.fall_into(header);
+                self.push(Elem::Cond(condition));
+                let after = self.new_block();
+
+                self.current = self.new_block();
+                self.edge(header, self.current, EdgeKind::True);
+                self.edge(header, after, EdgeKind::False);
+                self.loops.push(Loop { break_to: after, continue_to: Some(header) });
+                self.body(body);
+                self.loops.pop();
+                self.edge(self.current, header, EdgeKind::Next);
+                self.current = after;
+            }
+            AstNode::For { var, condition, body: (body, _span) } => {
+                self.push(Elem::Stmt(var));
+                let header = self.new_block();
+                self.fall_into(header);
+                self.push(Elem::Cond(condition));
+                let step = self.new_block();
+                let after = self.new_block();
+
+                self.current = self.new_block();
+                self.edge(header, self.current, EdgeKind::True);
+                self.edge(header, after, EdgeKind::False);
+                self.loops.push(Loop { break_to: after, continue_to: Some(step) });
+                self.body(body);
+                self.loops.pop();
+                self.fall_into(step);
+                self.push(Elem::ForStep(var));
+                self.edge(step, header, EdgeKind::Next);
+                self.current = after;
+            }
+            AstNode::Switch { clause, cases } => {
+                self.push(Elem::Cond(clause));
+                let after = self.new_block();
+                self.loops.push(Loop { break_to: after, continue_to: None });
+
+                let mut default = None;
+                for (expr, (body, _span)) in cases {
+                    let Some(expr) = expr else {
+                        default = Some(body);
+                        continue;
+                    };
+                    let test = self.new_block();
+                    self.fall_into(test);
+                    self.push(Elem::Case(expr));
+
+                    self.current = self.new_block();
+                    self.edge(test, self.current, EdgeKind::True);
+                    self.body(body);
+                    self.edge(self.current, after, EdgeKind::Next);
+
+                    self.current = self.new_block();
+                    self.edge(test, self.current, EdgeKind::False);
+                }
+                if let Some(body) = default {
+                    self.body(body);
+                }
+                self.edge(self.current, after, EdgeKind::Next);
+
+                self.loops.pop();
+                self.current = after;
+            }
+            AstNode::VarDef { .. } | AstNode::VarAssign { .. } | AstNode::Discarded(_)
+            | AstNode::PostDPlus(_) | AstNode::PostDMinus(_) | AstNode::Debug(_) | AstNode::Breakpoint => {
+                self.push(Elem::Stmt(spanned_stmt));
+            }
+            // these only appear at the top level
+            AstNode::Error | AstNode::Include(_) | AstNode::FnDef { .. } | AstNode::RuleDef { .. }
+            | AstNode::Class { .. } => {}
+        }
+    }
+
+    /// Skips over the empty blocks that only pass control on (like the end of an `if`) and removes the empty blocks
+    /// that nothing flows into, which are left behind by jumps
+    fn finish(mut self) -> Cfg<'a> {
+        for (from, label) in std::mem::take(&mut self.gotos) {
+            if let Some(&to) = self.labels.get(label) {
+                self.edge(from, to, EdgeKind::Next);
+            }
+        }
+
+        let is_empty = |id: BlockId| id != Cfg::ENTRY && id != Cfg::EXIT && self.blocks[id].elems.is_empty();
+        let forward = |mut id: BlockId| {
+            // a loop of empty blocks is cut short instead of being followed forever
+            for _ in 0..self.blocks.len() {
+                match self.blocks[id].succs.as_slice() {
+                    [(to, EdgeKind::Next)] if is_empty(id) => id = *to,
+                    _ => break,
+                }
+            }
+            id
+        };
+        let succs = self.blocks.iter()
+            .map(|block| block.succs.iter().map(|(to, kind)| (forward(*to), *kind)).collect::<Vec<_>>())
+            .collect::<Vec<_>>();
+
+        let mut is_kept = (0..self.blocks.len()).map(|id| forward(id) == id).collect::<Vec<_>>();
+        loop {
+            let mut has_preds = vec![false; self.blocks.len()];
+            for (id, succs) in succs.iter().enumerate() {
+                if !is_kept[id] {
+                    continue;
+                }
+                for (to, _kind) in succs {
+                    has_preds[*to] = true;
+                }
+            }
+            let unused = (0..self.blocks.len())
+                .filter(|id| is_kept[*id] && is_empty(*id) && !has_preds[*id])
+                .collect::<Vec<_>>();
+            if unused.is_empty() {
+                break;
+            }
+            for id in unused {
+                is_kept[id] = false;
+            }
+        }
+
+        let mut ids = vec![None; self.blocks.len()];
+        for (new_id, id) in (0..self.blocks.len()).filter(|id| is_kept[*id]).enumerate() {
+            ids[id] = Some(new_id);
+        }
+
+        let mut blocks = self.blocks.into_iter()
+            .zip(succs)
+            .enumerate()
+            .filter(|(id, _block)| is_kept[*id])
+            .map(|(_id, (block, succs))| Block {
+                elems: block.elems,
+                succs: succs.into_iter()
+                    .filter_map(|(to, kind)| ids[to].map(|to| (to, kind)))
+                    .collect(),
+                preds: vec![],
+            })
+            .collect::<Vec<_>>();
+        let edges = blocks.iter()
+            .enumerate()
+            .flat_map(|(from, block)| block.succs.iter().map(move |(to, _kind)| (from, *to)))
+            .collect::<Vec<_>>();
+        for (from, to) in edges {
+            blocks[to].preds.push(from);
+        }
+        Cfg { blocks }
+    }
+}
+
+impl<'a> Cfg<'a> {
+    pub const ENTRY: BlockId = 0;
+    pub const EXIT: BlockId = 1;
+
+    pub fn new(body: &'a Body) -> Self {
+        let mut builder = Builder {
+            blocks: vec![Block::default(), Block::default()],
+            current: Cfg::ENTRY,
+            loops: vec![],
+            labels: HashMap::new(),
+            gotos: vec![],
+        };
+        builder.body(body);
+        builder.edge(builder.current, Cfg::EXIT, EdgeKind::Next);
+        builder.finish()
+    }
+
+    /// Whether each block can be reached from the entry. Edges that a constant condition never takes are not
+    /// followed
+    pub fn reachable(&self) -> Vec<bool> {
+        let mut reachable = vec![false; self.blocks.len()];
+        let mut stack = vec![Cfg::ENTRY];
+        while let Some(id) = stack.pop() {
+            if reachable[id] {
+                continue;
+            }
+            reachable[id] = true;
+            stack.extend(self.blocks[id].taken_succs().map(|(to, _kind)| to));
+        }
+        reachable
+    }
+
+    /// Writes this graph as a DOT cluster named after its function or rule. Blocks are labelled with their code
+    pub fn write_dot(&self, name: &str, src: &str, dot: &mut String) {
+        let node = |id: BlockId| format!("\"{name}_{id}\"");
+        let _ = writeln!(dot, "  subgraph \"cluster_{name}\" {{");
+        let _ = writeln!(dot, "    label = \"{}\";", escape(name));
+        for (id, block) in self.blocks.iter().enumerate() {
+            let label = match id {
+                Cfg::ENTRY if block.elems.is_empty() => "entry".to_string(),
+                Cfg::EXIT => "exit".to_string(),
+                _ => block.elems.iter()
+                    .map(|elem| elem_src(elem, src))
+                    .map(|code| format!("{}\\l", escape(&code)))
+                    .collect::<String>(),
+            };
+            let _ = writeln!(dot, "    {} [shape = box, label = \"{label}\"];", node(id));
+            for (to, kind) in &block.succs {
+                let attrs = match kind {
+                    EdgeKind::Next => "",
+                    EdgeKind::True => " [label = \"true\"]",
+                    EdgeKind::False => " [label = \"false\"]",
+                };
+                let _ = writeln!(dot, "    {} -> {}{attrs};", node(id), node(*to));
+            }
+        }
+        let _ = writeln!(dot, "  }}");
+    }
+}
+
+/// The first line of code of an element
+fn elem_src(elem: &Elem, src: &str) -> String {
+    let span = elem.span();
+    let code = src.get(span.start..span.end).unwrap_or("");
+    let code = code.lines().next().unwrap_or("").trim();
+    match elem {
+        Elem::Cond(_) => format!("({code})"),
+        Elem::Case(_) => format!("case {code}"),
+        Elem::ForStep(_) => format!("step {}", code.split('=').next().unwrap_or("").trim()),
+        Elem::Stmt(_) => code.to_string(),
+    }
+}
+
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+/// Builds the graph of every function and rule defined in the file
+pub fn proc_cfgs(ast: &Body) -> Vec<(&Spanned<Identifier>, Cfg<'_>)> {
+    ast.iter()
+        .filter_map(|(stmt, _span)| match stmt {
+            AstNode::FnDef { name, body: (body, _span), .. } | AstNode::RuleDef { name, body: (body, _span), .. } => {
+                Some((name, Cfg::new(body)))
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+/// A DOT digraph with a cluster for each graph
+pub fn to_dot(cfgs: &[(&Spanned<Identifier>, Cfg)], src: &str) -> String {
+    let mut dot = String::from("digraph cfg {\n  node [fontname = \"monospace\"];\n");
+    for ((name, _span), cfg) in cfgs {
+        cfg.write_dot(&name.0, src, &mut dot);
+    }
+    dot.push_str("}\n");
+    dot
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::parse;
+
+    fn dot(src: &str) -> String {
+        let ast = parse(src);
+        to_dot(&proc_cfgs(&ast), src)
+    }
+
+    /// Whether the block of each statement that starts with the given code can be reached
+    fn reachable(src: &str, code: &str) -> bool {
+        let ast = parse(src);
+        let cfgs = proc_cfgs(&ast);
+        let (_name, cfg) = &cfgs[0];
+        let reachable = cfg.reachable();
+        let id = cfg.blocks.iter()
+            .position(|block| block.elems.iter().any(|elem| src[elem.span().start..].starts_with(code)))
+            .expect("Code is in a block");
+        reachable[id]
+    }
+
+    #[test]
+    fn if_else_branches_join() {
+        assert_eq!(dot("\
+int f(int a = 0) {
+    if (a > 0) {
+        a = 1;
+    } else {
+        a = 2;
+    }
+    return (a);
+}
+"), r#"digraph cfg {
+  node [fontname = "monospace"];
+  subgraph "cluster_f" {
+    label = "f";
+    "f_0" [shape = box, label = "(a > 0)\l"];
+    "f_0" -> "f_3" [label = "true"];
+    "f_0" -> "f_4" [label = "false"];
+    "f_1" [shape = box, label = "exit"];
+    "f_2" [shape = box, label = "return (a);\l"];
+    "f_2" -> "f_1";
+    "f_3" [shape = box, label = "a = 1;\l"];
+    "f_3" -> "f_2";
+    "f_4" [shape = box, label = "a = 2;\l"];
+    "f_4" -> "f_2";
+  }
+}
+"#);
+    }
+
+    #[test]
+    fn loops_continue_at_their_condition() {
+        assert_eq!(dot("\
+void w() {
+    int i = 0;
+    while (i < 3) {
+        if (i == 1) {
+            continue;
+        }
+        i++;
+    }
+}
+"), r#"digraph cfg {
+  node [fontname = "monospace"];
+  subgraph "cluster_w" {
+    label = "w";
+    "w_0" [shape = box, label = "int i = 0;\l"];
+    "w_0" -> "w_2";
+    "w_1" [shape = box, label = "exit"];
+    "w_2" [shape = box, label = "(i < 3)\l"];
+    "w_2" -> "w_3" [label = "true"];
+    "w_2" -> "w_1" [label = "false"];
+    "w_3" [shape = box, label = "(i == 1)\l"];
+    "w_3" -> "w_5" [label = "true"];
+    "w_3" -> "w_4" [label = "false"];
+    "w_4" [shape = box, label = "i++;\l"];
+    "w_4" -> "w_2";
+    "w_5" [shape = box, label = "continue;\l"];
+    "w_5" -> "w_2";
+  }
+}
+"#);
+    }
+
+    #[test]
+    fn switch_cases_do_not_fall_through() {
+        let ast = parse("\
+void s(int a = 0) {
+    switch (a) {
+        case 1: {
+            a = 2;
+        }
+        default: {
+            a = 3;
+        }
+    }
+}
+");
+        let cfgs = proc_cfgs(&ast);
+        let (_name, cfg) = &cfgs[0];
+        let succs = cfg.blocks.iter().map(|block| block.succs.clone()).collect::<Vec<_>>();
+        assert_eq!(succs, [
+            vec![(2, EdgeKind::Next)],
+            vec![],
+            vec![(3, EdgeKind::True), (4, EdgeKind::False)],
+            vec![(Cfg::EXIT, EdgeKind::Next)],
+            vec![(Cfg::EXIT, EdgeKind::Next)],
+        ]);
+        assert_eq!(cfg.blocks[Cfg::EXIT].preds, [3, 4]);
+    }
+
+    #[test]
+    fn constant_conditions_and_jumps_cut_off_code() {
+        let src = "\
+void f() {
+    if (false) {
+        int a = 1;
+    }
+    int b = 2;
+    goto end;
+    int c = 3;
+    label end;
+    while (true) {
+    }
+    int d = 4;
+}
+";
+        assert!(!reachable(src, "int a"));
+        assert!(reachable(src, "int b"));
+        assert!(!reachable(src, "int c"));
+        assert!(reachable(src, "label end"));
+        assert!(!reachable(src, "int d"));
+    }
+}
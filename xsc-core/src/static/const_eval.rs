@@ -0,0 +1,139 @@
+use crate::interp::{arith, compare, ArithOp, CmpOp, Value};
+use crate::parsing::ast::Expr;
+use crate::parsing::span::Spanned;
+use crate::r#static::info::TypeEnv;
+
+/// Folds an expression made up of literals and consts into the value XS computes for it. Arithmetic follows the
+/// same rules as when the script runs: the result has the type of the first operand and ints wrap around on
+/// overflow.
+///
+/// `None` if the expression calls a function, reads a variable that is not a const, or would cause an error when
+/// run (like a division by zero)
+pub fn const_eval((expr, _span): &Spanned<Expr>, type_env: &TypeEnv) -> Option<Value> {
+    let arith_op = |op: ArithOp, expr1: &Spanned<Expr>, expr2: &Spanned<Expr>| {
+        arith(op, const_eval(expr1, type_env)?, const_eval(expr2, type_env)?).ok()
+    };
+    let cmp_op = |op: CmpOp, expr1: &Spanned<Expr>, expr2: &Spanned<Expr>| {
+        let (val1, val2) = (const_eval(expr1, type_env)?, const_eval(expr2, type_env)?);
+        compare(op, &val1, &val2).ok().map(Value::Bool)
+    };
+
+    match expr {
+        Expr::Literal(lit) => Some(Value::from_lit(lit)),
+        Expr::Identifier(name) => {
+            let info = type_env.get(name)?;
+            if !info.modifiers.is_const() {
+                return None;
+            }
+            info.value
+        }
+        Expr::Paren(expr) => const_eval(expr, type_env),
+        Expr::Vec { x, y, z } => {
+            let (x, y, z) = (const_eval(x, type_env)?, const_eval(y, type_env)?, const_eval(z, type_env)?);
+            Some(Value::Vec(x.as_float(), y.as_float(), z.as_float()))
+        }
+        Expr::FnCall { .. } => None,
+        Expr::Neg(expr) => match const_eval(expr, type_env)? {
+            Value::Int(val) => Some(Value::Int(val.wrapping_neg())),
+            Value::Float(val) => Some(Value::Float(-val)),
+            _ => None,
+        },
+        Expr::Not(expr) => Some(Value::Bool(!const_eval(expr, type_env)?.as_bool())),
+
+        Expr::Star(expr1, expr2) => arith_op(ArithOp::Mul, expr1, expr2),
+        Expr::FSlash(expr1, expr2) => arith_op(ArithOp::Div, expr1, expr2),
+        Expr::PCent(expr1, expr2) => arith_op(ArithOp::Mod, expr1, expr2),
+        Expr::Plus(expr1, expr2) => arith_op(ArithOp::Add, expr1, expr2),
+        Expr::Minus(expr1, expr2) => arith_op(ArithOp::Sub, expr1, expr2),
+
+        Expr::Lt(expr1, expr2) => cmp_op(CmpOp::Lt, expr1, expr2),
+        Expr::Gt(expr1, expr2) => cmp_op(CmpOp::Gt, expr1, expr2),
+        Expr::Le(expr1, expr2) => cmp_op(CmpOp::Le, expr1, expr2),
+        Expr::Ge(expr1, expr2) => cmp_op(CmpOp::Ge, expr1, expr2),
+        Expr::Eq(expr1, expr2) => cmp_op(CmpOp::Eq, expr1, expr2),
+        Expr::Ne(expr1, expr2) => cmp_op(CmpOp::Ne, expr1, expr2),
+
+        Expr::And(expr1, expr2) => {
+            let (val1, val2) = (const_eval(expr1, type_env)?, const_eval(expr2, type_env)?);
+            Some(Value::Bool(val1.as_bool() && val2.as_bool()))
+        }
+        Expr::Or(expr1, expr2) => {
+            let (val1, val2) = (const_eval(expr1, type_env)?, const_eval(expr2, type_env)?);
+            Some(Value::Bool(val1.as_bool() || val2.as_bool()))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use crate::parsing::ast::AstNode;
+    use crate::test_utils::{check, fn_def, parse, warnings, PATH};
+
+    use super::*;
+
+    const CONSTS: &str = "\
+const int A = 7;
+const int ZERO = 0;
+int v = 1;
+";
+
+    /// The folded value of an expression that can use the globals in [`CONSTS`]
+    fn fold(expr: &str) -> Option<Value> {
+        let (type_env, _ast_cache) = check(CONSTS);
+        let ast = parse(&format!("void f() {{\n    int x = {expr};\n}}\n"));
+        let (_params, body, _span) = fn_def(&ast, "f");
+        let [(AstNode::VarDef { value: Some(value), .. }, _span)] = body.as_slice() else {
+            panic!("The body is a single definition");
+        };
+        const_eval(value, &type_env)
+    }
+
+    #[test]
+    fn expressions_fold_like_xs_computes_them() {
+        assert_eq!(fold("A / 2"), Some(Value::Int(3)));
+        assert_eq!(fold("7.0 / 2"), Some(Value::Float(3.5)));
+        // the first operand decides the type
+        assert_eq!(fold("1 + 2.5"), Some(Value::Int(3)));
+        assert_eq!(fold("2147483647 + 1"), Some(Value::Int(i32::MIN)));
+        assert_eq!(fold("-(A % 4)"), Some(Value::Int(-3)));
+        assert_eq!(fold("(A / 2) * 2 != A"), Some(Value::Bool(true)));
+        assert_eq!(fold("!(A > 1) || true"), Some(Value::Bool(true)));
+        assert_eq!(fold("vector(1, A, 0.5)"), Some(Value::Vec(1.0, 7.0, 0.5)));
+    }
+
+    #[test]
+    fn values_that_are_only_known_at_runtime_do_not_fold() {
+        assert_eq!(fold("5 / ZERO"), None);
+        assert_eq!(fold("v + 1"), None);
+        assert_eq!(fold("abs(1)"), None);
+    }
+
+    #[test]
+    fn cases_with_the_same_folded_value_are_duplicates() {
+        let (type_env, _ast_cache) = check("\
+const int THREE = 3;
+void f(int a = 0) {
+    switch (a) {
+        case THREE: {
+        }
+        case 1 + 2: {
+        }
+        case 2.9: {
+        }
+        case 4: {
+        }
+    }
+}
+");
+        let errs = type_env.errs().get(&PathBuf::from(PATH)).cloned().unwrap_or_default();
+        let dup_cases = warnings(&errs).into_iter()
+            .filter(|warning| warning.starts_with("DupCase"))
+            .collect::<Vec<_>>();
+        assert_eq!(dup_cases, [
+            "DupCase: Only the first case will run on a match, both are 3",
+            "DupCase: Only the first case will run on a match, both are 3",
+        ]);
+    }
+}
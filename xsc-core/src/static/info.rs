@@ -1,5 +1,6 @@
 mod fn_info;
 mod id_info;
+mod ref_info;
 mod src_loc;
 mod type_env;
 mod xs_error;
@@ -11,19 +12,21 @@ mod types;
 
 pub use fn_info::FnInfo;
 pub use id_info::IdInfo;
+pub use ref_info::{RefInfo, RefKind};
 pub use src_loc::SrcLoc;
 pub use type_env::TypeEnv;
 pub use xs_error::{WarningKind, XsError};
 pub use error::Error;
 pub use parse_error::ParseError;
 pub use modifiers::Modifiers;
-pub use gen_errs::{gen_errs_from_path, gen_errs_from_src};
+pub use gen_errs::{gen_errs_from_path, gen_errs_from_src, parse_src};
 pub use types::{
     AstCache,
     AstCacheRef,
     AstMap,
     AstMapRef,
     AstInfo,
+    ParsedSrc,
 
     SrcCache,
     SrcCacheRef,
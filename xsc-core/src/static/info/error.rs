@@ -5,6 +5,8 @@ use crate::r#static::info::ParseError;
 #[derive(Debug)]
 pub enum Error {
     FileErr(PathBuf, String),
+    /// The file includes itself, directly or through other files
+    CircularDep(PathBuf, String),
     ParseErrs { path: PathBuf, errs: Vec<ParseError> },
 }
 
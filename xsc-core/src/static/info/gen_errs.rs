@@ -8,7 +8,7 @@ use chumsky::Parser;
 
 use crate::parsing::lexer::{lexer, Token};
 use crate::parsing::parser::parser;
-use crate::r#static::info::{AstCacheRef, Error, ParseError, SrcCacheRef, TypeEnv};
+use crate::r#static::info::{AstCacheRef, Error, ParseError, ParsedSrc, SrcCacheRef, TypeEnv};
 use crate::r#static::type_check::xs_tc;
 use crate::utils::{pop};
 
@@ -52,33 +52,56 @@ pub fn gen_errs_from_src(
     ast_cache: AstCacheRef,
     src_cache: SrcCacheRef,
 ) -> Result<(), Vec<Error>> {
-    let (tokens, errs) = lexer()
-        .parse(src)
-        .into_output_errors();
-    
     let hash = blake3::hash(src.as_bytes());
-    if let Some((prev_hash, (ast, comments))) = pop(ast_cache, path) {
+    if let Some((prev_hash, (ast, comments), parse_errs)) = pop(ast_cache, path) {
         let Some(prev_hash) = prev_hash else {
-            return Err(vec![Error::FileErr(
+            return Err(vec![Error::CircularDep(
                 path.clone(), format!("Circular dependency detected while parsing '{}'", path.display())
             )])
         };
-        ast_cache.insert(path.clone(), (None, (vec![], vec![])));
+        ast_cache.insert(path.clone(), (None, (vec![], vec![]), vec![]));
         if hash == prev_hash {
             let r = xs_tc(path, &ast, type_env, ast_cache, src_cache, &comments);
-            ast_cache.insert(path.clone(), (Some(hash), (ast, comments)));
+            let r = with_parse_errs(path, &parse_errs, r);
+            ast_cache.insert(path.clone(), (Some(hash), (ast, comments), parse_errs));
             return r
         }
     };
-    
+
+    // the parser recovers from syntax errors, so whatever could be parsed is still type checked
+    let ((ast, comments), parse_errs) = parse_src(src);
+
+    ast_cache.insert(path.clone(), (None, (vec![], vec![]), vec![]));
+    let r = xs_tc(path, &ast, type_env, ast_cache, src_cache, &comments);
+    let r = with_parse_errs(path, &parse_errs, r);
+    ast_cache.insert(path.clone(), (Some(hash), (ast, comments), parse_errs));
+    r
+}
+
+fn with_parse_errs(path: &PathBuf, parse_errs: &[ParseError], result: Result<(), Vec<Error>>) -> Result<(), Vec<Error>> {
+    if parse_errs.is_empty() {
+        return result;
+    }
+    let mut errs = vec![Error::parse_errs(path, parse_errs.to_vec())];
+    if let Err(mut tc_errs) = result {
+        errs.append(&mut tc_errs);
+    }
+    Err(errs)
+}
+
+/// Lexes and parses the given source, returning its AST and the comments that were stripped from it. The AST
+/// is partial if there are any errors
+pub fn parse_src(src: &str) -> (ParsedSrc, Vec<ParseError>) {
+    let (tokens, lex_errs) = lexer()
+        .parse(src)
+        .into_output_errors();
+
+    let mut errs = lex_errs.iter()
+        .map(ParseError::lex_err)
+        .collect::<Vec<_>>();
+
     let Some(tokens) = tokens else {
-        ast_cache.insert(path.clone(), (Some(hash), (vec![], vec![])));
-        return Err(vec![Error::parse_errs(
-            path,
-            errs.iter()
-                .map(ParseError::lex_err)
-                .collect()
-        )]);
+        return ((vec![], vec![]), errs);
     };
 
     let (tokens, comments) = tokens.into_iter()
@@ -90,23 +113,16 @@ pub fn gen_errs_from_src(
             _ => unreachable!(),
         }).collect();
     
-    let (ast, errs) = parser()
+    let (ast, parse_errs) = parser()
         .map_with(|ast, e| (ast, e.span()))
         .parse(tokens.as_slice().spanned((src.len()..src.len()).into()))
         .into_output_errors();
 
-    let Some((ast, _span)) = ast else {
-        ast_cache.insert(path.clone(), (Some(hash), (vec![], vec![])));
-        return Err(vec![Error::parse_errs(
-            path,
-            errs.iter()
-                .map(ParseError::parse_err)
-                .collect()
-        )]);
-    };
+    // invalid tokens have already been reported by the lexer
+    errs.extend(parse_errs.iter()
+        .filter(|err| err.found() != Some(&Token::Error))
+        .map(ParseError::parse_err));
+    let ast = ast.map(|(ast, _span)| ast).unwrap_or_default();
 
-    ast_cache.insert(path.clone(), (None, (vec![], vec![])));
-    let r = xs_tc(path, &ast, type_env, ast_cache, src_cache, &comments);
-    ast_cache.insert(path.clone(), (Some(hash), (ast, comments)));
-    r
-}
\ No newline at end of file
+    ((ast, comments), errs)
+}
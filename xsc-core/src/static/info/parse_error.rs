@@ -4,7 +4,7 @@ use chumsky::error::Rich;
 
 use crate::parsing::span::Span;
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum ParseError {
     LexerError { msg: String, span: Span },
     ParseError { msg: String, span: Span },
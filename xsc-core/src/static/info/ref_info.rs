@@ -0,0 +1,22 @@
+use crate::r#static::info::src_loc::SrcLoc;
+
+/// Whether a use of a name reads its value or writes to it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RefKind {
+    Read,
+    /// assignments, `++`, `--`, the variable of a `for` loop and the override of a mutable function
+    Write,
+}
+
+/// A use of a name found while type checking
+#[derive(Debug, Clone)]
+pub struct RefInfo {
+    pub src_loc: SrcLoc,
+    pub kind: RefKind,
+}
+
+impl RefInfo {
+    pub fn new(src_loc: SrcLoc, kind: RefKind) -> Self {
+        Self { src_loc, kind }
+    }
+}
@@ -2,7 +2,7 @@ use std::path::PathBuf;
 
 use crate::parsing::span::Span;
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub struct SrcLoc {
     pub file_path: PathBuf,
     pub span: Span,
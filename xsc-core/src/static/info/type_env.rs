@@ -3,10 +3,12 @@ use std::path::PathBuf;
 use std::sync::{Arc, RwLock};
 use chumsky::container::{Container};
 
-use crate::parsing::ast::{Identifier};
+use crate::parsing::ast::{Identifier, Type};
 use crate::parsing::span::{contains, Span};
 use crate::r#static::info::fn_info::FnInfo;
 use crate::r#static::info::id_info::IdInfo;
+use crate::r#static::info::ref_info::{RefInfo, RefKind};
+use crate::r#static::info::src_loc::SrcLoc;
 use crate::r#static::info::xs_error::XsError;
 
 #[derive(Debug, Clone)]
@@ -17,10 +19,22 @@ pub struct TypeEnv {
     
     pub errs: HashMap<PathBuf, Vec<XsError>>,
 
+    /// every use of a name, by the location of its definition
+    pub refs: HashMap<SrcLoc, Vec<RefInfo>>,
+    /// the overrides of mutable functions, mapped to the definition they override
+    pub overrides: HashMap<SrcLoc, SrcLoc>,
+    /// rule names passed to the rule functions of the prelude before the rule is defined
+    pub pending_rule_refs: Vec<(Identifier, SrcLoc)>,
+    /// the names of rule groups in rule options and in calls to the rule group functions of the prelude
+    pub group_refs: HashMap<String, Vec<SrcLoc>>,
+
     pub current_doc: Option<String>,
     pub current_fnv_env: Option<FnInfo>, // mmm...
     
     pub current_ignores: Arc<RwLock<Option<HashSet<u32>>>>,
+    /// the warnings ignored by a statement, by the span of the statement. Flow warnings are only found after the
+    /// whole function has been walked, so these are kept around for them
+    pub stmt_ignores: HashMap<PathBuf, Vec<(Span, HashSet<u32>)>>,
     
     pub include_dirs: Arc<Vec<PathBuf>>,
     pub dependencies: Option<HashMap<PathBuf, HashSet<PathBuf>>>,
@@ -49,10 +63,16 @@ impl TypeEnv {
             fn_envs: HashMap::new(),
             errs: HashMap::new(),
 
+            refs: HashMap::new(),
+            overrides: HashMap::new(),
+            pending_rule_refs: vec![],
+            group_refs: HashMap::new(),
+
             include_dirs: Arc::new(include_dirs),
             dependencies: Some(HashMap::new()),
 
             current_ignores: Arc::new(RwLock::new(None)),
+            stmt_ignores: HashMap::new(),
             
             current_doc: None,
             current_fnv_env: None,
@@ -67,7 +87,11 @@ impl TypeEnv {
         ignores.contains(&ignore)
     }
     
-    pub fn temp_ignore(&mut self, ignores: HashSet<u32>) -> TempIgnore {
+    pub fn temp_ignore(&mut self, path: &PathBuf, span: &Span, ignores: HashSet<u32>) -> TempIgnore {
+        self.stmt_ignores
+            .entry(path.clone())
+            .or_default()
+            .push((*span, ignores.clone()));
         self.current_ignores.write().expect("Not concurrent").replace(ignores);
         TempIgnore { ignores: self.current_ignores.clone() }
     }
@@ -91,6 +115,7 @@ impl TypeEnv {
     }
     
     pub fn set(&mut self, id: &Identifier, info: IdInfo) {
+        self.add_def(&info.src_loc);
         match &mut self.current_fnv_env {
             Some(env) => env.set(id.clone(), info),
             None => self.identifiers.push((id.clone(), info)),
@@ -98,6 +123,7 @@ impl TypeEnv {
     }
 
     pub fn set_global(&mut self, id: &Identifier, info: IdInfo) {
+        self.add_def(&info.src_loc);
         self.identifiers.push((id.clone(), info))
     }
     
@@ -110,11 +136,21 @@ impl TypeEnv {
         self.groups.insert(group.clone());
     }
 
-    fn process_err(&mut self, err: &mut XsError) {
+    /// Whether a warning starts in a statement that ignores it
+    fn is_stmt_ignored(&self, path: &PathBuf, err: &XsError) -> bool {
+        let start = err.span().start;
+        self.stmt_ignores.get(path).is_some_and(|stmt_ignores| {
+            stmt_ignores.iter().any(|(span, ignores)| {
+                span.start <= start && start < span.end && ignores.contains(&err.code())
+            })
+        })
+    }
+
+    fn process_err(&mut self, path: &PathBuf, err: &mut XsError) {
         if !err.is_warning() {
             return;
         }
-        let to_ignore = self.is_warning_ignored(err.code());
+        let to_ignore = self.is_warning_ignored(err.code()) || self.is_stmt_ignored(path, err);
         match err {
             XsError::Warning { ignored, .. } => *ignored = to_ignore,
             _ => unreachable!("is_warning check above"),
@@ -122,7 +158,7 @@ impl TypeEnv {
     }
     
     pub fn add_err(&mut self, path: &PathBuf, mut err: XsError) {
-        self.process_err(&mut err);
+        self.process_err(path, &mut err);
         
         self.errs
             .entry(path.clone())
@@ -132,7 +168,7 @@ impl TypeEnv {
     
     pub fn add_errs(&mut self, path: &PathBuf, mut errs: Vec<XsError>) {
         for err in errs.iter_mut() {
-            self.process_err(err);
+            self.process_err(path, err);
         }
         
         self.errs
@@ -165,6 +201,106 @@ impl TypeEnv {
         self.current_doc.take()
     }
     
+    fn add_def(&mut self, src_loc: &SrcLoc) {
+        // dummy definitions (like the return type of a function) don't have a location
+        if src_loc.file_path.as_os_str().is_empty() {
+            return;
+        }
+        let def = self.overrides.get(src_loc).unwrap_or(src_loc).clone();
+        self.refs.entry(def).or_default();
+    }
+
+    /// Records a use of the name defined at `def`. An expression may be checked more than once, so a use is only
+    /// recorded the first time
+    pub fn add_ref(&mut self, def: &SrcLoc, path: &PathBuf, span: &Span, kind: RefKind) {
+        if def.file_path.as_os_str().is_empty() {
+            return;
+        }
+        let def = self.overrides.get(def).unwrap_or(def).clone();
+        let src_loc = SrcLoc::from(path, span);
+        if def == src_loc {
+            return;
+        }
+        let refs = self.refs.entry(def).or_default();
+        if refs.iter().all(|ref_| ref_.src_loc != src_loc) {
+            refs.push(RefInfo::new(src_loc, kind));
+        }
+    }
+
+    /// Records that the mutable function defined at `def` is overridden at `override_`. Both are treated as the
+    /// same definition, so the uses of either are uses of the one that was overridden
+    pub fn add_override(&mut self, def: &SrcLoc, override_: &SrcLoc) {
+        let def = self.overrides.get(def).unwrap_or(def).clone();
+        self.add_ref(&def, &override_.file_path, &override_.span, RefKind::Write);
+        self.overrides.insert(override_.clone(), def);
+    }
+
+    /// Records a rule name in a string passed to a rule function of the prelude, like `xsEnableRule("name")`.
+    /// Rules are often enabled before they are defined, so the use is only added once the rule is
+    pub fn add_rule_ref(&mut self, name: &Identifier, path: &PathBuf, span: &Span) {
+        match self.identifiers.get(name) {
+            Some(IdInfo { type_: Type::Rule, src_loc, .. }) => {
+                let def = src_loc.clone();
+                self.add_ref(&def, path, span, RefKind::Read);
+            }
+            Some(_) => {}
+            None => self.pending_rule_refs.push((name.clone(), SrcLoc::from(path, span))),
+        }
+    }
+
+    /// Adds the uses of a rule that came before its definition
+    pub fn resolve_rule_refs(&mut self, name: &Identifier, def: &SrcLoc) {
+        let (refs, pending) = std::mem::take(&mut self.pending_rule_refs)
+            .into_iter()
+            .partition::<Vec<_>, _>(|(ref_name, _src_loc)| ref_name == name);
+        self.pending_rule_refs = pending;
+        for (_name, src_loc) in refs {
+            self.add_ref(def, &src_loc.file_path, &src_loc.span, RefKind::Read);
+        }
+    }
+
+    pub fn add_group_ref(&mut self, group: &str, path: &PathBuf, span: &Span) {
+        let src_loc = SrcLoc::from(path, span);
+        let refs = self.group_refs.entry(group.to_string()).or_default();
+        if !refs.contains(&src_loc) {
+            refs.push(src_loc);
+        }
+    }
+
+    /// The rule group named at a position in a file
+    pub fn group_at(&self, path: &PathBuf, pos: usize) -> Option<&String> {
+        self.group_refs.iter()
+            .find(|(_group, refs)| refs.iter().any(|src_loc| {
+                src_loc.file_path == *path && src_loc.span.start <= pos && pos <= src_loc.span.end
+            }))
+            .map(|(group, _refs)| group)
+    }
+
+    /// The name and info of the global or local defined at `def`. For an overridden mutable function, this is the
+    /// info of its last override
+    pub fn info_of(&self, def: &SrcLoc) -> Option<(&Identifier, &IdInfo)> {
+        self.identifiers.iter()
+            .chain(self.fn_envs.values().flatten().flat_map(|env| env.identifiers.iter()))
+            .find(|(_id, info)| info.src_loc == *def || self.overrides.get(&info.src_loc) == Some(def))
+    }
+
+    /// The uses of the name defined at `def`
+    pub fn refs(&self, def: &SrcLoc) -> &[RefInfo] {
+        let def = self.overrides.get(def).unwrap_or(def);
+        self.refs.get(def).map_or(&[], Vec::as_slice)
+    }
+
+    /// The definition of the name at a position in a file, whether the position is on the definition itself or on
+    /// one of its uses
+    pub fn def_at(&self, path: &PathBuf, pos: usize) -> Option<&SrcLoc> {
+        let is_at = |src_loc: &SrcLoc| {
+            src_loc.file_path == *path && src_loc.span.start <= pos && pos <= src_loc.span.end
+        };
+        self.refs.iter()
+            .find(|(def, refs)| is_at(def) || refs.iter().any(|ref_| is_at(&ref_.src_loc)))
+            .map(|(def, _refs)| def)
+    }
+
     pub fn local_ids(&self, path: &PathBuf, span: &Span) -> Option<&HashMap<Identifier, IdInfo>> {
         self.fn_envs
             .values()
@@ -174,4 +310,90 @@ impl TypeEnv {
                 loc.file_path == *path && contains(&loc.span, span)
             }).map(|env| &env.identifiers).next()
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{check, PATH};
+
+    /// The line of the definition of the name at the first occurrence of `at` in the source, and the lines and kinds
+    /// of its uses
+    fn refs_at(src: &str, at: &str) -> (usize, Vec<(usize, RefKind)>) {
+        let (type_env, _ast_cache) = check(src);
+        let line = |pos: usize| src[..pos].lines().count();
+        let pos = src.find(at).expect("Source contains the name");
+        let def = type_env.def_at(&PathBuf::from(PATH), pos).expect("Position is on a name");
+        let refs = type_env.refs(def).iter()
+            .map(|ref_| (line(ref_.src_loc.span.start), ref_.kind))
+            .collect();
+        (line(def.span.start), refs)
+    }
+
+    #[test]
+    fn locals_of_different_functions_are_different_names() {
+        let src = "\
+int total = 0;
+void f() {
+    int n = 1;
+    n++;
+    total = total + n;
+}
+void g() {
+    int n = 2;
+    total = n;
+}
+";
+        assert_eq!(refs_at(src, "n++"), (3, vec![(4, RefKind::Write), (5, RefKind::Read)]));
+        assert_eq!(refs_at(src, "n = 2"), (8, vec![(9, RefKind::Read)]));
+        assert_eq!(refs_at(src, "total = n"), (1, vec![(5, RefKind::Write), (5, RefKind::Read), (9, RefKind::Write)]));
+    }
+
+    #[test]
+    fn loop_variables_are_written() {
+        let src = "\
+void f() {
+    int i = 0;
+    for (i = 0; < 3) {
+        xsChatData(\"%d\", i);
+    }
+}
+";
+        let (def, refs) = refs_at(src, "i = 0;");
+        assert_eq!(def, 2);
+        assert!(refs.contains(&(3, RefKind::Write)), "{refs:?}");
+        assert!(refs.contains(&(4, RefKind::Read)), "{refs:?}");
+    }
+
+    #[test]
+    fn rules_are_used_by_name_before_their_definition() {
+        let src = "\
+void main() {
+    xsEnableRule(\"tick\");
+}
+rule tick group timers {
+    xsDisableRuleGroup(\"timers\");
+}
+";
+        assert_eq!(refs_at(src, "tick group"), (4, vec![(2, RefKind::Read)]));
+
+        let (type_env, _ast_cache) = check(src);
+        let path = PathBuf::from(PATH);
+        let group_pos = src.find("\"timers\"").expect("Group is named");
+        assert_eq!(type_env.group_at(&path, group_pos + 1).map(String::as_str), Some("timers"));
+    }
+
+    #[test]
+    fn overrides_share_the_uses_of_the_function() {
+        let src = "\
+mutable void onTick() {
+}
+void main() {
+    onTick();
+}
+void onTick() {
+}
+";
+        assert_eq!(refs_at(src, "onTick() {\n}\nvoid main"), (1, vec![(4, RefKind::Read), (6, RefKind::Write)]));
+        assert_eq!(refs_at(src, "onTick();"), (1, vec![(4, RefKind::Read), (6, RefKind::Write)]));
+    }
+}
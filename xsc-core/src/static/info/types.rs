@@ -39,10 +39,15 @@ use blake3::Hash;
 
 use crate::parsing::ast::AstNode;
 use crate::parsing::span::Spanned;
+use crate::r#static::info::ParseError;
 
 pub use types::*;
 
-pub type AstInfo = (Option<Hash>, (Vec<Spanned<AstNode>>, Vec<Spanned<String>>));
+/// The AST of a file along with its comments
+pub type ParsedSrc = (Vec<Spanned<AstNode>>, Vec<Spanned<String>>);
+
+/// The hash of the source that was parsed, its AST and any syntax errors found while parsing it
+pub type AstInfo = (Option<Hash>, ParsedSrc, Vec<ParseError>);
 
 pub type AstCache = AstMap<PathBuf, AstInfo>;
 
@@ -41,12 +41,36 @@ pub enum WarningKind {
     InfRecLim = 112,
     Deprecated = 113,
     SwappedParams = 114,
+    UnusedVar = 115,
+    DeadStore = 116,
+    UnusedParam = 117,
+    MissingReturn = 118,
+    Unreachable = 119,
+    UninitVar = 120,
+    DivByZero = 121,
+    NegIndex = 122,
+    IntOverflow = 123,
+    RuleOptRange = 124,
+    ExternConflict = 125,
 
     InvalidExternDecl = 999,
     UnknownWarningName = 1000,
 }
 
 impl XsError {
+    /// (code, kind, help) for every error that is not a warning, by the code returned by [`XsError::code`]
+    pub const ERRORS: [(u32, &'static str, &'static str); 9] = [
+        (0, "TypeError", "A function is called with more arguments than it has parameters"),
+        (1, "TypeError", "A value of one type is used where a different type is expected"),
+        (2, "TypeError", "A variable that is not a function is called"),
+        (3, "TypeError", "An operator is used with operand types it does not support"),
+        (4, "NameError", "A name is used without being defined or declared extern"),
+        (5, "NameError", "A name that is not extern is used outside of the file it is defined in"),
+        (6, "NameError", "A name is defined more than once. Only mutable functions may be overridden"),
+        (7, "UnresolvedInclude", "An included file could not be found in the include directories"),
+        (8, "SyntaxError", "The code is not valid XS"),
+    ];
+
     pub fn extra_arg(fn_name: &str, span: &Span) -> XsError {
         XsError::ExtraArg {
             fn_name: String::from(fn_name),
@@ -154,20 +178,14 @@ impl XsError {
 
     pub fn kind(&self) -> &str {
         match self {
-            XsError::ExtraArg { .. } => { "TypeError" }
-            XsError::TypeMismatch { .. } => { "TypeError" }
-            XsError::NotCallable { .. } => { "TypeError" }
-            XsError::OpMismatch { .. } => { "TypeError" }
-
-            XsError::UndefinedName { .. } => { "NameError" }
-            XsError::PrivateName { .. } => { "NameError" }
-            XsError::RedefinedName { .. } => { "NameError" }
-
-            XsError::UnresolvedInclude { .. } => { "UnresolvedInclude" }
-            
-            XsError::Syntax { .. } => { "SyntaxError" }
-
             XsError::Warning { kind: type_, .. } => { type_.as_str() }
+            _ => {
+                let code = self.code();
+                XsError::ERRORS.iter()
+                    .find(|(err_code, _kind, _help)| *err_code == code)
+                    .map(|(_code, kind, _help)| *kind)
+                    .expect("Every error code is listed in ERRORS")
+            }
         }
     }
 
@@ -199,6 +217,37 @@ impl XsError {
 }
 
 impl WarningKind {
+    pub const ALL: [WarningKind; 28] = [
+        WarningKind::TopStrInit,
+        WarningKind::DupCase,
+        WarningKind::DiscardedFn,
+        WarningKind::BreakPt,
+        WarningKind::UnusableClasses,
+        WarningKind::FirstOprArith,
+        WarningKind::CmpSilentCrash,
+        WarningKind::BoolCaseSilentCrash,
+        WarningKind::NumDownCast,
+        WarningKind::NoNumPromo,
+        WarningKind::FloatMod,
+        WarningKind::InfLoopLim,
+        WarningKind::InfRecLim,
+        WarningKind::Deprecated,
+        WarningKind::SwappedParams,
+        WarningKind::UnusedVar,
+        WarningKind::DeadStore,
+        WarningKind::UnusedParam,
+        WarningKind::MissingReturn,
+        WarningKind::Unreachable,
+        WarningKind::UninitVar,
+        WarningKind::DivByZero,
+        WarningKind::NegIndex,
+        WarningKind::IntOverflow,
+        WarningKind::RuleOptRange,
+        WarningKind::ExternConflict,
+        WarningKind::InvalidExternDecl,
+        WarningKind::UnknownWarningName,
+    ];
+
     pub fn as_u32(&self) -> u32 {
         self.clone() as u32
     }
@@ -220,11 +269,64 @@ impl WarningKind {
             WarningKind::InfRecLim           => "InfRecLim",
             WarningKind::Deprecated          => "Deprecated",
             WarningKind::SwappedParams       => "SwappedParams",
+            WarningKind::UnusedVar           => "UnusedVar",
+            WarningKind::DeadStore           => "DeadStore",
+            WarningKind::UnusedParam         => "UnusedParam",
+            WarningKind::MissingReturn       => "MissingReturn",
+            WarningKind::Unreachable         => "Unreachable",
+            WarningKind::UninitVar           => "UninitVar",
+            WarningKind::DivByZero           => "DivByZero",
+            WarningKind::NegIndex            => "NegIndex",
+            WarningKind::IntOverflow         => "IntOverflow",
+            WarningKind::RuleOptRange        => "RuleOptRange",
+            WarningKind::ExternConflict      => "ExternConflict",
             WarningKind::InvalidExternDecl   => "InvalidExternDecl",
             WarningKind::UnknownWarningName  => "UnknownWarningName",
         }
     }
 
+    /// A short explanation of the pitfall that this warning is about
+    pub fn help(&self) -> &str {
+        match self {
+            WarningKind::TopStrInit          => "Strings initialised at the top level of a file do not hold their value correctly. Assign them inside a function or rule instead",
+            WarningKind::DupCase             => "Only the first of multiple case or default blocks matching the same value will ever run",
+            WarningKind::DiscardedFn         => "The return value of a function call is ignored. Use @allow_discard in the function's doc comment if this is intentional",
+            WarningKind::BreakPt             => "Breakpoints pause XS execution irrecoverably and should not be left in shipped code",
+            WarningKind::UnusableClasses     => "Classes are parsed by XS but cannot be used for anything",
+            WarningKind::FirstOprArith       => "The resulting type of an arithmetic operation is the type of its first operand, so int + float yields an int",
+            WarningKind::CmpSilentCrash      => "Comparing these types causes XS to crash silently",
+            WarningKind::BoolCaseSilentCrash => "Using a bool in a case expression causes XS to crash silently",
+            WarningKind::NumDownCast         => "Assigning a float to an int truncates the value",
+            WarningKind::NoNumPromo          => "Ints and bools are not promoted to floats when passed as arguments to float parameters",
+            WarningKind::FloatMod            => "Modulo with floats does not return the fractional part",
+            WarningKind::InfLoopLim          => "Loops run for one extra iteration beyond the limit set by infiniteLoopLimit",
+            WarningKind::InfRecLim           => "Exceeding the limit set by infiniteRecursionLimit in a recursive function causes XS to crash silently",
+            WarningKind::Deprecated          => "The function or variable used is marked as deprecated",
+            WarningKind::SwappedParams       => "An argument's name matches a different parameter of the function, it may be passed in the wrong position",
+            WarningKind::UnusedVar           => "A local variable is declared but its value is never read",
+            WarningKind::DeadStore           => "A value is assigned to a variable but is overwritten, or the function ends, before it is ever read",
+            WarningKind::UnusedParam         => "A parameter is never read in the body of its function. Parameters whose names start with _ are not reported",
+            WarningKind::MissingReturn       => "Falling off the end of a function that has a return type returns garbage or causes XS to crash silently",
+            WarningKind::Unreachable         => "Code after a return, break, continue or goto, or in a branch whose condition is always false, never runs",
+            WarningKind::UninitVar           => "A local declared without an initial value holds an unspecified value until one is assigned to it",
+            WarningKind::DivByZero           => "Dividing by zero or taking a remainder modulo zero causes an error when the script runs",
+            WarningKind::NegIndex            => "Getting or setting an array element at a negative index fails",
+            WarningKind::IntOverflow         => "ints are 32 bits wide, and results outside their range wrap around",
+            WarningKind::RuleOptRange        => "Rule intervals can't be negative, and the minInterval of a rule can't be larger than its maxInterval",
+            WarningKind::ExternConflict      => "The uses of a name that is not defined in the project require different types for it",
+            WarningKind::InvalidExternDecl   => "An @extern doc comment could not be understood",
+            WarningKind::UnknownWarningName  => "An xsc-ignore comment names a warning that does not exist",
+        }
+    }
+
+    /// Whether this warning is about code that can be removed, which editors may show faded out
+    pub fn is_unnecessary(&self) -> bool {
+        matches!(
+            self,
+            WarningKind::UnusedVar | WarningKind::DeadStore | WarningKind::UnusedParam | WarningKind::Unreachable
+        )
+    }
+
     pub fn from_name(name: &str) -> Option<WarningKind> {
         match name {
             "TopStrInit"          => Some(WarningKind::TopStrInit),
@@ -242,6 +344,17 @@ impl WarningKind {
             "InfRecLim"           => Some(WarningKind::InfRecLim),
             "Deprecated"          => Some(WarningKind::Deprecated),
             "SwappedParams"       => Some(WarningKind::SwappedParams),
+            "UnusedVar"           => Some(WarningKind::UnusedVar),
+            "DeadStore"           => Some(WarningKind::DeadStore),
+            "UnusedParam"         => Some(WarningKind::UnusedParam),
+            "MissingReturn"       => Some(WarningKind::MissingReturn),
+            "Unreachable"         => Some(WarningKind::Unreachable),
+            "UninitVar"           => Some(WarningKind::UninitVar),
+            "DivByZero"           => Some(WarningKind::DivByZero),
+            "NegIndex"            => Some(WarningKind::NegIndex),
+            "IntOverflow"         => Some(WarningKind::IntOverflow),
+            "RuleOptRange"        => Some(WarningKind::RuleOptRange),
+            "ExternConflict"      => Some(WarningKind::ExternConflict),
 
             // InvalidExternDecl and UnknownWarningName cannot be ignored, so it is excluded here
             _                     => None
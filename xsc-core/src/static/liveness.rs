@@ -0,0 +1,221 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::parsing::ast::{AstNode, Body, Expr, Identifier, Param};
+use crate::parsing::span::Span;
+use crate::r#static::cfg::{Cfg, Elem};
+use crate::r#static::info::{WarningKind, XsError};
+
+/// A parameter or a variable declared in the body of a function or rule
+struct Local {
+    span: Span,
+    is_param: bool,
+    /// static locals keep their value between calls, so they are still live when the function returns
+    is_static: bool,
+}
+
+/// The locals that are live at the end of every block of a graph, i.e. whose current value may still be read
+/// on some path after the block
+fn live_out<'a>(
+    cfg: &Cfg<'a>,
+    tracked: &HashSet<&'a Identifier>,
+    live_at_exit: &HashSet<&'a Identifier>,
+) -> Vec<HashSet<&'a Identifier>> {
+    // locals that a block reads before writing, and the ones it writes
+    let (gens, kills): (Vec<_>, Vec<_>) = cfg.blocks.iter()
+        .map(|block| {
+            let (mut gen_, mut kill) = (HashSet::new(), HashSet::new());
+            for elem in block.elems.iter().rev() {
+                if let Some((name, _span)) = elem.write() {
+                    gen_.remove(name);
+                    kill.insert(name);
+                }
+                gen_.extend(elem.reads().into_iter().map(|(name, _span)| name).filter(|name| tracked.contains(name)));
+            }
+            (gen_, kill)
+        })
+        .unzip();
+
+    let mut live_in = vec![HashSet::new(); cfg.blocks.len()];
+    let mut live_out = vec![HashSet::new(); cfg.blocks.len()];
+    live_in[Cfg::EXIT] = live_at_exit.clone();
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for id in (0..cfg.blocks.len()).rev() {
+            if id == Cfg::EXIT {
+                continue;
+            }
+            let out = cfg.blocks[id].succs.iter()
+                .flat_map(|(to, _kind)| live_in[*to].iter().copied())
+                .collect::<HashSet<_>>();
+            let in_ = gens[id].iter()
+                .copied()
+                .chain(out.difference(&kills[id]).copied())
+                .collect::<HashSet<_>>();
+            if in_ != live_in[id] || out != live_out[id] {
+                live_in[id] = in_;
+                live_out[id] = out;
+                changed = true;
+            }
+        }
+    }
+    live_out
+}
+
+/// A value written by a literal initialiser is usually a default that is overwritten on purpose, so it is not
+/// reported as a dead store
+fn is_lit((expr, _span): &(Expr, Span)) -> bool {
+    match expr {
+        Expr::Literal(_) => true,
+        Expr::Neg(expr) | Expr::Paren(expr) => is_lit(expr),
+        _ => false,
+    }
+}
+
+/// Finds locals that are never read, parameters that are never read and values that are assigned to a local but
+/// are overwritten (or never read) before being read. `x++` does not count as reading `x`.
+///
+/// Parameters whose names start with `_` (like the `int _ = -1` convention for functions that must take a
+/// parameter) are not reported. Neither are the parameters of mutable functions, since overrides must keep the
+/// same signature, or the parameters of functions with an empty body
+pub fn chk_liveness(params: &[Param], body: &Body, is_mutable: bool) -> Vec<XsError> {
+    let cfg = Cfg::new(body);
+
+    let mut locals = HashMap::new();
+    for param in params {
+        let (name, span) = &param.name;
+        locals.insert(name, Local { span: *span, is_param: true, is_static: false });
+    }
+    for elem in cfg.blocks.iter().flat_map(|block| block.elems.iter()) {
+        if let Elem::Stmt((AstNode::VarDef { name: (name, span), is_static, .. }, _span)) = elem {
+            locals.insert(name, Local { span: *span, is_param: false, is_static: *is_static });
+        }
+    }
+
+    let read = cfg.blocks.iter()
+        .flat_map(|block| block.elems.iter())
+        .filter(|elem| !matches!(elem, Elem::Stmt((AstNode::PostDPlus(_) | AstNode::PostDMinus(_), _span))))
+        .flat_map(|elem| elem.reads())
+        .map(|(name, _span)| name)
+        .filter(|name| locals.contains_key(name))
+        .collect::<HashSet<_>>();
+
+    let mut errs = vec![];
+    let mut unread = locals.iter()
+        .filter(|(name, _local)| !read.contains(*name))
+        .collect::<Vec<_>>();
+    unread.sort_by_key(|(_name, local)| local.span.start);
+    for (name, local) in unread {
+        if !local.is_param {
+            errs.push(XsError::warning(
+                &local.span,
+                "The variable {0} is never read",
+                vec![&name.0],
+                WarningKind::UnusedVar,
+            ));
+        } else if !name.0.starts_with('_') && !is_mutable && !body.is_empty() {
+            errs.push(XsError::warning(
+                &local.span,
+                "The parameter {0} is never read",
+                vec![&name.0],
+                WarningKind::UnusedParam,
+            ));
+        }
+    }
+
+    let live_at_exit = locals.iter()
+        .filter(|(_name, local)| local.is_static)
+        .map(|(name, _local)| *name)
+        .collect();
+    let live_out = live_out(&cfg, &read, &live_at_exit);
+    let reachable = cfg.reachable();
+    for (id, block) in cfg.blocks.iter().enumerate() {
+        if !reachable[id] {
+            continue;
+        }
+        let mut live = live_out[id].clone();
+        for elem in block.elems.iter().rev() {
+            if let Some((name, _span)) = elem.write() {
+                let is_dead = read.contains(name) && !live.contains(name);
+                let span = match elem {
+                    Elem::Stmt((AstNode::VarAssign { .. }, span)) => Some(span),
+                    Elem::Stmt((AstNode::VarDef { value: Some(value), .. }, _span)) if !is_lit(value) => {
+                        Some(&value.1)
+                    }
+                    // steps of for loops and x++ are not reported
+                    _ => None,
+                };
+                if let (true, Some(span)) = (is_dead, span) {
+                    errs.push(XsError::warning(
+                        span,
+                        "The value assigned to {0} here is never read",
+                        vec![&name.0],
+                        WarningKind::DeadStore,
+                    ));
+                }
+                live.remove(name);
+            }
+            live.extend(elem.reads().into_iter().map(|(name, _span)| name).filter(|name| read.contains(name)));
+        }
+    }
+    errs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{fn_def, parse, warnings};
+
+    fn liveness(src: &str, is_mutable: bool) -> Vec<String> {
+        let ast = parse(src);
+        let (params, body, _span) = fn_def(&ast, "f");
+        warnings(&chk_liveness(params, body, is_mutable))
+    }
+
+    #[test]
+    fn unread_params_are_reported_unless_named_with_an_underscore() {
+        let src = "\
+void f(int a = 0, int _ = -1, int _b = 0) {
+    int x = 1;
+}
+";
+        assert_eq!(liveness(src, false), [
+            "UnusedParam: The parameter a is never read",
+            "UnusedVar: The variable x is never read",
+        ]);
+        assert_eq!(liveness(src, true), ["UnusedVar: The variable x is never read"]);
+        assert!(liveness("void f(int a = 0) {}", false).is_empty());
+    }
+
+    #[test]
+    fn static_locals_are_live_when_the_function_returns() {
+        let src = "\
+int f() {
+    static int count = 0;
+    int total = 0;
+    count = count + 1;
+    total = count * 2;
+    total = count;
+    return (total);
+}
+";
+        assert_eq!(liveness(src, false), ["DeadStore: The value assigned to total here is never read"]);
+    }
+
+    #[test]
+    fn values_overwritten_before_being_read_are_dead() {
+        let src = "\
+int f(int a = 0) {
+    int x = a * 2;
+    x = 3;
+    int y = 5;
+    y++;
+    if (a > 0) {
+        x = 4;
+    }
+    return (x + y);
+}
+";
+        assert_eq!(liveness(src, false), ["DeadStore: The value assigned to x here is never read"]);
+    }
+}
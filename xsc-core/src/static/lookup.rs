@@ -0,0 +1,322 @@
+use std::path::PathBuf;
+
+use chumsky::Parser;
+
+use crate::parsing::ast::{AstNode, Expr, Identifier, RuleOpt};
+use crate::parsing::lexer::{lexer, Token};
+use crate::parsing::span::{Span, Spanned};
+use crate::r#static::info::{IdInfo, TypeEnv};
+
+/// A name at a position in a file
+#[derive(Debug, Clone)]
+pub struct NameAt {
+    pub name: Identifier,
+    pub span: Span,
+}
+
+/// The function call whose arguments a position is in
+#[derive(Debug, Clone)]
+pub struct CallAt {
+    pub name: Identifier,
+    pub name_span: Span,
+    /// the index of the argument that the position is in
+    pub arg_idx: usize,
+}
+
+/// The cursor is still on a name right after its last character
+fn is_at(span: &Span, pos: usize) -> bool {
+    span.start <= pos && pos <= span.end
+}
+
+/// The tokens of a statement that failed to parse. The statement is being edited more often than not, so its
+/// tokens are all there is to go on
+fn tokens(src: &str, span: &Span) -> Vec<Spanned<Token>> {
+    let Some(stmt_src) = src.get(span.start..span.end) else {
+        return vec![];
+    };
+    lexer().parse(stmt_src)
+        .into_output()
+        .unwrap_or_default()
+        .into_iter()
+        .map(|(tok, tok_span)| (tok, Span::new(tok_span.start + span.start, tok_span.end + span.start)))
+        .collect()
+}
+
+fn name_of((name, span): &Spanned<Identifier>, pos: usize) -> Option<NameAt> {
+    is_at(span, pos).then(|| NameAt { name: name.clone(), span: *span })
+}
+
+fn name_in_expr((expr, span): &Spanned<Expr>, pos: usize) -> Option<NameAt> {
+    if !is_at(span, pos) {
+        return None;
+    }
+    match expr {
+        Expr::Literal(_) => None,
+        Expr::Identifier(name) => Some(NameAt { name: name.clone(), span: *span }),
+        Expr::Paren(expr) | Expr::Neg(expr) | Expr::Not(expr) => name_in_expr(expr, pos),
+        Expr::Vec { x, y, z } => [x, y, z].into_iter().find_map(|expr| name_in_expr(expr, pos)),
+        Expr::FnCall { name, args } => {
+            name_of(name, pos).or_else(|| args.iter().find_map(|arg| name_in_expr(arg, pos)))
+        }
+        Expr::Star(expr1, expr2) | Expr::FSlash(expr1, expr2) | Expr::PCent(expr1, expr2)
+        | Expr::Plus(expr1, expr2) | Expr::Minus(expr1, expr2) | Expr::Lt(expr1, expr2) | Expr::Gt(expr1, expr2)
+        | Expr::Le(expr1, expr2) | Expr::Ge(expr1, expr2) | Expr::Eq(expr1, expr2) | Expr::Ne(expr1, expr2)
+        | Expr::And(expr1, expr2) | Expr::Or(expr1, expr2) => {
+            name_in_expr(expr1, pos).or_else(|| name_in_expr(expr2, pos))
+        }
+    }
+}
+
+fn name_in_body(body: &[Spanned<AstNode>], src: &str, pos: usize) -> Option<NameAt> {
+    body.iter()
+        .filter(|(_stmt, span)| is_at(span, pos))
+        .find_map(|stmt| name_in_stmt(stmt, src, pos))
+}
+
+fn name_in_stmt((stmt, span): &Spanned<AstNode>, src: &str, pos: usize) -> Option<NameAt> {
+    match stmt {
+        AstNode::Error => tokens(src, span).into_iter().find_map(|(tok, span)| match tok {
+            Token::Identifier(name) if is_at(&span, pos) => Some(NameAt { name, span }),
+            _ => None,
+        }),
+        AstNode::Include(_) | AstNode::Break | AstNode::Continue | AstNode::Breakpoint => None,
+        AstNode::VarDef { name, value, .. } => {
+            name_of(name, pos).or_else(|| value.as_ref().and_then(|value| name_in_expr(value, pos)))
+        }
+        AstNode::VarAssign { name, value } => name_of(name, pos).or_else(|| name_in_expr(value, pos)),
+        AstNode::RuleDef { name, rule_opts, body: (body, _span) } => {
+            name_of(name, pos)
+                .or_else(|| rule_opts.iter().find_map(|(opt, _span)| match opt {
+                    RuleOpt::MinInterval(expr) | RuleOpt::MaxInterval(expr) | RuleOpt::Priority(expr) => {
+                        name_in_expr(expr, pos)
+                    }
+                    _ => None,
+                }))
+                .or_else(|| name_in_body(body, src, pos))
+        }
+        AstNode::FnDef { name, params, body: (body, _span), .. } => {
+            name_of(name, pos)
+                .or_else(|| params.iter().find_map(|param| {
+                    name_of(&param.name, pos).or_else(|| name_in_expr(&param.default, pos))
+                }))
+                .or_else(|| name_in_body(body, src, pos))
+        }
+        AstNode::Return(expr) => expr.as_ref().and_then(|expr| name_in_expr(expr, pos)),
+        AstNode::IfElse { condition, consequent: (consequent, _span), alternate } => {
+            name_in_expr(condition, pos)
+                .or_else(|| name_in_body(consequent, src, pos))
+                .or_else(|| alternate.as_ref().and_then(|(alternate, _span)| name_in_body(alternate, src, pos)))
+        }
+        AstNode::While { condition, body: (body, _span) } => {
+            name_in_expr(condition, pos).or_else(|| name_in_body(body, src, pos))
+        }
+        AstNode::For { var, condition, body: (body, _span) } => {
+            name_in_stmt(var, src, pos)
+                .or_else(|| name_in_expr(condition, pos))
+                .or_else(|| name_in_body(body, src, pos))
+        }
+        AstNode::Switch { clause, cases } => {
+            name_in_expr(clause, pos).or_else(|| cases.iter().find_map(|(case, (body, _span))| {
+                case.as_ref()
+                    .and_then(|case| name_in_expr(case, pos))
+                    .or_else(|| name_in_body(body, src, pos))
+            }))
+        }
+        AstNode::PostDPlus(name) | AstNode::PostDMinus(name) | AstNode::LabelDef(name) | AstNode::Goto(name)
+        | AstNode::Debug(name) => name_of(name, pos),
+        AstNode::Discarded(expr) => name_in_expr(expr, pos),
+        AstNode::Class { name, member_vars } => {
+            name_of(name, pos).or_else(|| name_in_body(member_vars, src, pos))
+        }
+    }
+}
+
+/// The name at a position in a file, from its AST. Statements that failed to parse are searched by their tokens
+/// instead
+pub fn name_at(ast: &[Spanned<AstNode>], src: &str, pos: usize) -> Option<NameAt> {
+    name_in_body(ast, src, pos)
+}
+
+fn call_in_expr((expr, span): &Spanned<Expr>, pos: usize) -> Option<CallAt> {
+    if !is_at(span, pos) {
+        return None;
+    }
+    match expr {
+        Expr::Literal(_) | Expr::Identifier(_) => None,
+        Expr::Paren(expr) | Expr::Neg(expr) | Expr::Not(expr) => call_in_expr(expr, pos),
+        Expr::Vec { x, y, z } => [x, y, z].into_iter().find_map(|expr| call_in_expr(expr, pos)),
+        Expr::FnCall { name: (name, name_span), args } => {
+            // a call in one of the arguments is closer to the position
+            if let Some(call) = args.iter().find_map(|arg| call_in_expr(arg, pos)) {
+                return Some(call);
+            }
+            // between the parentheses
+            (name_span.end < pos && pos < span.end).then(|| CallAt {
+                name: name.clone(),
+                name_span: *name_span,
+                arg_idx: args.iter().filter(|(_arg, arg_span)| arg_span.end < pos).count(),
+            })
+        }
+        Expr::Star(expr1, expr2) | Expr::FSlash(expr1, expr2) | Expr::PCent(expr1, expr2)
+        | Expr::Plus(expr1, expr2) | Expr::Minus(expr1, expr2) | Expr::Lt(expr1, expr2) | Expr::Gt(expr1, expr2)
+        | Expr::Le(expr1, expr2) | Expr::Ge(expr1, expr2) | Expr::Eq(expr1, expr2) | Expr::Ne(expr1, expr2)
+        | Expr::And(expr1, expr2) | Expr::Or(expr1, expr2) => {
+            call_in_expr(expr1, pos).or_else(|| call_in_expr(expr2, pos))
+        }
+    }
+}
+
+/// The innermost call that is still open before the position, e.g. `f(a, g(b), |`
+fn call_in_tokens(tokens: &[Spanned<Token>], pos: usize) -> Option<CallAt> {
+    // every open parenthesis, `None` for the ones that are not the start of a call
+    let mut calls: Vec<Option<CallAt>> = vec![];
+    let mut prev: Option<&Spanned<Token>> = None;
+    for tok in tokens.iter().take_while(|(_tok, span)| span.end <= pos) {
+        match &tok.0 {
+            Token::LParen => calls.push(match prev {
+                Some((Token::Identifier(name), name_span)) => Some(CallAt {
+                    name: name.clone(),
+                    name_span: *name_span,
+                    arg_idx: 0,
+                }),
+                _ => None,
+            }),
+            Token::RParen => {
+                calls.pop();
+            }
+            Token::Comma => {
+                if let Some(Some(call)) = calls.last_mut() {
+                    call.arg_idx += 1;
+                }
+            }
+            _ => {}
+        }
+        prev = Some(tok);
+    }
+    calls.into_iter().rev().flatten().next()
+}
+
+fn call_in_body(body: &[Spanned<AstNode>], src: &str, pos: usize) -> Option<CallAt> {
+    body.iter()
+        .enumerate()
+        .filter(|(idx, (stmt, span))| match stmt {
+            // the arguments of an unfinished call run on until the next statement, e.g. `f(a, |`
+            AstNode::Error => {
+                span.start <= pos && body.get(idx + 1).is_none_or(|(_next, next_span)| pos <= next_span.start)
+            }
+            _ => is_at(span, pos),
+        })
+        .find_map(|(_idx, stmt)| call_in_stmt(stmt, src, pos))
+}
+
+fn call_in_stmt((stmt, span): &Spanned<AstNode>, src: &str, pos: usize) -> Option<CallAt> {
+    match stmt {
+        AstNode::Error => call_in_tokens(&tokens(src, span), pos),
+        AstNode::VarDef { value, .. } => value.as_ref().and_then(|value| call_in_expr(value, pos)),
+        AstNode::VarAssign { value, .. } => call_in_expr(value, pos),
+        AstNode::RuleDef { body: (body, _span), .. } => call_in_body(body, src, pos),
+        AstNode::FnDef { body: (body, _span), .. } => call_in_body(body, src, pos),
+        AstNode::Return(expr) => expr.as_ref().and_then(|expr| call_in_expr(expr, pos)),
+        AstNode::IfElse { condition, consequent: (consequent, _span), alternate } => {
+            call_in_expr(condition, pos)
+                .or_else(|| call_in_body(consequent, src, pos))
+                .or_else(|| alternate.as_ref().and_then(|(alternate, _span)| call_in_body(alternate, src, pos)))
+        }
+        AstNode::While { condition, body: (body, _span) } => {
+            call_in_expr(condition, pos).or_else(|| call_in_body(body, src, pos))
+        }
+        AstNode::For { var, condition, body: (body, _span) } => {
+            call_in_stmt(var, src, pos)
+                .or_else(|| call_in_expr(condition, pos))
+                .or_else(|| call_in_body(body, src, pos))
+        }
+        AstNode::Switch { clause, cases } => {
+            call_in_expr(clause, pos).or_else(|| cases.iter().find_map(|(case, (body, _span))| {
+                case.as_ref()
+                    .and_then(|case| call_in_expr(case, pos))
+                    .or_else(|| call_in_body(body, src, pos))
+            }))
+        }
+        AstNode::Discarded(expr) => call_in_expr(expr, pos),
+        _ => None,
+    }
+}
+
+/// The function call whose arguments a position is in, from the AST of a file. Statements that failed to parse
+/// are searched by their tokens instead
+pub fn call_at(ast: &[Spanned<AstNode>], src: &str, pos: usize) -> Option<CallAt> {
+    call_in_body(ast, src, pos)
+}
+
+/// The definition that a name refers to. This is found through the uses of names recorded while type checking,
+/// so shadowed names resolve to the right definition. Names in statements that failed to parse were never type
+/// checked, and are looked up in the scope they are in instead
+pub fn binding<'a>(env: &'a TypeEnv, path: &PathBuf, name_at: &NameAt) -> Option<&'a IdInfo> {
+    let checked = env.def_at(path, name_at.span.start)
+        .and_then(|def| env.info_of(def))
+        .filter(|(name, _info)| **name == name_at.name)
+        .map(|(_name, info)| info);
+
+    checked.or_else(|| {
+        env.local_ids(path, &name_at.span)
+            .and_then(|ids| ids.get(&name_at.name))
+            .or_else(|| env.identifiers.get(&name_at.name))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::r#static::info::parse_src;
+
+    /// The source without the `|` that marks the position, and that position. Sources with syntax errors are
+    /// parsed as far as they go
+    fn lookup<T>(marked: &str, find: impl Fn(&[Spanned<AstNode>], &str, usize) -> Option<T>) -> Option<T> {
+        let pos = marked.find('|').expect("Source marks a position");
+        let src = marked.replacen('|', "", 1);
+        let ((ast, _comments), _errs) = parse_src(&src);
+        find(&ast, &src, pos)
+    }
+
+    fn name(marked: &str) -> Option<String> {
+        lookup(marked, name_at).map(|name_at| name_at.name.0)
+    }
+
+    fn call(marked: &str) -> Option<(String, usize)> {
+        lookup(marked, call_at).map(|call_at| (call_at.name.0, call_at.arg_idx))
+    }
+
+    #[test]
+    fn names_are_found_in_definitions_and_expressions() {
+        assert_eq!(name("int to|tal = 0;"), Some("total".to_string()));
+        assert_eq!(name("void f(int amo|unt = 0) {}"), Some("amount".to_string()));
+        assert_eq!(name("void f() { int x = (1 + -cou|nt) * 2; }"), Some("count".to_string()));
+        // the cursor is still on a name right after it
+        assert_eq!(name("void f() { g|(); }"), Some("g".to_string()));
+        assert_eq!(name("void f() { x = vector(1, 2, z|); }"), Some("z".to_string()));
+        assert_eq!(name("void f() { int x = 1|2; }"), None);
+        assert_eq!(name("vo|id f() {}"), None);
+    }
+
+    #[test]
+    fn names_in_statements_that_failed_to_parse_are_found_by_their_tokens() {
+        assert_eq!(name("void f() {\n    int x = fo|o(;\n}\n"), Some("foo".to_string()));
+    }
+
+    #[test]
+    fn calls_count_the_arguments_before_the_position() {
+        assert_eq!(call("void f() { g(|); }"), Some(("g".to_string(), 0)));
+        assert_eq!(call("void f() { g(1, |2); }"), Some(("g".to_string(), 1)));
+        // a call in an argument is closer than the call it is in
+        assert_eq!(call("void f() { g(1, h(2, |3)); }"), Some(("h".to_string(), 1)));
+        assert_eq!(call("void f() { g(1, h(2, 3), |4); }"), Some(("g".to_string(), 2)));
+        assert_eq!(call("void f() { g|(1); }"), None);
+        assert_eq!(call("void f() { g(1);| }"), None);
+    }
+
+    #[test]
+    fn unfinished_calls_are_found_by_their_tokens() {
+        assert_eq!(call("void f() {\n    g(1, h(2), |\n}\n"), Some(("g".to_string(), 2)));
+        assert_eq!(call("void f() {\n    g(1, h(|\n}\n"), Some(("h".to_string(), 0)));
+    }
+}
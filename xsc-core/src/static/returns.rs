@@ -0,0 +1,186 @@
+use std::collections::VecDeque;
+
+use crate::parsing::ast::{AstNode, Body, Type};
+use crate::parsing::span::Span;
+use crate::r#static::cfg::{const_cond, BlockId, Cfg, EdgeKind, Elem};
+use crate::r#static::info::{WarningKind, XsError};
+
+/// Checks that every path through the body of a non-void function ends in a `return`. If one doesn't, a warning
+/// is given at the closing brace of the body which describes the shortest such path by the branches it takes
+pub fn chk_returns(return_type: &Type, body: &Body, body_span: &Span) -> Option<XsError> {
+    if *return_type == Type::Void {
+        return None;
+    }
+    let cfg = Cfg::new(body);
+
+    // a breadth first search finds the shortest path to every block
+    let mut parents: Vec<Option<(BlockId, EdgeKind)>> = vec![None; cfg.blocks.len()];
+    let mut seen = vec![false; cfg.blocks.len()];
+    let mut queue = VecDeque::from([Cfg::ENTRY]);
+    seen[Cfg::ENTRY] = true;
+    let mut falls_off = None;
+    while let Some(id) = queue.pop_front() {
+        for (to, kind) in cfg.blocks[id].taken_succs() {
+            if to == Cfg::EXIT && !matches!(cfg.blocks[id].elems.last(), Some(Elem::Stmt((AstNode::Return(_), _span)))) {
+                falls_off = Some((id, kind));
+                break;
+            }
+            if !seen[to] {
+                seen[to] = true;
+                parents[to] = Some((id, kind));
+                queue.push_back(to);
+            }
+        }
+        if falls_off.is_some() {
+            break;
+        }
+    }
+    let (mut id, kind) = falls_off?;
+
+    let mut path = vec![(id, kind, Cfg::EXIT)];
+    while let Some((parent, kind)) = parents[id] {
+        path.push((parent, kind, id));
+        id = parent;
+    }
+    path.reverse();
+
+    let mut steps = vec![];
+    let mut keywords = vec![];
+    for (from, kind, to) in path {
+        let step = match (cfg.blocks[from].elems.last(), kind) {
+            // a loop like `while (true)` always takes the same branch, so it isn't a choice on the path
+            (Some(Elem::Cond(expr)), _kind) if const_cond(expr).is_some() => continue,
+            (Some(Elem::Cond((expr, _span))), EdgeKind::True) => {
+                keywords.push(expr.to_string());
+                "{} is true"
+            }
+            (Some(Elem::Cond((expr, _span))), EdgeKind::False) => {
+                keywords.push(expr.to_string());
+                "{} is false"
+            }
+            (Some(Elem::Case((expr, _span))), EdgeKind::True) => {
+                keywords.push(expr.to_string());
+                "case {} matches"
+            }
+            // the next case is tested, so only the default or the end of the switch is worth mentioning
+            (Some(Elem::Case(_)), EdgeKind::False) => match cfg.blocks[to].elems.first() {
+                Some(Elem::Case(_)) => continue,
+                _ => "no case matches",
+            },
+            (Some(Elem::Stmt((AstNode::Goto((label, _span)), _span2))), _kind) => {
+                keywords.push(label.to_string());
+                "jumping to {}"
+            }
+            (Some(Elem::Stmt((AstNode::Break, _span))), _kind) => {
+                keywords.push("break".to_string());
+                "leaving with {}"
+            }
+            _ => continue,
+        };
+        steps.push(step);
+    }
+
+    let mut msg = String::from("This function can reach its end without returning a value");
+    if !steps.is_empty() {
+        msg.push_str(" when ");
+    }
+    let mut idx = 0;
+    for (i, step) in steps.iter().enumerate() {
+        if i > 0 {
+            msg.push_str(", then ");
+        }
+        match step.split_once("{}") {
+            Some((before, after)) => {
+                msg.push_str(&format!("{before}{{{idx}}}{after}"));
+                idx += 1;
+            }
+            None => msg.push_str(step),
+        }
+    }
+
+    let brace = Span::new(body_span.end.saturating_sub(1), body_span.end);
+    Some(XsError::warning(
+        &brace,
+        &msg,
+        keywords.iter().map(String::as_str).collect(),
+        WarningKind::MissingReturn,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{fn_def, parse, warnings};
+
+    fn returns(src: &str) -> Vec<String> {
+        let ast = parse(src);
+        let (_params, body, body_span) = fn_def(&ast, "f");
+        warnings(&chk_returns(&Type::Int, body, body_span).into_iter().collect::<Vec<_>>())
+    }
+
+    #[test]
+    fn the_shortest_path_without_a_return_is_described() {
+        assert_eq!(returns("\
+int f(int a = 0) {
+    if (a > 0) {
+        return (1);
+    } else if (a < -5) {
+        return (2);
+    }
+}
+"), ["MissingReturn: This function can reach its end without returning a value when a > 0 is false, then \
+a < -5 is false"]);
+        assert_eq!(returns("\
+int f(int a = 0) {
+    switch (a) {
+        case 1: {
+            return (1);
+        }
+        case 2: {
+            return (2);
+        }
+    }
+}
+"), ["MissingReturn: This function can reach its end without returning a value when no case matches"]);
+        assert_eq!(returns("\
+int f(int a = 0) {
+    while (true) {
+        if (a > 3) {
+            break;
+        }
+        a++;
+    }
+}
+"), ["MissingReturn: This function can reach its end without returning a value when a > 3 is true, then \
+leaving with break"]);
+    }
+
+    #[test]
+    fn functions_that_always_return_are_fine() {
+        assert!(returns("\
+int f(int a = 0) {
+    if (a > 0) {
+        return (1);
+    }
+    return (0);
+}
+").is_empty());
+        assert!(returns("\
+int f(int a = 0) {
+    while (true) {
+        a++;
+    }
+}
+").is_empty());
+    }
+
+    #[test]
+    fn the_warning_is_at_the_closing_brace() {
+        let src = "int f() {\n}\n";
+        let ast = parse(src);
+        let (_params, body, body_span) = fn_def(&ast, "f");
+        let err = chk_returns(&Type::Int, body, body_span).expect("f doesn't return");
+        assert_eq!(&src[err.span().start..err.span().end], "}");
+        assert!(chk_returns(&Type::Void, body, body_span).is_none());
+    }
+}
@@ -0,0 +1,644 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fmt::{Display, Formatter};
+
+use crate::parsing::ast::{AstNode, Body, Expr, Identifier, Literal, Param, Type};
+use crate::parsing::span::{Span, Spanned};
+use crate::r#static::cfg::{BlockId, Cfg, EdgeKind, Elem};
+use crate::r#static::info::{WarningKind, XsError};
+
+const INT_MIN: f64 = i32::MIN as f64;
+const INT_MAX: f64 = i32::MAX as f64;
+
+/// A block's state is widened once it has been updated this many times, so that loops are analysed in a finite
+/// number of steps
+const WIDEN_AFTER: usize = 3;
+
+/// The number of times the states are recomputed after widening, see [`chk_sign`]
+const NARROW_ROUNDS: usize = 3;
+
+/// The range of values that an `int` or `float` can have. Bounds may be infinite
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Interval {
+    pub lo: f64,
+    pub hi: f64,
+}
+
+impl Display for Interval {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "[{}, {}]", self.lo, self.hi)
+    }
+}
+
+/// `0 * inf` is `0` here, since a bound of zero means that the value really is zero
+fn mul(a: f64, b: f64) -> f64 {
+    if a == 0.0 || b == 0.0 { 0.0 } else { a * b }
+}
+
+impl Interval {
+    pub const TOP: Interval = Interval { lo: f64::NEG_INFINITY, hi: f64::INFINITY };
+
+    pub fn val(val: f64) -> Self {
+        Interval { lo: val, hi: val }
+    }
+
+    pub fn is_top(&self) -> bool {
+        *self == Interval::TOP
+    }
+
+    pub fn contains(&self, val: f64) -> bool {
+        self.lo <= val && val <= self.hi
+    }
+
+    pub fn join(&self, other: &Interval) -> Self {
+        Interval { lo: self.lo.min(other.lo), hi: self.hi.max(other.hi) }
+    }
+
+    /// Bounds that keep growing are pushed to infinity
+    fn widen(&self, new: &Interval) -> Self {
+        Interval {
+            lo: if new.lo < self.lo { f64::NEG_INFINITY } else { self.lo },
+            hi: if new.hi > self.hi { f64::INFINITY } else { self.hi },
+        }
+    }
+
+    /// `int` conversion truncates towards zero
+    fn trunc(&self) -> Self {
+        Interval { lo: self.lo.trunc(), hi: self.hi.trunc() }
+    }
+
+    fn from_corners(corners: [f64; 4]) -> Self {
+        if corners.iter().any(|val| val.is_nan()) {
+            return Interval::TOP;
+        }
+        Interval {
+            lo: corners.iter().copied().fold(f64::INFINITY, f64::min),
+            hi: corners.iter().copied().fold(f64::NEG_INFINITY, f64::max),
+        }
+    }
+
+    fn neg(&self) -> Self {
+        Interval { lo: -self.hi, hi: -self.lo }
+    }
+
+    fn add(&self, other: &Interval) -> Self {
+        let (lo, hi) = (self.lo + other.lo, self.hi + other.hi);
+        if lo.is_nan() || hi.is_nan() { Interval::TOP } else { Interval { lo, hi } }
+    }
+
+    fn sub(&self, other: &Interval) -> Self {
+        self.add(&other.neg())
+    }
+
+    fn mul(&self, other: &Interval) -> Self {
+        Interval::from_corners([
+            mul(self.lo, other.lo), mul(self.lo, other.hi), mul(self.hi, other.lo), mul(self.hi, other.hi),
+        ])
+    }
+
+    fn div(&self, other: &Interval) -> Self {
+        if other.contains(0.0) {
+            return Interval::TOP;
+        }
+        Interval::from_corners([
+            self.lo / other.lo, self.lo / other.hi, self.hi / other.lo, self.hi / other.hi,
+        ])
+    }
+
+    /// The remainder has the sign of the dividend and is smaller than the divisor
+    fn rem(&self, other: &Interval) -> Self {
+        if other.contains(0.0) {
+            return Interval::TOP;
+        }
+        let bound = other.lo.abs().max(other.hi.abs()) - 1.0;
+        Interval {
+            lo: if self.lo >= 0.0 { 0.0 } else { self.lo.max(-bound) },
+            hi: if self.hi <= 0.0 { 0.0 } else { self.hi.min(bound) },
+        }
+    }
+
+    fn meet(&self, other: &Interval) -> Option<Self> {
+        let meet = Interval { lo: self.lo.max(other.lo), hi: self.hi.min(other.hi) };
+        (meet.lo <= meet.hi).then_some(meet)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Num {
+    Int,
+    Float,
+}
+
+impl Num {
+    fn of(type_: &Type) -> Option<Num> {
+        match type_ {
+            Type::Int => Some(Num::Int),
+            Type::Float => Some(Num::Float),
+            _ => None,
+        }
+    }
+
+    /// Converts a value to this type
+    fn conv(&self, (val, num): (Interval, Option<Num>)) -> Interval {
+        match (self, num) {
+            (Num::Int, Some(Num::Float)) => val.trunc(),
+            (_, Some(_)) => val,
+            (_, None) => Interval::TOP,
+        }
+    }
+}
+
+/// The intervals of the locals at a point. Locals that are missing may have any value
+type State<'a> = HashMap<&'a Identifier, Interval>;
+
+fn join<'a>(state1: &State<'a>, state2: &State<'a>) -> State<'a> {
+    state1.iter()
+        .filter_map(|(name, val1)| state2.get(name).map(|val2| (*name, val1.join(val2))))
+        .collect()
+}
+
+fn widen<'a>(old: &State<'a>, new: &State<'a>) -> State<'a> {
+    new.iter()
+        .map(|(name, val)| (*name, old.get(name).map_or(*val, |old_val| old_val.widen(val))))
+        .collect()
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Cmp {
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Eq,
+    Ne,
+}
+
+impl Cmp {
+    fn negate(self) -> Self {
+        match self {
+            Cmp::Lt => Cmp::Ge,
+            Cmp::Le => Cmp::Gt,
+            Cmp::Gt => Cmp::Le,
+            Cmp::Ge => Cmp::Lt,
+            Cmp::Eq => Cmp::Ne,
+            Cmp::Ne => Cmp::Eq,
+        }
+    }
+
+    /// `a op b` is `b flip(op) a`
+    fn flip(self) -> Self {
+        match self {
+            Cmp::Lt => Cmp::Gt,
+            Cmp::Le => Cmp::Ge,
+            Cmp::Gt => Cmp::Lt,
+            Cmp::Ge => Cmp::Le,
+            Cmp::Eq | Cmp::Ne => self,
+        }
+    }
+}
+
+fn unparen((expr, _span): &Spanned<Expr>) -> &Expr {
+    match expr {
+        Expr::Paren(expr) => unparen(expr),
+        _ => expr,
+    }
+}
+
+/// Interprets a function or rule body over intervals
+struct Analysis<'a> {
+    /// the `int` and `float` locals that are tracked
+    locals: HashMap<&'a Identifier, Num>,
+    statics: HashSet<&'a Identifier>,
+    /// warnings are only collected once the intervals are final
+    report: bool,
+    errs: Vec<XsError>,
+    reported: HashSet<(usize, usize, u32)>,
+}
+
+impl<'a> Analysis<'a> {
+    fn warn(&mut self, span: &Span, msg: &str, keywords: Vec<&str>, kind: WarningKind) {
+        if self.report && self.reported.insert((span.start, span.end, kind.as_u32())) {
+            self.errs.push(XsError::warning(span, msg, keywords, kind));
+        }
+    }
+
+    fn eval(&mut self, (expr, span): &Spanned<Expr>, state: &State) -> (Interval, Option<Num>) {
+        match expr {
+            Expr::Literal(Literal::Int(val)) => (Interval::val(*val as f64), Some(Num::Int)),
+            Expr::Literal(Literal::Float(val)) => (Interval::val(*val), Some(Num::Float)),
+            Expr::Literal(_) => (Interval::TOP, None),
+            Expr::Identifier(name) => match self.locals.get(name) {
+                Some(num) => (state.get(name).copied().unwrap_or(Interval::TOP), Some(*num)),
+                None => (Interval::TOP, None),
+            },
+            Expr::Paren(expr) => self.eval(expr, state),
+            Expr::Neg(expr) => {
+                let (val, num) = self.eval(expr, state);
+                (val.neg(), num)
+            }
+            Expr::Vec { x, y, z } => {
+                for component in [x, y, z] {
+                    self.eval(component, state);
+                }
+                (Interval::TOP, None)
+            }
+            Expr::FnCall { name: (name, _span), args } => {
+                let vals = args.iter().map(|arg| self.eval(arg, state).0).collect::<Vec<_>>();
+                self.chk_index(name, args, &vals, span);
+                (Interval::TOP, None)
+            }
+
+            Expr::Star(expr1, expr2) | Expr::Plus(expr1, expr2) | Expr::Minus(expr1, expr2)
+            | Expr::FSlash(expr1, expr2) | Expr::PCent(expr1, expr2) => {
+                self.eval_arith(expr, expr1, expr2, span, state)
+            }
+
+            Expr::Not(expr) => {
+                self.eval(expr, state);
+                (Interval::TOP, None)
+            }
+            Expr::Lt(expr1, expr2) | Expr::Gt(expr1, expr2) | Expr::Le(expr1, expr2) | Expr::Ge(expr1, expr2)
+            | Expr::Eq(expr1, expr2) | Expr::Ne(expr1, expr2) | Expr::And(expr1, expr2) | Expr::Or(expr1, expr2) => {
+                self.eval(expr1, state);
+                self.eval(expr2, state);
+                (Interval::TOP, None)
+            }
+        }
+    }
+
+    /// The type of an arithmetic expression is the type of its first operand, and the second operand is converted
+    /// to it
+    fn eval_arith(
+        &mut self,
+        expr: &Expr,
+        expr1: &Spanned<Expr>,
+        expr2: &Spanned<Expr>,
+        span: &Span,
+        state: &State,
+    ) -> (Interval, Option<Num>) {
+        let (val1, num1) = self.eval(expr1, state);
+        let (val2, num2) = self.eval(expr2, state);
+
+        if matches!(expr, Expr::FSlash(..) | Expr::PCent(..)) && val2.contains(0.0) && !val2.is_top() {
+            let range = val2.to_string();
+            if val2 == Interval::val(0.0) {
+                self.warn(&expr2.1, "This divisor is always zero", vec![], WarningKind::DivByZero);
+            } else {
+                self.warn(&expr2.1, "This divisor may be zero, it is in {0}", vec![&range], WarningKind::DivByZero);
+            }
+        }
+
+        let Some(num1) = num1 else {
+            return (Interval::TOP, None);
+        };
+        let val2 = num1.conv((val2, num2));
+        let val = match expr {
+            Expr::Star(..) => val1.mul(&val2),
+            Expr::Plus(..) => val1.add(&val2),
+            Expr::Minus(..) => val1.sub(&val2),
+            Expr::FSlash(..) if num1 == Num::Int => val1.div(&val2).trunc(),
+            Expr::FSlash(..) => val1.div(&val2),
+            // float modulo doesn't give the fractional part, so nothing is assumed about it
+            Expr::PCent(..) if num1 == Num::Int => val1.rem(&val2),
+            _ => Interval::TOP,
+        };
+        if num1 == Num::Float {
+            return (val, Some(num1));
+        }
+
+        let overflows = |bound: f64| bound.is_finite() && !(INT_MIN..=INT_MAX).contains(&bound);
+        if !overflows(val.lo) && !overflows(val.hi) {
+            return (val, Some(num1));
+        }
+        let range = val.to_string();
+        if val.lo > INT_MAX || val.hi < INT_MIN {
+            self.warn(span, "This overflows the 32-bit range of ints, it is in {0}", vec![&range], WarningKind::IntOverflow);
+        } else {
+            self.warn(span, "This may overflow the 32-bit range of ints, it is in {0}", vec![&range], WarningKind::IntOverflow);
+        }
+        (Interval { lo: INT_MIN, hi: INT_MAX }, Some(num1))
+    }
+
+    /// Array indices must not be negative. The index of the array functions defaults to `-1`
+    fn chk_index(&mut self, name: &Identifier, args: &[Spanned<Expr>], vals: &[Interval], span: &Span) {
+        let is_indexed = (name.0.starts_with("xsArrayGet") || name.0.starts_with("xsArraySet"))
+            && name.0 != "xsArrayGetSize";
+        if !is_indexed || args.is_empty() {
+            return;
+        }
+        let (Some((_arg, arg_span)), Some(val)) = (args.get(1), vals.get(1)) else {
+            self.warn(span, "No index is given, so the default index of -1 is used", vec![], WarningKind::NegIndex);
+            return;
+        };
+        if val.is_top() || val.lo >= 0.0 {
+            return;
+        }
+        let range = val.to_string();
+        if val.hi < 0.0 {
+            self.warn(arg_span, "This index is always negative, it is in {0}", vec![&range], WarningKind::NegIndex);
+        } else {
+            self.warn(arg_span, "This index may be negative, it is in {0}", vec![&range], WarningKind::NegIndex);
+        }
+    }
+
+    fn assign(&mut self, name: &'a Identifier, value: Option<&Spanned<Expr>>, state: &mut State<'a>) {
+        let val = value.map(|value| self.eval(value, state));
+        let Some(num) = self.locals.get(name).copied() else {
+            return;
+        };
+        match val {
+            // statics keep the value from the last call, and the initialiser only runs in the first one
+            _ if self.statics.contains(name) => state.remove(name),
+            Some(val) => state.insert(name, num.conv(val)),
+            None => state.remove(name),
+        };
+    }
+
+    fn step(&mut self, name: &'a Identifier, by: f64, state: &mut State<'a>) {
+        if !self.locals.contains_key(name) {
+            return;
+        }
+        if let Some(val) = state.get_mut(name) {
+            *val = val.add(&Interval::val(by));
+        }
+    }
+
+    fn transfer(&mut self, cfg: &Cfg<'a>, id: BlockId, state: &mut State<'a>) {
+        for elem in &cfg.blocks[id].elems {
+            match elem {
+                Elem::Cond(expr) | Elem::Case(expr) => {
+                    self.eval(expr, state);
+                }
+                Elem::ForStep((AstNode::VarAssign { name: (name, _span), .. }, _span2)) => {
+                    self.step(name, for_step(cfg, id), state);
+                }
+                Elem::ForStep(_) => {}
+                Elem::Stmt((stmt, _span)) => match stmt {
+                    AstNode::VarDef { name: (name, _span), value, .. } => self.assign(name, value.as_ref(), state),
+                    AstNode::VarAssign { name: (name, _span), value } => self.assign(name, Some(value), state),
+                    AstNode::PostDPlus((name, _span)) => self.step(name, 1.0, state),
+                    AstNode::PostDMinus((name, _span)) => self.step(name, -1.0, state),
+                    AstNode::Return(Some(expr)) | AstNode::Discarded(expr) => {
+                        self.eval(expr, state);
+                    }
+                    _ => {}
+                },
+            }
+        }
+    }
+
+    /// The states that a block passes on to each of its successors, given the state at its start
+    fn outs(&mut self, cfg: &Cfg<'a>, id: BlockId, mut state: State<'a>) -> Vec<(BlockId, State<'a>)> {
+        self.transfer(cfg, id, &mut state);
+        let cond = match cfg.blocks[id].elems.last() {
+            Some(Elem::Cond(expr)) => Some(*expr),
+            _ => None,
+        };
+        cfg.blocks[id].taken_succs()
+            .filter_map(|(to, kind)| {
+                let out = match (cond, kind) {
+                    (Some(cond), EdgeKind::True) => self.refine(state.clone(), cond, true)?,
+                    (Some(cond), EdgeKind::False) => self.refine(state.clone(), cond, false)?,
+                    _ => state.clone(),
+                };
+                Some((to, out))
+            })
+            .collect()
+    }
+
+    /// Narrows the intervals of the locals compared in a condition, for the edge where it is `truth`. `None` if
+    /// the edge can't be taken
+    fn refine(&mut self, mut state: State<'a>, cond: &'a Spanned<Expr>, truth: bool) -> Option<State<'a>> {
+        let (cmp, expr1, expr2) = match unparen(cond) {
+            Expr::Not(expr) => return self.refine(state, expr, !truth),
+            Expr::And(expr1, expr2) if truth => {
+                let state = self.refine(state, expr1, true)?;
+                return self.refine(state, expr2, true);
+            }
+            Expr::Or(expr1, expr2) if !truth => {
+                let state = self.refine(state, expr1, false)?;
+                return self.refine(state, expr2, false);
+            }
+            Expr::Lt(expr1, expr2) => (Cmp::Lt, expr1, expr2),
+            Expr::Le(expr1, expr2) => (Cmp::Le, expr1, expr2),
+            Expr::Gt(expr1, expr2) => (Cmp::Gt, expr1, expr2),
+            Expr::Ge(expr1, expr2) => (Cmp::Ge, expr1, expr2),
+            Expr::Eq(expr1, expr2) => (Cmp::Eq, expr1, expr2),
+            Expr::Ne(expr1, expr2) => (Cmp::Ne, expr1, expr2),
+            _ => return Some(state),
+        };
+        let cmp = if truth { cmp } else { cmp.negate() };
+
+        let val1 = self.eval(expr1, &state).0;
+        let val2 = self.eval(expr2, &state).0;
+        if let Expr::Identifier(name) = unparen(expr1) {
+            self.constrain(&mut state, name, cmp, &val2)?;
+        }
+        if let Expr::Identifier(name) = unparen(expr2) {
+            self.constrain(&mut state, name, cmp.flip(), &val1)?;
+        }
+        Some(state)
+    }
+
+    /// Narrows a local `x` so that `x cmp other` holds
+    fn constrain(&self, state: &mut State<'a>, name: &Identifier, cmp: Cmp, other: &Interval) -> Option<()> {
+        let Some((name, num)) = self.locals.get_key_value(name) else {
+            return Some(());
+        };
+        let val = state.get(name).copied().unwrap_or(Interval::TOP);
+        let step = if *num == Num::Int { 1.0 } else { 0.0 };
+        let new_val = match cmp {
+            Cmp::Lt => val.meet(&Interval { lo: f64::NEG_INFINITY, hi: other.hi - step })?,
+            Cmp::Le => val.meet(&Interval { lo: f64::NEG_INFINITY, hi: other.hi })?,
+            Cmp::Gt => val.meet(&Interval { lo: other.lo + step, hi: f64::INFINITY })?,
+            Cmp::Ge => val.meet(&Interval { lo: other.lo, hi: f64::INFINITY })?,
+            Cmp::Eq => val.meet(other)?,
+            // only an int at the edge of its interval can be excluded
+            Cmp::Ne if *num == Num::Int && other.lo == other.hi => {
+                let excluded = other.lo;
+                let lo = if val.lo == excluded { val.lo + 1.0 } else { val.lo };
+                let hi = if val.hi == excluded { val.hi - 1.0 } else { val.hi };
+                (lo <= hi).then_some(Interval { lo, hi })?
+            }
+            Cmp::Ne => val,
+        };
+        if !new_val.is_top() {
+            state.insert(name, new_val);
+        }
+        Some(())
+    }
+}
+
+/// A `for` loop counts up if its condition is `<` or `<=` and down otherwise. The step block flows back into the
+/// block with the condition
+fn for_step(cfg: &Cfg, step_block: BlockId) -> f64 {
+    let header = cfg.blocks[step_block].succs.first().map(|(to, _kind)| *to);
+    match header.and_then(|header| cfg.blocks[header].elems.last()) {
+        Some(Elem::Cond((Expr::Gt(..) | Expr::Ge(..), _span))) => -1.0,
+        _ => 1.0,
+    }
+}
+
+/// Tracks the intervals, and with them the signs, of the `int` and `float` locals of a function or rule body
+/// through its control flow graph. Warns about division or modulo by a value that may be zero, array indices that
+/// may be negative and `int` arithmetic that may overflow 32 bits.
+///
+/// Values that nothing is known about (globals, function results and parameters) are not reported, only the ones
+/// with at least one known bound
+pub fn chk_sign(params: &[Param], body: &Body) -> Vec<XsError> {
+    let cfg = Cfg::new(body);
+
+    let mut analysis = Analysis {
+        locals: HashMap::new(),
+        statics: HashSet::new(),
+        report: false,
+        errs: vec![],
+        reported: HashSet::new(),
+    };
+    for param in params {
+        if let Some(num) = Num::of(&param.type_) {
+            analysis.locals.insert(&param.name.0, num);
+        }
+    }
+    for elem in cfg.blocks.iter().flat_map(|block| block.elems.iter()) {
+        match elem {
+            Elem::Stmt((AstNode::VarDef { type_, name: (name, _span), is_static, .. }, _span2)) => {
+                if let Some(num) = Num::of(type_) {
+                    analysis.locals.insert(name, num);
+                    if *is_static {
+                        analysis.statics.insert(name);
+                    }
+                }
+            }
+            // the loop variable is always an int, and can't be assigned to in the loop
+            Elem::ForStep((AstNode::VarAssign { name: (name, _span), .. }, _span2)) => {
+                analysis.locals.insert(name, Num::Int);
+            }
+            _ => {}
+        }
+    }
+
+    let mut ins: Vec<Option<State>> = vec![None; cfg.blocks.len()];
+    let mut updates = vec![0; cfg.blocks.len()];
+    ins[Cfg::ENTRY] = Some(HashMap::new());
+    let mut queue = VecDeque::from([Cfg::ENTRY]);
+    while let Some(id) = queue.pop_front() {
+        let Some(state) = ins[id].clone() else {
+            continue;
+        };
+        for (to, out) in analysis.outs(&cfg, id, state) {
+            let new_in = match &ins[to] {
+                None => out,
+                Some(old) if updates[to] >= WIDEN_AFTER => widen(old, &join(old, &out)),
+                Some(old) => join(old, &out),
+            };
+            if ins[to].as_ref() != Some(&new_in) {
+                ins[to] = Some(new_in);
+                updates[to] += 1;
+                queue.push_back(to);
+            }
+        }
+    }
+
+    // widening overshoots, e.g. the counter of a loop that counts down loses its lower bound in the loop body even
+    // though the condition gives it one. Recomputing every state from the current ones a few more times narrows
+    // them back down
+    for _ in 0..NARROW_ROUNDS {
+        let mut new_ins: Vec<Option<State>> = vec![None; cfg.blocks.len()];
+        new_ins[Cfg::ENTRY] = Some(HashMap::new());
+        for (id, state) in ins.iter().enumerate() {
+            let Some(state) = state.clone() else {
+                continue;
+            };
+            for (to, out) in analysis.outs(&cfg, id, state) {
+                new_ins[to] = Some(match &new_ins[to] {
+                    None => out,
+                    Some(old) => join(old, &out),
+                });
+            }
+        }
+        if new_ins == ins {
+            break;
+        }
+        ins = new_ins;
+    }
+
+    analysis.report = true;
+    for (id, state) in ins.into_iter().enumerate() {
+        if let Some(mut state) = state {
+            analysis.transfer(&cfg, id, &mut state);
+        }
+    }
+    let mut errs = analysis.errs;
+    errs.sort_by_key(|err| err.span().start);
+    errs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{fn_def, parse, warnings};
+
+    fn sign(src: &str) -> Vec<String> {
+        let ast = parse(src);
+        let (params, body, _span) = fn_def(&ast, "f");
+        warnings(&chk_sign(params, body))
+    }
+
+    #[test]
+    fn loop_counters_keep_the_bounds_of_their_condition() {
+        assert!(sign("\
+void f(int arr = -1) {
+    for (i = 10; > 0) {
+        int q = 100 / i;
+    }
+    for (j = 0; < 10) {
+        xsArraySetInt(arr, j, 100 % (j + 1));
+    }
+    int n = 10;
+    while (n > 0) {
+        n--;
+        xsArrayGetInt(arr, n);
+    }
+    int k = 0;
+    while (k < 5) {
+        k++;
+        int r = 10 / k;
+    }
+}
+").is_empty());
+    }
+
+    #[test]
+    fn values_that_may_be_zero_or_negative_are_reported() {
+        assert_eq!(sign("\
+void f(int arr = -1, int p = 0) {
+    int z = 0;
+    int a = 5 / z;
+    int m = p % 3;
+    float b = 1.0 / m;
+    for (i = 5; >= 0) {
+        int c = 10 / i;
+    }
+    xsArrayGetInt(arr, z - 1);
+    xsArrayGetInt(arr);
+}
+"), [
+            "DivByZero: This divisor is always zero",
+            "DivByZero: This divisor may be zero, it is in [-2, 2]",
+            "DivByZero: This divisor may be zero, it is in [0, 5]",
+            "NegIndex: This index is always negative, it is in [-1, -1]",
+            "NegIndex: No index is given, so the default index of -1 is used",
+        ]);
+    }
+
+    #[test]
+    fn ints_that_leave_32_bits_are_reported() {
+        assert_eq!(sign("\
+void f() {
+    int big = 2147483647;
+    int over = big + 1;
+    float fine = 2147483647.0 + 1.0;
+}
+"), ["IntOverflow: This overflows the 32-bit range of ints, it is in [2147483648, 2147483648]"]);
+    }
+}
@@ -1,10 +1,19 @@
 use std::path::PathBuf;
 
-use crate::parsing::ast::{Expr, Literal, Type};
-use crate::parsing::span::Spanned;
-use crate::r#static::info::{IdInfo, TypeEnv, WarningKind, XsError};
+use crate::parsing::ast::{Expr, Identifier, Literal, Type};
+use crate::parsing::span::{Span, Spanned};
+use crate::r#static::info::{IdInfo, RefKind, TypeEnv, WarningKind, XsError};
 use crate::r#static::type_check::util::{arith_op, logical_op, reln_op, chk_int_lit, chk_num_lit, type_cmp, get_broken_path_name};
 
+/// Prelude functions that are given the name of a rule as their first argument
+const RULE_FNS: [&str; 6] = [
+    "xsDisableRule", "xsEnableRule", "xsIsRuleEnabled", "xsSetRuleMaxInterval", "xsSetRuleMinInterval",
+    "xsSetRulePriority",
+];
+
+/// Prelude functions that are given the name of a rule group as their first argument
+const GROUP_FNS: [&str; 3] = ["xsDisableRuleGroup", "xsEnableRuleGroup", "xsIsRuleGroupEnabled"];
+
 pub fn xs_tc_expr(
     path: &PathBuf,
     (expr, span): &Spanned<Expr>,
@@ -24,6 +33,7 @@ pub fn xs_tc_expr(
             type_env.add_err(path, XsError::undefined_name(id, span));
             return None;
         };
+        type_env.add_ref(&src_loc, path, span, RefKind::Read);
         if type_.is_concrete() {
             let current_file = get_broken_path_name(path);
             let def_file = get_broken_path_name(&src_loc.file_path);
@@ -47,19 +57,35 @@ pub fn xs_tc_expr(
     }
     Expr::Paren(expr) => { xs_tc_expr(path, expr, type_env) }
     Expr::Vec { x, y, z } => {
+        // vector components can only be literals or consts, which are not type checked as expressions
+        for (expr, span) in [x, y, z].map(|component| component.as_ref()) {
+            if let Expr::Identifier(id) = expr && let Some(IdInfo { src_loc, .. }) = type_env.get(id) {
+                type_env.add_ref(&src_loc, path, span, RefKind::Read);
+            }
+        }
         type_env.add_errs(path, chk_num_lit(x, type_env, false, true));
         type_env.add_errs(path, chk_num_lit(y, type_env, false, true));
         type_env.add_errs(path, chk_num_lit(z, type_env, false, true));
         Some(Type::Vec)
     }
     Expr::FnCall { name: (name, name_span), args } => {
-        let Some(IdInfo { type_, doc, .. }) = type_env.get(name) else {
+        let Some(IdInfo { type_, doc, src_loc, .. }) = type_env.get(name) else {
             type_env.add_err(path, XsError::undefined_name(name, name_span));
             for arg in args {
                 xs_tc_expr(path, arg, type_env);
             }
             return None;
         };
+        type_env.add_ref(&src_loc, path, name_span, RefKind::Read);
+        if let Some((Expr::Literal(Literal::Str(lit)), lit_span)) = args.first() && lit.len() > 2 {
+            // the name inside the quotes
+            let (arg, arg_span) = (&lit[1..lit.len()-1], Span::new(lit_span.start + 1, lit_span.end - 1));
+            if RULE_FNS.contains(&name.0.as_str()) {
+                type_env.add_rule_ref(&Identifier::new(arg), path, &arg_span);
+            } else if GROUP_FNS.contains(&name.0.as_str()) {
+                type_env.add_group_ref(arg, path, &arg_span);
+            }
+        }
         if let Some(reason) = doc.deprecation_reason() {
             type_env.add_err(path, XsError::warning(
                 name_span,
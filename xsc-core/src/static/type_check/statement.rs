@@ -3,6 +3,7 @@ use std::path::PathBuf;
 
 use chumsky::container::Container;
 use crate::doxygen::Doc;
+use crate::interp::Value;
 use crate::parsing::ast::{AstNode, RuleOpt, Expr, Identifier, Literal, Type};
 use crate::parsing::span::{Span, Spanned};
 use crate::r#static::info::{
@@ -12,12 +13,19 @@ use crate::r#static::info::{
     FnInfo,
     IdInfo,
     Modifiers,
+    RefKind,
     SrcCacheRef,
     SrcLoc,
     TypeEnv,
     WarningKind,
     XsError,
 };
+use crate::r#static::const_eval::const_eval;
+use crate::r#static::liveness::chk_liveness;
+use crate::r#static::returns::chk_returns;
+use crate::r#static::sign::chk_sign;
+use crate::r#static::uninit::chk_uninit;
+use crate::r#static::unreachable::chk_unreachable;
 use crate::r#static::type_check::expression::xs_tc_expr;
 use crate::r#static::type_check::util::{chk_rule_opt, combine_results, get_broken_path_name, type_cmp};
 
@@ -107,11 +115,11 @@ pub fn xs_tc_stmt(
         _ => break,
     }};
     let (doc, _temp_ignore) = docstr
-        .map(|(com, span)| {
+        .map(|(com, com_span)| {
             match Doc::parse(com) {
                 Err(err) => {
                     type_env.add_err(path, XsError::warning(
-                        span,
+                        com_span,
                         &format!("Unrecognised warning name '{}'", err),
                         vec![],
                         WarningKind::UnknownWarningName,
@@ -119,7 +127,7 @@ pub fn xs_tc_stmt(
                     (Doc::None, None)
                 }
                 Ok(Doc::Ignore(ignores)) => {
-                    (Doc::None, Some(type_env.temp_ignore(ignores)))
+                    (Doc::None, Some(type_env.temp_ignore(path, span, ignores)))
                 }
                 Ok(doc) => (doc, None)
             }
@@ -282,8 +290,10 @@ match stmt {
                     vec!["const", "static"],
                 ));
             } else if *is_const {
+                let value = const_eval(spanned_expr, type_env).map(|value| value.cast(type_));
                 let info = type_env.get_mut(name).expect("Value inserted above");
                 info.init = Some(expr.clone());
+                info.value = value;
             }
         }
 
@@ -365,6 +375,7 @@ match stmt {
             ));
             return Ok(());
         };
+        type_env.add_ref(&src_loc, path, name_span, RefKind::Write);
 
         if type_.is_concrete() {
             let current_file = get_broken_path_name(path);
@@ -409,6 +420,8 @@ match stmt {
         }
 
         let mut opt_spans: HashMap<&str, &Span> = HashMap::with_capacity(rule_opts.len());
+        // the folded values of the int options
+        let mut opt_vals: HashMap<&str, (i32, &Span)> = HashMap::with_capacity(rule_opts.len());
 
         for (opt, opt_span) in rule_opts {
             let mut opt_expr_name = None;
@@ -435,16 +448,23 @@ match stmt {
                     opt_expr_name = Some((expr, "priority"));
                     chk_rule_opt("priority", opt_span, &mut opt_spans, path, type_env);
                 }
-                RuleOpt::Group((grp, _grp_span)) => {
+                RuleOpt::Group((grp, grp_span)) => {
                     if chk_rule_opt("group", opt_span, &mut opt_spans, path, type_env) {
                         type_env.add_group(grp)
                     }
+                    // groups can be named with a string too
+                    match grp.strip_prefix('"').and_then(|grp| grp.strip_suffix('"')) {
+                        Some(name) => type_env.add_group_ref(name, path, &Span::new(grp_span.start + 1, grp_span.end - 1)),
+                        None => type_env.add_group_ref(grp, path, grp_span),
+                    }
                 }
             }
             if let Some(((expr, span), name)) = opt_expr_name && let Expr::Identifier(id) = expr { 'consts: {
                 let Some(id_info) = type_env.get(id) else {
                     break 'consts;
                 };
+                // rule options are not type checked as expressions
+                type_env.add_ref(&id_info.src_loc, path, span, RefKind::Read);
                 if !id_info.modifiers.is_const() {
                     type_env.add_err(path, XsError::syntax(
                         span,
@@ -453,6 +473,44 @@ match stmt {
                     ));
                 }
             }}
+            let Some((spanned_expr, name)) = opt_expr_name else {
+                continue;
+            };
+            match const_eval(spanned_expr, type_env) {
+                Some(Value::Int(val)) => {
+                    if val < 0 && name != "priority" {
+                        type_env.add_err(path, XsError::warning(
+                            &spanned_expr.1,
+                            "{0} can't be negative, but this is {1}",
+                            vec![name, &val.to_string()],
+                            WarningKind::RuleOptRange,
+                        ));
+                    }
+                    opt_vals.insert(name, (val, &spanned_expr.1));
+                }
+                Some(value) => {
+                    type_env.add_err(path, XsError::type_mismatch(
+                        &value.type_().to_string(),
+                        "int",
+                        &spanned_expr.1,
+                        None,
+                    ));
+                }
+                None => {}
+            }
+        }
+
+        if let (Some(&(min, min_span)), Some(&(max, max_span))) = (opt_vals.get("minInterval"), opt_vals.get("maxInterval"))
+            && min > max {
+            let (min, max) = (min.to_string(), max.to_string());
+            for span in [min_span, max_span] {
+                type_env.add_err(path, XsError::warning(
+                    span,
+                    "The {0} of this rule ({1}) is larger than its {2} ({3})",
+                    vec!["minInterval", &min, "maxInterval", &max],
+                    WarningKind::RuleOptRange,
+                ));
+            }
         }
 
         match type_env.get(name) {
@@ -473,6 +531,7 @@ match stmt {
                         doc
                     )
                 );
+                type_env.resolve_rule_refs(name, &SrcLoc::from(path, name_span));
             }
         };
 
@@ -501,6 +560,11 @@ match stmt {
             type_env.set_fn_env(env);
         };
 
+        type_env.add_errs(path, chk_liveness(&[], body, false));
+        type_env.add_errs(path, chk_unreachable(body));
+        type_env.add_errs(path, chk_uninit(body));
+        type_env.add_errs(path, chk_sign(&[], body));
+
         results
     }
     AstNode::FnDef {
@@ -615,6 +679,7 @@ match stmt {
                     Some("Type signatures of mutable functions must be the same"),
                 ))
             } else {
+                type_env.add_override(&og_src_loc, &SrcLoc::from(path, name_span));
                 type_env.set_global(name, IdInfo::new(
                     Type::Fn { is_mutable: *is_mutable, type_sign: new_type_sign },
                     SrcLoc::from(path, name_span),
@@ -652,6 +717,14 @@ match stmt {
         if let Some(env) = old_env {
             type_env.set_fn_env(env);
         };
+
+        type_env.add_errs(path, chk_liveness(params, body, *is_mutable));
+        type_env.add_errs(path, chk_unreachable(body));
+        type_env.add_errs(path, chk_uninit(body));
+        type_env.add_errs(path, chk_sign(params, body));
+        if let Some(err) = chk_returns(return_type, body, body_span) {
+            type_env.add_err(path, err);
+        }
         
         results
     },
@@ -798,6 +871,8 @@ match stmt {
             }
             actual_type = id_info.type_.clone();
             do_set = false;
+            let def = id_info.src_loc.clone();
+            type_env.add_ref(&def, path, name_span, RefKind::Write);
         };
         if actual_type != Type::Int {
             type_env.add_err(path, XsError::type_mismatch(
@@ -866,6 +941,7 @@ match stmt {
 
         let mut default_span: Option<&Span> = None;
         let mut case_spans: HashMap<&Expr, &Span> = HashMap::with_capacity(cases.len());
+        let mut case_vals: HashMap<i32, (&Expr, &Span)> = HashMap::with_capacity(cases.len());
 
         let mut results = Vec::with_capacity(cases.len());
         
@@ -902,19 +978,33 @@ match stmt {
             if let Some(clause_type) = xs_tc_expr(path, spanned_case_expr, type_env) {
                 type_env.add_errs(path, type_cmp(&Type::Int, &clause_type, case_expr_span, false, true, false));
             }
-            if let Some(&og_span) = case_spans.get(case_expr) {
+            // cases are compared to the int clause, so they match the same value if they fold to the same int
+            let case_val = const_eval(spanned_case_expr, type_env).map(|value| value.as_int());
+            let og_case = match case_val {
+                Some(val) => case_vals.get(&val).map(|&(og_expr, og_span)| (og_expr, og_span, val.to_string())),
+                None => case_spans.get(case_expr).map(|&og_span| (case_expr, og_span, String::new())),
+            };
+            if let Some((og_expr, og_span, val)) = og_case {
+                // the value is only worth mentioning when the cases are written differently
+                let (msg, keywords) = if og_expr == case_expr {
+                    ("Only the first case will run on a match", vec![])
+                } else {
+                    ("Only the first case will run on a match, both are {0}", vec![val.as_str()])
+                };
                 type_env.add_err(path, XsError::warning(
                     og_span,
-                    "Only the first case will run on a match",
-                    vec![],
+                    msg,
+                    keywords.clone(),
                     WarningKind::DupCase,
                 ));
                 type_env.add_err(path, XsError::warning(
                     &spanned_case_expr.1,
-                    "Only the first case will run on a match",
-                    vec![],
+                    msg,
+                    keywords,
                     WarningKind::DupCase,
                 ));
+            } else if let Some(val) = case_val {
+                case_vals.insert(val, (case_expr, case_expr_span));
             } else {
                 case_spans.push((case_expr, case_expr_span));
             }
@@ -931,10 +1021,11 @@ match stmt {
             ));
         }
 
-        let Some(IdInfo { type_: id_type, .. }) = type_env.get(id) else {
+        let Some(IdInfo { type_: id_type, src_loc, .. }) = type_env.get(id) else {
             type_env.add_err(path, XsError::undefined_name(id, id_span));
             return Ok(());
         };
+        type_env.add_ref(&src_loc, path, id_span, RefKind::Write);
 
         if let Type::Int | Type::Float = id_type {
             return Ok(());
@@ -956,10 +1047,11 @@ match stmt {
             ));
         }
 
-        let Some(IdInfo { type_: id_type, .. }) = type_env.get(id) else {
+        let Some(IdInfo { type_: id_type, src_loc, .. }) = type_env.get(id) else {
             type_env.add_err(path, XsError::undefined_name(id, id_span));
             return Ok(());
         };
+        type_env.add_ref(&src_loc, path, id_span, RefKind::Write);
 
         if let Type::Int | Type::Float = id_type {
             return Ok(());
@@ -1024,10 +1116,11 @@ match stmt {
                 vec!["goto"],
             ));
         }
-        let Some(IdInfo { type_: id_type, .. }) = type_env.get(id) else {
+        let Some(IdInfo { type_: id_type, src_loc, .. }) = type_env.get(id) else {
             type_env.add_err(path, XsError::undefined_name(id, id_span));
             return Ok(());
         };
+        type_env.add_ref(&src_loc, path, id_span, RefKind::Read);
 
         type_env.add_errs(path, type_cmp(&Type::Label, &id_type, id_span, false, false, false));
 
@@ -1077,10 +1170,11 @@ match stmt {
                 vec!["dbg"],
             ));
         }
-        let Some(IdInfo { type_: id_type, .. }) = type_env.get(id) else {
+        let Some(IdInfo { type_: id_type, src_loc, .. }) = type_env.get(id) else {
             type_env.add_err(path, XsError::undefined_name(id, id_span));
             return Ok(());
         };
+        type_env.add_ref(&src_loc, path, id_span, RefKind::Read);
 
         let (Type::Fn { .. } | Type::Rule | Type::Class | Type::Label) = id_type else {
             return Ok(());
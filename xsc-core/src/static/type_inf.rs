@@ -0,0 +1,424 @@
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+
+use crate::parsing::ast::{AstNode, Expr, Identifier, Literal, Type};
+use crate::parsing::span::{Span, Spanned};
+use crate::r#static::info::{SrcLoc, TypeEnv, WarningKind, XsError};
+
+/// A use of an undefined name that tells us something about its type
+#[derive(Debug, Clone)]
+pub struct ExternUse {
+    pub type_: Type,
+    pub src_loc: SrcLoc,
+    /// the type is only suggested by the other operand of an operator, instead of being required by a declaration,
+    /// parameter, assignment or condition
+    pub is_hint: bool,
+}
+
+/// The type inferred for a name that is used but never defined, like the globals that a scenario's triggers inject
+/// into a script
+#[derive(Debug, Clone)]
+pub struct ExternInference {
+    pub name: Identifier,
+    pub type_: Type,
+    pub uses: Vec<ExternUse>,
+}
+
+/// ints, floats and bools are converted into each other implicitly, so using one in place of another is not a
+/// conflict
+fn compatible(type1: &Type, type2: &Type) -> bool {
+    let is_num = |type_: &Type| matches!(type_, Type::Int | Type::Float | Type::Bool);
+    type1 == type2 || is_num(type1) && is_num(type2)
+}
+
+impl ExternInference {
+    /// The `@extern` doc comment that declares this name with its inferred type
+    pub fn decl(&self) -> String {
+        decl(&self.name, &self.type_)
+    }
+
+    /// Every type that a use of this name suggests, the inferred one first
+    pub fn candidates(&self) -> Vec<&Type> {
+        let mut candidates = vec![&self.type_];
+        for use_ in &self.uses {
+            if !candidates.contains(&&use_.type_) {
+                candidates.push(&use_.type_);
+            }
+        }
+        candidates
+    }
+
+    /// Warnings for the uses that don't agree with the inferred type, by the file they are in
+    pub fn conflicts(&self) -> Vec<(PathBuf, XsError)> {
+        let (inferred, name) = (self.type_.to_string(), self.name.to_string());
+        self.uses.iter()
+            .filter(|use_| !compatible(&use_.type_, &self.type_))
+            .map(|use_| (use_.src_loc.file_path.clone(), XsError::warning(
+                &use_.src_loc.span,
+                "{0} is used as a value of type {1} here, but its other uses give it the type {2}",
+                vec![&name, &use_.type_.to_string(), &inferred],
+                WarningKind::ExternConflict,
+            )))
+            .collect()
+    }
+}
+
+/// The `@extern` doc comment that declares a name with a type
+pub fn decl(name: &Identifier, type_: &Type) -> String {
+    format!("/** @extern {} {} */", type_, name)
+}
+
+fn unparen((expr, span): &Spanned<Expr>) -> (&Expr, &Span) {
+    match expr {
+        Expr::Paren(expr) => unparen(expr),
+        _ => (expr, span),
+    }
+}
+
+/// Walks the ASTs of a project and collects the uses of undefined names
+struct Inferrer<'a> {
+    type_env: &'a TypeEnv,
+    path: &'a PathBuf,
+    /// the spans of the names that the type checker could not find in the current file
+    undefined: HashSet<(usize, usize)>,
+    /// the locals of the function or rule being walked
+    locals: HashMap<&'a Identifier, Type>,
+    in_proc: bool,
+    return_type: Option<&'a Type>,
+    uses: HashMap<Identifier, Vec<ExternUse>>,
+    /// names in the order they are first used
+    order: Vec<Identifier>,
+}
+
+impl<'a> Inferrer<'a> {
+    fn is_undefined(&self, span: &Span) -> bool {
+        self.undefined.contains(&(span.start, span.end))
+    }
+
+    fn add_use(&mut self, name: &Identifier, span: &Span, type_: Type, is_hint: bool) {
+        if !type_.is_concrete() {
+            return;
+        }
+        let uses = self.uses.entry(name.clone()).or_insert_with(|| {
+            self.order.push(name.clone());
+            vec![]
+        });
+        uses.push(ExternUse { type_, src_loc: SrcLoc::from(self.path, span), is_hint });
+    }
+
+    fn type_of_name(&self, name: &Identifier) -> Option<Type> {
+        if let Some(type_) = self.locals.get(name) {
+            return Some(type_.clone());
+        }
+        self.type_env.get(name).map(|info| info.type_).filter(Type::is_concrete)
+    }
+
+    /// The type of an expression, if it can be told without type checking it again
+    fn type_of(&self, (expr, _span): &Spanned<Expr>) -> Option<Type> {
+        match expr {
+            Expr::Literal(Literal::Int(_)) => Some(Type::Int),
+            Expr::Literal(Literal::Float(_)) => Some(Type::Float),
+            Expr::Literal(Literal::Bool(_)) => Some(Type::Bool),
+            Expr::Literal(Literal::Str(_)) => Some(Type::Str),
+            Expr::Identifier(name) => self.type_of_name(name),
+            Expr::Paren(expr) | Expr::Neg(expr) => self.type_of(expr),
+            Expr::Vec { .. } => Some(Type::Vec),
+            Expr::FnCall { name: (name, _span), .. } => match self.type_env.get(name)?.type_ {
+                Type::Fn { type_sign, .. } => type_sign.last().map(|(_name, type_)| type_.clone()),
+                _ => None,
+            },
+            Expr::Not(_) | Expr::Lt(..) | Expr::Gt(..) | Expr::Le(..) | Expr::Ge(..) | Expr::Eq(..) | Expr::Ne(..)
+            | Expr::And(..) | Expr::Or(..) => Some(Type::Bool),
+            Expr::Plus(expr1, expr2) => match (self.type_of(expr1), self.type_of(expr2)) {
+                (Some(Type::Str), _) | (_, Some(Type::Str)) => Some(Type::Str),
+                (type1, _) => type1,
+            },
+            // the result of arithmetic has the type of its first operand
+            Expr::Star(expr1, _) | Expr::FSlash(expr1, _) | Expr::PCent(expr1, _) | Expr::Minus(expr1, _) => {
+                self.type_of(expr1)
+            }
+        }
+    }
+
+    /// Walks an expression that is used where a value of the given type is expected
+    fn expect(&mut self, spanned_expr: &'a Spanned<Expr>, type_: Option<Type>) {
+        if let (Expr::Identifier(name), span) = unparen(spanned_expr)
+            && self.is_undefined(span)
+            && let Some(type_) = type_ {
+            self.add_use(name, span, type_, false);
+        }
+        self.expr(spanned_expr);
+    }
+
+    /// An undefined operand of a binary operator is likely to have the type of the other operand
+    fn hint(&mut self, expr1: &'a Spanned<Expr>, expr2: &'a Spanned<Expr>, is_plus: bool) {
+        for (expr, other) in [(expr1, expr2), (expr2, expr1)] {
+            let (Expr::Identifier(name), span) = unparen(expr) else {
+                continue;
+            };
+            if !self.is_undefined(span) {
+                continue;
+            }
+            match self.type_of(other) {
+                // anything can be concatenated with a string
+                Some(Type::Str) if is_plus => {}
+                Some(type_) => self.add_use(name, span, type_, true),
+                None => {}
+            }
+        }
+    }
+
+    fn expr(&mut self, (expr, _span): &'a Spanned<Expr>) {
+        match expr {
+            Expr::Literal(_) | Expr::Identifier(_) => {}
+            Expr::Paren(expr) | Expr::Neg(expr) => self.expr(expr),
+            Expr::Not(expr) => self.expect(expr, Some(Type::Bool)),
+            Expr::Vec { x, y, z } => {
+                for component in [x, y, z] {
+                    self.expect(component, Some(Type::Float));
+                }
+            }
+            Expr::FnCall { name: (name, _span), args } => {
+                let params = match self.type_env.get(name).map(|info| info.type_) {
+                    Some(Type::Fn { type_sign, .. }) => type_sign,
+                    _ => vec![],
+                };
+                // the last entry of the signature is the return type
+                for (i, arg) in args.iter().enumerate() {
+                    let param = params.get(i).filter(|_| i + 1 < params.len());
+                    self.expect(arg, param.map(|(_name, type_)| type_.clone()));
+                }
+            }
+            Expr::And(expr1, expr2) | Expr::Or(expr1, expr2) => {
+                self.expect(expr1, Some(Type::Bool));
+                self.expect(expr2, Some(Type::Bool));
+            }
+            Expr::Plus(expr1, expr2) => {
+                self.hint(expr1, expr2, true);
+                self.expr(expr1);
+                self.expr(expr2);
+            }
+            Expr::Star(expr1, expr2) | Expr::FSlash(expr1, expr2) | Expr::PCent(expr1, expr2)
+            | Expr::Minus(expr1, expr2) | Expr::Lt(expr1, expr2) | Expr::Gt(expr1, expr2) | Expr::Le(expr1, expr2)
+            | Expr::Ge(expr1, expr2) | Expr::Eq(expr1, expr2) | Expr::Ne(expr1, expr2) => {
+                self.hint(expr1, expr2, false);
+                self.expr(expr1);
+                self.expr(expr2);
+            }
+        }
+    }
+
+    fn body(&mut self, body: &'a [Spanned<AstNode>]) {
+        for stmt in body {
+            self.stmt(stmt);
+        }
+    }
+
+    fn stmt(&mut self, (stmt, _span): &'a Spanned<AstNode>) {
+        match stmt {
+            AstNode::VarDef { type_, name: (name, _span), value, .. } => {
+                if self.in_proc {
+                    self.locals.insert(name, type_.clone());
+                }
+                if let Some(value) = value {
+                    self.expect(value, Some(type_.clone()));
+                }
+            }
+            AstNode::VarAssign { name: (name, span), value } => {
+                if self.is_undefined(span) {
+                    if let Some(type_) = self.type_of(value) {
+                        self.add_use(name, span, type_, false);
+                    }
+                    self.expr(value);
+                } else {
+                    self.expect(value, self.type_of_name(name));
+                }
+            }
+            AstNode::RuleDef { body: (body, _span), .. } => {
+                self.in_proc = true;
+                self.body(body);
+                self.in_proc = false;
+                self.locals.clear();
+            }
+            AstNode::FnDef { return_type, params, body: (body, _span), .. } => {
+                self.locals = params.iter()
+                    .map(|param| (&param.name.0, param.type_.clone()))
+                    .collect();
+                self.return_type = Some(return_type);
+                self.in_proc = true;
+                self.body(body);
+                self.in_proc = false;
+                self.locals.clear();
+                self.return_type = None;
+            }
+            AstNode::Return(Some(expr)) => self.expect(expr, self.return_type.cloned()),
+            AstNode::IfElse { condition, consequent: (consequent, _span), alternate } => {
+                self.expect(condition, Some(Type::Bool));
+                self.body(consequent);
+                if let Some((alternate, _span)) = alternate {
+                    self.body(alternate);
+                }
+            }
+            AstNode::While { condition, body: (body, _span) } => {
+                self.expect(condition, Some(Type::Bool));
+                self.body(body);
+            }
+            AstNode::For { var, condition, body: (body, _span) } => {
+                if let (AstNode::VarAssign { name: (name, _span), value }, _span2) = var.as_ref() {
+                    self.locals.insert(name, Type::Int);
+                    self.expect(value, Some(Type::Int));
+                }
+                self.expr(condition);
+                self.body(body);
+            }
+            AstNode::Switch { clause, cases } => {
+                self.expect(clause, Some(Type::Int));
+                for (case, (body, _span)) in cases {
+                    if let Some(case) = case {
+                        self.expect(case, Some(Type::Int));
+                    }
+                    self.body(body);
+                }
+            }
+            AstNode::PostDPlus((name, span)) | AstNode::PostDMinus((name, span)) if self.is_undefined(span) => {
+                self.add_use(name, span, Type::Int, false);
+            }
+            AstNode::Discarded(expr) => self.expr(expr),
+            _ => {}
+        }
+    }
+}
+
+/// Infers the types of the names that are used in a project but not defined anywhere in it, from the way they are
+/// used. `type_env` is the environment the project was type checked in, and is used to find the undefined names
+/// (from its errors) and the types of everything else. `asts` are the ASTs of the files in the project.
+///
+/// Every use that requires a type counts twice as much as one that only hints at a type (like `x + 1`), and the
+/// type with the most votes wins. Uses that aren't compatible with the winner are reported by
+/// [`ExternInference::conflicts`]
+pub fn infer_externs<'a>(
+    type_env: &TypeEnv,
+    asts: impl IntoIterator<Item = (&'a PathBuf, &'a [Spanned<AstNode>])>,
+) -> Vec<ExternInference> {
+    let mut undefined: HashMap<&PathBuf, HashSet<(usize, usize)>> = HashMap::new();
+    for (path, errs) in type_env.errs() {
+        for err in errs {
+            if let XsError::UndefinedName { span, .. } = err {
+                undefined.entry(path).or_default().insert((span.start, span.end));
+            }
+        }
+    }
+
+    let mut uses = HashMap::new();
+    let mut order = vec![];
+    for (path, ast) in asts {
+        let Some(file_undefined) = undefined.remove(path) else {
+            continue;
+        };
+        let mut inferrer = Inferrer {
+            type_env,
+            path,
+            undefined: file_undefined,
+            locals: HashMap::new(),
+            in_proc: false,
+            return_type: None,
+            uses,
+            order,
+        };
+        inferrer.body(ast);
+        (uses, order) = (inferrer.uses, inferrer.order);
+    }
+
+    // a name is only recorded once one of its uses has a type, so every name has at least one vote
+    order.into_iter()
+        .map(|name| {
+            let uses = uses.remove(&name).expect("Recorded with its first use");
+            let mut votes: Vec<(&Type, usize)> = vec![];
+            for use_ in &uses {
+                let weight = if use_.is_hint { 1 } else { 2 };
+                match votes.iter_mut().find(|(type_, _votes)| **type_ == use_.type_) {
+                    Some((_type, votes)) => *votes += weight,
+                    None => votes.push((&use_.type_, weight)),
+                }
+            }
+            // ties go to the type that was used first
+            let type_ = votes.iter()
+                .rev()
+                .max_by_key(|(_type, votes)| *votes)
+                .map(|(type_, _votes)| (*type_).clone())
+                .expect("Every name has a vote");
+            ExternInference { name, type_, uses }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{check, warnings, PATH};
+
+    fn infer(src: &str) -> Vec<ExternInference> {
+        let (type_env, ast_cache) = check(src);
+        let path = PathBuf::from(PATH);
+        let ast = ast_cache.get(&path).map(|info| info.1.0.clone()).expect("Source is cached");
+        infer_externs(&type_env, [(&path, ast.as_slice())])
+    }
+
+    fn decls(inferences: &[ExternInference]) -> Vec<String> {
+        inferences.iter().map(ExternInference::decl).collect()
+    }
+
+    #[test]
+    fn required_types_outvote_hints() {
+        let inferences = infer("\
+void main() {
+    float f = cRatio;
+    int a = cRatio + 1;
+    int b = cRatio - 2;
+    if (cFlag) {
+    }
+}
+");
+        assert_eq!(decls(&inferences), [
+            "/** @extern float cRatio */",
+            "/** @extern bool cFlag */",
+        ]);
+        assert!(inferences[0].conflicts().is_empty());
+        let candidates = inferences[0].candidates().into_iter().cloned().collect::<Vec<_>>();
+        assert_eq!(candidates, [Type::Float, Type::Int]);
+    }
+
+    #[test]
+    fn ties_go_to_the_first_use() {
+        let inferences = infer("\
+void main() {
+    string s = cName;
+    vector v = cName;
+}
+");
+        assert_eq!(decls(&inferences), ["/** @extern string cName */"]);
+    }
+
+    #[test]
+    fn uses_that_disagree_with_the_winner_conflict() {
+        let src = "\
+void main() {
+    int a = cLevel;
+    int b = cLevel;
+    string s = cLevel;
+    float f = cLevel;
+}
+";
+        let inferences = infer(src);
+        assert_eq!(decls(&inferences), ["/** @extern int cLevel */"]);
+
+        let conflicts = inferences[0].conflicts();
+        let (paths, errs): (Vec<_>, Vec<_>) = conflicts.into_iter().unzip();
+        assert_eq!(paths, [PathBuf::from(PATH)]);
+        assert_eq!(warnings(&errs), [
+            "ExternConflict: cLevel is used as a value of type string here, but its other uses give it the type int",
+        ]);
+        // the string use on the fourth line
+        assert_eq!(src[..errs[0].span().start].lines().count(), 4);
+    }
+}
@@ -0,0 +1,148 @@
+use std::collections::HashSet;
+
+use crate::parsing::ast::{AstNode, Body, Identifier};
+use crate::parsing::span::Span;
+use crate::r#static::cfg::{Cfg, Elem};
+use crate::r#static::info::{WarningKind, XsError};
+
+/// Updates the set of locals that may not have a value yet with an element, calling `on_read` for every read of
+/// a local in the set
+fn transfer<'a>(
+    elem: &Elem<'a>,
+    unassigned: &mut HashSet<&'a Identifier>,
+    mut on_read: impl FnMut(&Identifier, &Span),
+) {
+    for (name, span) in elem.reads() {
+        if unassigned.contains(name) {
+            on_read(name, &span);
+        }
+    }
+    if let Some((name, _span)) = elem.write() {
+        unassigned.remove(name);
+    }
+    if let Elem::Stmt((AstNode::VarDef { name: (name, _span), value: None, .. }, _span2)) = elem {
+        unassigned.insert(name);
+    }
+}
+
+/// Finds reads of locals that are declared without an initial value and may not have been assigned one on some
+/// path to the read. A path is any way through the body, including going around a loop, so a value assigned at the
+/// end of a loop body does not count for reads earlier in its first iteration.
+///
+/// `static` locals with an initial value are only initialised once but always have a value. `static` locals
+/// without one are treated like other locals, as they don't have a value until the first assignment in the first
+/// call of their function
+pub fn chk_uninit(body: &Body) -> Vec<XsError> {
+    let cfg = Cfg::new(body);
+    let reachable = cfg.reachable();
+
+    let mut preds = vec![vec![]; cfg.blocks.len()];
+    for (id, block) in cfg.blocks.iter().enumerate().filter(|(id, _block)| reachable[*id]) {
+        for (to, _kind) in block.taken_succs() {
+            preds[to].push(id);
+        }
+    }
+
+    // the locals that may not have a value at the end of each block
+    let mut outs: Vec<HashSet<&Identifier>> = vec![HashSet::new(); cfg.blocks.len()];
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for (id, block) in cfg.blocks.iter().enumerate().filter(|(id, _block)| reachable[*id]) {
+            let mut unassigned = preds[id].iter()
+                .flat_map(|pred| outs[*pred].iter().copied())
+                .collect::<HashSet<_>>();
+            for elem in &block.elems {
+                transfer(elem, &mut unassigned, |_name, _span| {});
+            }
+            if unassigned != outs[id] {
+                outs[id] = unassigned;
+                changed = true;
+            }
+        }
+    }
+
+    let mut errs = vec![];
+    for (id, block) in cfg.blocks.iter().enumerate().filter(|(id, _block)| reachable[*id]) {
+        let mut unassigned = preds[id].iter()
+            .flat_map(|pred| outs[*pred].iter().copied())
+            .collect::<HashSet<_>>();
+        for elem in &block.elems {
+            transfer(elem, &mut unassigned, |name, span| {
+                errs.push(XsError::warning(
+                    span,
+                    "The variable {0} may not have been assigned a value here",
+                    vec![&name.0],
+                    WarningKind::UninitVar,
+                ));
+            });
+        }
+    }
+    errs.sort_by_key(|err| err.span().start);
+    errs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{fn_def, parse};
+
+    /// The names of the locals read before they may have a value, with the lines they are read on
+    fn uninit_reads(src: &str) -> Vec<(String, usize)> {
+        let ast = parse(src);
+        let (_params, body, _span) = fn_def(&ast, "f");
+        chk_uninit(body).iter()
+            .map(|err| {
+                let line = src[..err.span().start].lines().count();
+                (src[err.span().start..err.span().end].to_string(), line)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn values_assigned_later_in_a_loop_are_not_there_in_the_first_iteration() {
+        assert_eq!(uninit_reads("\
+void f() {
+    int prev;
+    for (i = 0; < 3) {
+        int d = prev;
+        prev = i;
+    }
+}
+"), [("prev".to_string(), 4)]);
+    }
+
+    #[test]
+    fn values_assigned_before_a_loop_are_carried_around_it() {
+        assert!(uninit_reads("\
+void f() {
+    int x;
+    x = 1;
+    while (x < 5) {
+        int y;
+        y = x;
+        x = y + 1;
+    }
+    int z = x;
+}
+").is_empty());
+    }
+
+    #[test]
+    fn every_path_to_a_read_must_assign_a_value() {
+        assert_eq!(uninit_reads("\
+void f(int a = 0) {
+    int x;
+    int y;
+    static int s;
+    if (a > 0) {
+        x = 1;
+        y = 1;
+    } else {
+        y = 2;
+    }
+    a = x + y + s;
+}
+"), [("x".to_string(), 11), ("s".to_string(), 11)]);
+    }
+}
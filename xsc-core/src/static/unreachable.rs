@@ -0,0 +1,166 @@
+use std::collections::HashSet;
+
+use crate::parsing::ast::{AstNode, Body};
+use crate::parsing::span::{Span, Spanned};
+use crate::r#static::cfg::{Cfg, Elem};
+use crate::r#static::info::{WarningKind, XsError};
+
+/// The address of the statement or expression an element refers to, which identifies it in the AST
+fn elem_addr(elem: &Elem) -> usize {
+    match elem {
+        Elem::Stmt(stmt) | Elem::ForStep(stmt) => *stmt as *const _ as usize,
+        Elem::Cond(expr) | Elem::Case(expr) => *expr as *const _ as usize,
+    }
+}
+
+/// The address of the element that control reaches first when running a statement, if it has one
+fn entry_addr(spanned_stmt: &Spanned<AstNode>) -> Option<usize> {
+    let (stmt, _span) = spanned_stmt;
+    match stmt {
+        AstNode::IfElse { condition, .. } | AstNode::While { condition, .. } => {
+            Some(condition as *const _ as usize)
+        }
+        AstNode::Switch { clause, .. } => Some(clause as *const _ as usize),
+        AstNode::For { var, .. } => Some(var.as_ref() as *const _ as usize),
+        AstNode::Error | AstNode::Include(_) | AstNode::FnDef { .. } | AstNode::RuleDef { .. }
+        | AstNode::Class { .. } => None,
+        _ => Some(spanned_stmt as *const _ as usize),
+    }
+}
+
+/// Finds the statements of a function or rule body that can never run: code after a `return`, `break`,
+/// `continue` or `goto` that no label leads back into, and branches whose condition is always `true` or `false`.
+/// Every run of consecutive unreachable statements in a body is reported once.
+///
+/// `xsDisableSelf()` only stops a rule from being run again, the rest of the rule still runs after it, so it does
+/// not make any code unreachable
+pub fn chk_unreachable(body: &Body) -> Vec<XsError> {
+    let cfg = Cfg::new(body);
+    let reachable = cfg.reachable();
+    let reached = cfg.blocks.iter()
+        .enumerate()
+        .filter(|(id, _block)| reachable[*id])
+        .flat_map(|(_id, block)| block.elems.iter().map(elem_addr))
+        .collect::<HashSet<_>>();
+
+    let mut errs = vec![];
+    chk_body(body, &reached, &mut errs);
+    errs
+}
+
+fn chk_body(body: &Body, reached: &HashSet<usize>, errs: &mut Vec<XsError>) {
+    let mut run: Option<Span> = None;
+    for spanned_stmt in body {
+        let (stmt, span) = spanned_stmt;
+        let Some(addr) = entry_addr(spanned_stmt) else {
+            continue;
+        };
+        if !reached.contains(&addr) {
+            run = Some(match run {
+                Some(run) => Span::new(run.start, span.end),
+                None => *span,
+            });
+            continue;
+        }
+        if let Some(run) = run.take() {
+            errs.push(unreachable(&run));
+        }
+
+        match stmt {
+            AstNode::IfElse { consequent: (consequent, _span), alternate, .. } => {
+                chk_body(consequent, reached, errs);
+                if let Some((alternate, _span)) = alternate {
+                    chk_body(alternate, reached, errs);
+                }
+            }
+            AstNode::While { body: (body, _span), .. } | AstNode::For { body: (body, _span), .. } => {
+                chk_body(body, reached, errs);
+            }
+            AstNode::Switch { cases, .. } => {
+                for (_expr, (body, _span)) in cases {
+                    chk_body(body, reached, errs);
+                }
+            }
+            _ => {}
+        }
+    }
+    if let Some(run) = run {
+        errs.push(unreachable(&run));
+    }
+}
+
+fn unreachable(span: &Span) -> XsError {
+    XsError::warning(span, "This code is unreachable", vec![], WarningKind::Unreachable)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{fn_def, parse};
+
+    /// The code of each reported run of unreachable statements
+    fn unreachable_code(src: &str) -> Vec<&str> {
+        let ast = parse(src);
+        let (_params, body, _span) = fn_def(&ast, "f");
+        chk_unreachable(body).iter()
+            .map(|err| &src[err.span().start..err.span().end])
+            .collect()
+    }
+
+    #[test]
+    fn code_after_a_goto_is_unreachable_until_a_label() {
+        assert_eq!(unreachable_code("\
+void f() {
+    goto end;
+    int a = 1;
+    a++;
+    label end;
+    int b = 2;
+}
+"), ["int a = 1;\n    a++;"]);
+    }
+
+    #[test]
+    fn constant_conditions_cut_off_their_branches() {
+        assert_eq!(unreachable_code("\
+void f() {
+    if (false) {
+        int a = 1;
+    } else {
+        int b = 2;
+    }
+    while (!true) {
+        int c = 3;
+    }
+}
+"), ["int a = 1;", "int c = 3;"]);
+    }
+
+    #[test]
+    fn code_after_leaving_a_block_is_unreachable() {
+        assert_eq!(unreachable_code("\
+void f(int x = 0) {
+    for (i = 0; < 3) {
+        if (x > 0) {
+            continue;
+            x--;
+        }
+        break;
+        x++;
+    }
+    return;
+    x = 4;
+}
+"), ["x--;", "x++;", "x = 4;"]);
+    }
+
+    #[test]
+    fn disabling_a_rule_does_not_stop_it() {
+        assert!(unreachable_code("\
+rule f {
+    xsDisableSelf();
+    int a = 1;
+}
+").is_empty());
+    }
+}
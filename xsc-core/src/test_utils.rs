@@ -0,0 +1,75 @@
+use std::path::PathBuf;
+
+use crate::config::Flavour;
+use crate::interp::{Interpreter, NativeHost, Natives};
+use crate::parsing::ast::{AstNode, Body, Param};
+use crate::parsing::span::Span;
+use crate::r#static::info::{gen_errs_from_src, parse_src, AstCache, AstMap, TypeEnv, XsError};
+
+pub const PRELUDE_PATH: &str = "prelude.xs";
+pub const PATH: &str = "test.xs";
+
+/// Parses a source without type checking it
+pub fn parse(src: &str) -> Body {
+    let ((ast, _comments), errs) = parse_src(src);
+    assert!(errs.is_empty(), "Test sources have no syntax errors: {errs:?}");
+    ast
+}
+
+/// The parameters, body and body span of the function or rule with this name
+pub fn fn_def<'a>(ast: &'a Body, name: &str) -> (&'a [Param], &'a Body, &'a Span) {
+    ast.iter()
+        .find_map(|(stmt, _span)| match stmt {
+            AstNode::FnDef { name: (fn_name, _span), params, body: (body, body_span), .. } if fn_name.0 == name => {
+                Some((params.as_slice(), body, body_span))
+            }
+            AstNode::RuleDef { name: (rule_name, _span), body: (body, body_span), .. } if rule_name.0 == name => {
+                Some((&[][..], body, body_span))
+            }
+            _ => None,
+        })
+        .expect("Function is defined")
+}
+
+/// The kind and message of each warning, with its keywords filled in
+pub fn warnings(errs: &[XsError]) -> Vec<String> {
+    errs.iter()
+        .map(|err| {
+            let XsError::Warning { msg, keywords, .. } = err else {
+                panic!("Expected a warning, got {err:?}");
+            };
+            let msg = keywords.iter()
+                .enumerate()
+                .fold(msg.clone(), |msg, (idx, kw)| msg.replace(&format!("{{{idx}}}"), kw));
+            format!("{}: {msg}", err.kind())
+        })
+        .collect()
+}
+
+/// Type checks a source along with the prelude. The source is cached as [`PATH`]
+pub fn check(src: &str) -> (TypeEnv, AstCache) {
+    let ast_cache = &mut AstMap::new();
+    let src_cache = AstMap::new();
+    let mut type_env = TypeEnv::new(vec![]);
+
+    gen_errs_from_src(&PathBuf::from(PRELUDE_PATH), Flavour::AoE2DE.prelude(), &mut type_env, ast_cache, &src_cache)
+        .expect("Prelude can't produce parse errors");
+    gen_errs_from_src(&PathBuf::from(PATH), src, &mut type_env, ast_cache, &src_cache)
+        .expect("Test sources have no syntax errors");
+
+    (type_env, std::mem::take(ast_cache))
+}
+
+/// An interpreter with the prelude and the source loaded
+pub fn interp(src: &str) -> Interpreter<NativeHost> {
+    interp_with(src, NativeHost::new())
+}
+
+/// An interpreter over the given natives with the prelude and the source loaded
+pub fn interp_with<N: Natives>(src: &str, natives: N) -> Interpreter<N> {
+    let (_type_env, ast_cache) = check(src);
+    let mut interp = Interpreter::new(natives);
+    interp.load_prelude(&PathBuf::from(PRELUDE_PATH), &ast_cache).expect("Prelude loads");
+    interp.load(&PathBuf::from(PATH), &ast_cache, &[]).expect("Test source loads");
+    interp
+}
@@ -0,0 +1,10 @@
+// Functions available to tests run by xs-check test
+
+
+/**
+* Fails the current test if the condition is false. The test stops running at the first failed assertion
+*
+* @param condition The condition that must hold
+* @param message Shown when the condition is false
+*/
+void xsAssert(bool condition = false, string message = "") {}
@@ -0,0 +1,47 @@
+use xsc_core::formatter::{format_src, FormatOptions};
+
+fn format(src: &str) -> String {
+    format_src(src, &FormatOptions::default()).expect("Test sources have no syntax errors")
+}
+
+#[test]
+fn comments_in_call_stay_after_their_argument() {
+    let src = "\
+void main() {
+    f(a /* trailing */, b);
+    g(a, // first
+      b, // second
+      c);
+}
+";
+    let expected = "\
+void main() {
+    f(a /* trailing */, b);
+    g(a, // first
+        b, // second
+        c);
+}
+";
+    assert_eq!(format(src), expected);
+    assert_eq!(format(expected), expected);
+}
+
+#[test]
+fn comments_in_binary_expression_stay_after_their_operand() {
+    let src = "\
+void main() {
+    int x = a /* the a */ + b;
+    int y = a // the a
+        + b /* the b */ * c;
+}
+";
+    let expected = "\
+void main() {
+    int x = a /* the a */ + b;
+    int y = a + // the a
+        b /* the b */ * c;
+}
+";
+    assert_eq!(format(src), expected);
+    assert_eq!(format(expected), expected);
+}
@@ -4,25 +4,38 @@ use std::sync::{Arc, OnceLock};
 use dashmap::{DashMap, DashSet};
 use ropey::Rope;
 use tokio::sync::RwLock;
-use tower_lsp::lsp_types::{MessageType, Position, Url};
+use tower_lsp::lsp_types::{DidChangeWatchedFilesRegistrationOptions, FileSystemWatcher, GlobPattern, Location, MessageType, Position, Range, Registration, Url};
+use tower_lsp::jsonrpc::Error;
 use tower_lsp::Client;
 
-use xsc_core::parsing::ast::Identifier;
-use xsc_core::r#static::info::{gen_errs_from_path, gen_errs_from_src, AstCache, AstMap, TypeEnv};
+use xsc_core::config::{ProjectConfig, CONFIG_FILENAME};
+use xsc_core::r#static::info::{gen_errs_from_path, gen_errs_from_src, AstCache, AstMap, RefInfo, SrcLoc, TypeEnv};
+use xsc_core::r#static::lookup::{call_at, name_at, CallAt, NameAt};
+use xsc_core::r#static::type_inf::{infer_externs, ExternInference};
 
 use crate::config::config::fetch_config;
 use crate::config::ext_config::ExtConfig;
 use crate::fmt::errs_to_diags::{parse_errs_to_diags, xs_errs_to_diags};
-use crate::utils::path_from_uri;
+use crate::fmt::pos_info::{pos_from_span, span_from_pos};
+use crate::rename::{rename_target, RenameTarget};
+use crate::utils::{is_same_file, path_from_uri};
 
 pub type SrcCache = DashMap<PathBuf, (Url, Rope)>;
 
 pub type EnvCache = DashMap<PathBuf, TypeEnv>; 
 
+/// The settings and prelude shared by all the files that use the same xsc.toml
+pub struct Project {
+    pub config: ExtConfig,
+    pub prelude_env: TypeEnv,
+}
+
 pub struct Backend {
     client: Client,
+    /// The settings configured in the editor
     pub config: Arc<OnceLock<RwLock<ExtConfig>>>,
-    prelude_env: Arc<OnceLock<RwLock<TypeEnv>>>,
+    /// config path -> the project of all the files that use that config
+    projects: DashMap<Option<PathBuf>, Arc<Project>>,
     pub editors: SrcCache,
     pub ast_cache: AstCache,
     pub env_cache: EnvCache,
@@ -34,7 +47,7 @@ impl Backend {
         Self {
             client,
             config: Arc::new(OnceLock::new()),
-            prelude_env: Arc::new(OnceLock::new()),
+            projects: DashMap::new(),
             editors: DashMap::new(),
             ast_cache: AstMap::new(),
             env_cache: DashMap::new(),
@@ -50,20 +63,12 @@ impl Backend {
     }
     
     pub async fn do_lint(&self, uri: Url) {
-        let config = self.config
-            .get()
-            .expect("Initialized")
-            .read()
-            .await;
+        let path = path_from_uri(&uri);
+        let project = self.project(&path).await;
+        let config = &project.config;
 
-        let mut type_env = self.prelude_env
-            .get()
-            .expect("Initialized")
-            .read()
-            .await
-            .clone();
+        let mut type_env = project.prelude_env.clone();
         
-        let path = path_from_uri(&uri);
         let (_uri, src) = &*self.editors.get(&path).expect("Cached before do_lint");
 
         let mut parse_errs = match gen_errs_from_src(
@@ -76,7 +81,19 @@ impl Backend {
             Err(errs) => parse_errs_to_diags(&uri, &errs, &self.editors),
         };
 
-        let mut diags = xs_errs_to_diags(&uri, &type_env.errs, &self.editors, &config.ignores);
+        // uses of undefined names can only disagree on a type once every file has been checked
+        let asts = type_env.errs().keys()
+            .filter_map(|path| self.ast_cache.get(path).map(|info| (path.clone(), info.1.0.clone())))
+            .collect::<Vec<_>>();
+        let conflicts = infer_externs(&type_env, asts.iter().map(|(path, ast)| (path, ast.as_slice())))
+            .iter()
+            .flat_map(ExternInference::conflicts)
+            .collect::<Vec<_>>();
+        for (conflict_path, err) in conflicts {
+            type_env.add_err(&conflict_path, err);
+        }
+
+        let mut diags = xs_errs_to_diags(&uri, &type_env.errs, &self.editors, &config.ignores, &config.severities);
         diags.append(&mut parse_errs);
         
         let deps = type_env.dependencies.take().expect("New type-env created above");
@@ -86,51 +103,117 @@ impl Backend {
         self.client.publish_diagnostics(uri, diags, None).await;
     }
     
+    /// Fetches the settings configured in the editor. Refreshing them also drops every project, so that they are
+    /// rebuilt with the new settings
     pub async fn load_config(&self, refresh: bool) {
         if self.config.get().is_some() && !refresh {
             return;
         }
-        match fetch_config(&self.client).await {
-            Ok(config) if self.config.get().is_none() => {
-                self.config.set(RwLock::new(config)).expect("Only runs once");
-            }
-            Ok(new_config) => {
-                let mut config = self.config.get().expect("Initialized").write().await;
-                *config = new_config;
-            }
+        let new_config = match fetch_config(&self.client).await {
+            Ok(config) => { config }
             Err(err) => {
                 self.client.show_message(MessageType::ERROR, format!("XSC: Failed to load config: {}", err)).await;
                 return;
             }
+        };
+
+        if self.config.get().is_none() {
+            self.config.set(RwLock::new(new_config)).expect("Only runs once");
+        } else {
+            let mut config = self.config.get().expect("Initialized").write().await;
+            *config = new_config;
         }
+        self.projects.clear();
     }
-    
-    pub async fn build_prelude_env(&self, refresh: bool) {
-        self.load_config(refresh).await;
 
-        let config = self.config
+    /// Drops the projects that a changed file is the xsc.toml or an extra prelude of, so that they are rebuilt the
+    /// next time one of their files is linted. Any xsc.toml may change which config the files under it use, so
+    /// all projects are dropped for those. Returns whether any project was dropped
+    pub fn evict_projects(&self, path: &Path) -> bool {
+        let is_config = path.file_name().is_some_and(|name| name == CONFIG_FILENAME);
+        let num_projects = self.projects.len();
+        self.projects.retain(|_config_path, project| {
+            !is_config && !project.config.extra_prelude_paths.iter()
+                .any(|prelude_path| is_same_file(prelude_path, path))
+        });
+        self.projects.len() != num_projects
+    }
+
+    /// Asks the client to report changes to xsc.toml and XS files on disk, so projects are rebuilt when their config or
+    /// extra preludes change outside of the editor
+    pub async fn watch_files(&self) {
+        let watchers = ["**/xsc.toml", "**/*.xs"].into_iter()
+            .map(|glob| FileSystemWatcher { glob_pattern: GlobPattern::String(glob.into()), kind: None })
+            .collect();
+        let registration = Registration {
+            id: "xsc-watched-files".into(),
+            method: "workspace/didChangeWatchedFiles".into(),
+            register_options: serde_json::to_value(DidChangeWatchedFilesRegistrationOptions { watchers }).ok(),
+        };
+        // clients that can't watch files still rebuild projects when the files are saved in the editor
+        if let Err(err) = self.client.register_capability(vec![registration]).await {
+            self.client.log_message(MessageType::INFO, format!("XSC: Not watching files: {err}")).await;
+        }
+    }
+
+    /// Lints every open file again
+    pub async fn relint_all(&self) {
+        let uris = self.editors.iter()
+            .map(|entry| entry.0.clone())
+            .collect::<Vec<_>>();
+        for uri in uris {
+            self.do_lint(uri).await;
+        }
+    }
+
+    /// The project of a file. Like the CLI, the closest xsc.toml is looked for by walking up from the file, and the
+    /// project is built the first time a file that uses it is linted
+    pub async fn project(&self, path: &Path) -> Arc<Project> {
+        self.load_config(false).await;
+
+        let config_path = ProjectConfig::find(path);
+        if let Some(project) = self.projects.get(&config_path) {
+            return project.clone();
+        }
+
+        let mut config = self.config
             .get()
             .expect("Config loaded above")
             .read()
-            .await;
+            .await
+            .clone();
 
-        if self.prelude_env.get().is_some() && !refresh {
-            return;
+        if let Some(config_path) = &config_path {
+            match ProjectConfig::load(config_path) {
+                Ok(project_config) => {
+                    config.merge_project(&project_config);
+                }
+                Err(err) => {
+                    self.client.show_message(MessageType::ERROR, format!("XSC: {}", err)).await;
+                }
+            }
         }
-        
+
+        let prelude_env = self.build_prelude_env(&config).await;
+        let project = Arc::new(Project { config, prelude_env });
+        self.projects.insert(config_path, project.clone());
+        project
+    }
+    
+    async fn build_prelude_env(&self, config: &ExtConfig) -> TypeEnv {
         let mut type_env = TypeEnv::new(config.include_dirs.clone());
 
         let prelude_path = PathBuf::from(r"prelude.xs");
-        let prelude = include_str!(r"../../../xsc-core/prelude.xs");
+        let prelude = config.flavour.prelude();
 
         gen_errs_from_src(&prelude_path, prelude, &mut type_env, &self.ast_cache, &self.editors)
             .expect("Prelude can't produce parse errors");
 
-        if let Some(extra_prelude_path) = config.extra_prelude_path.as_ref() { 'extra: {
+        for extra_prelude_path in config.extra_prelude_paths.iter() {
             let path = PathBuf::from(extra_prelude_path);
             if !path.is_file() {
                 self.client.show_message(MessageType::ERROR, "XSC: Extra prelude file not found".to_string()).await;
-                break 'extra;
+                continue;
             }
             let result = gen_errs_from_path(extra_prelude_path, &mut type_env, &self.ast_cache, &self.editors);
             let err_count = type_env.errs.get(&path).map_or(0, |errs| {
@@ -139,57 +222,98 @@ impl Backend {
             if result.is_err() || err_count > 0 {
                 self.client.show_message(MessageType::ERROR, "XSC: Errors found in the extra prelude file".to_string()).await;
             }
-        }}
+        }
+        type_env
+    }
 
-        if self.prelude_env.get().is_none() {
-            self.prelude_env.set(RwLock::from(type_env)).expect("Called with true in initialized");
-        } else {
-            let mut prelude_env = self.prelude_env.get().expect("Initialized").write().await;
-            *prelude_env = type_env;
+    /// The source of a file, from the editor if it is open or from disk otherwise
+    pub fn src(&self, path: &Path) -> Option<(Url, Rope)> {
+        if let Some(entry) = self.editors.get(path) {
+            return Some(entry.value().clone());
         }
+        let url = Url::from_file_path(path).ok()?;
+        let src = std::fs::read_to_string(path).ok()?;
+        Some((url, Rope::from_str(&src)))
     }
 
-    pub fn get_id(&self, src: &Rope, pos: &Position) -> Identifier {
-        let line = src.line(pos.line as usize).to_string();
-        let char_idx = pos.character as usize;
+    /// The location of a span in a file that may not be open in the editor
+    pub fn location(&self, src_loc: &SrcLoc) -> Option<Location> {
+        let (url, src) = self.src(&src_loc.file_path)?;
+        let (start, end) = pos_from_span(&src, &src_loc.span);
+        Some(Location::new(url, Range::new(start, end)))
+    }
 
-        let start = line[..char_idx]
-            .rfind(|c: char| !c.is_alphanumeric() && c != '_')
-            .map(|i| i + 1)
-            .unwrap_or(0);
-        
-        let end = line[char_idx..]
-            .find(|c: char| !c.is_alphanumeric() && c != '_')
-            .map(|i| i + char_idx)
-            .unwrap_or(line.len());
-
-        (&line[start..end]).into()
-    }
-
-    pub fn get_fn_id(&self, src: &Rope, pos: &Position) -> (Identifier, u32) {
-        let line = src.line(pos.line as usize).to_string();
-        let char_idx = pos.character as usize;
-
-        let mut depth = 0u32;
-        let mut commas = 0u32;
-        let mut end = char_idx;
-        for c in line[..char_idx].chars().rev() {
-            end -= 1;
-            match c {
-                ')' => depth += 1,
-                '(' if depth > 0 => depth -= 1,
-                '(' if depth == 0 => break,
-                ',' => commas += 1,
-                _ => {},
+    /// Every linted file and the files they include
+    pub fn include_graph(&self) -> Vec<PathBuf> {
+        let mut paths: Vec<PathBuf> = vec![];
+        for entry in self.dependencies.iter() {
+            let (path, deps) = entry.pair();
+            for path in std::iter::once(path.clone()).chain(deps.iter().map(|dep| dep.key().clone())) {
+                if !paths.contains(&path) {
+                    paths.push(path);
+                }
             }
         }
+        paths.sort();
+        paths
+    }
 
-        let start = line[..end]
-            .rfind(|c: char| !c.is_alphanumeric() && c != '_')
-            .map(|i| i + 1)
-            .unwrap_or(0);
+    /// The uses of the name defined at `def` in every linted file. A file only knows about the uses in itself and
+    /// in the files it includes, so the files that include the definition's file are needed to find all of them
+    pub fn refs(&self, def: &SrcLoc) -> Vec<RefInfo> {
+        let mut refs: Vec<RefInfo> = vec![];
+        for env in self.env_cache.iter() {
+            for ref_ in env.refs(def) {
+                if refs.iter().all(|other| other.src_loc != ref_.src_loc) {
+                    refs.push(ref_.clone());
+                }
+            }
+        }
+        refs.sort_by_key(|ref_| (ref_.src_loc.file_path.clone(), ref_.src_loc.span.start));
+        refs
+    }
+
+    /// The uses of a rule group in every linted file
+    pub fn group_refs(&self, group: &str) -> Vec<SrcLoc> {
+        let mut refs: Vec<SrcLoc> = vec![];
+        for env in self.env_cache.iter() {
+            for src_loc in env.group_refs.get(group).into_iter().flatten() {
+                if !refs.contains(src_loc) {
+                    refs.push(src_loc.clone());
+                }
+            }
+        }
+        refs.sort_by_key(|src_loc| (src_loc.file_path.clone(), src_loc.span.start));
+        refs
+    }
+
+    /// The name to rename at a position in a file
+    pub async fn rename_target(&self, path: &PathBuf, pos: &Position) -> tower_lsp::jsonrpc::Result<Option<RenameTarget>> {
+        let project = self.project(path).await;
+        let config = &project.config;
+
+        // a rename can arrive before the file is first linted, or after it was closed
+        let Some(span) = self.editors.get(path).map(|editor| span_from_pos(&editor.1, pos, pos)) else {
+            return Ok(None);
+        };
+        let Some(env) = self.env_cache.get(path) else {
+            return Ok(None);
+        };
+        rename_target(&env, path, span.start, &config.extra_prelude_paths).map_err(Error::invalid_params)
+    }
+
+    /// The name at a position in a file, from its cached AST
+    pub fn name_at(&self, path: &PathBuf, src: &Rope, pos: &Position) -> Option<NameAt> {
+        let (_hash, (ast, _comments), _parse_errs) = &*self.ast_cache.get(path)?;
+        let span = span_from_pos(src, pos, pos);
+        name_at(ast, &src.to_string(), span.start)
+    }
 
-        ((&line[start..end]).into(), commas)
+    /// The function call whose arguments a position is in, from the file's cached AST
+    pub fn call_at(&self, path: &PathBuf, src: &Rope, pos: &Position) -> Option<CallAt> {
+        let (_hash, (ast, _comments), _parse_errs) = &*self.ast_cache.get(path)?;
+        let span = span_from_pos(src, pos, pos);
+        call_at(ast, &src.to_string(), span.start)
     }
 }
 
@@ -1,28 +1,35 @@
 use std::default::Default;
-use std::collections::{HashSet};
-use std::path::PathBuf;
+use std::collections::{HashMap, HashSet};
 use async_trait::async_trait;
 use tower_lsp::LanguageServer;
-use tower_lsp::lsp_types::{Command, CompletionItem, CompletionItemKind, CompletionOptions, CompletionParams, CompletionResponse, DidChangeConfigurationParams, DidChangeTextDocumentParams, DidCloseTextDocumentParams, DidOpenTextDocumentParams, Documentation, GotoDefinitionParams, GotoDefinitionResponse, Hover, HoverContents, HoverParams, HoverProviderCapability, InitializeParams, InitializeResult, InitializedParams, InlayHint, InlayHintParams, InsertTextFormat, Location, MarkupContent, MarkupKind, OneOf, ParameterInformation, ParameterLabel, Range, SemanticTokens, SemanticTokensFullOptions, SemanticTokensOptions, SemanticTokensParams, SemanticTokensResult, SemanticTokensServerCapabilities, ServerCapabilities, ServerInfo, SignatureHelp, SignatureHelpOptions, SignatureHelpParams, SignatureInformation, TextDocumentSyncCapability, TextDocumentSyncKind, Url};
+use tower_lsp::lsp_types::{CodeActionParams, CodeActionProviderCapability, CodeActionResponse, Command, CompletionItem, CompletionItemKind, CompletionOptions, CompletionParams, CompletionResponse, DidChangeConfigurationParams, DidChangeTextDocumentParams, DidChangeWatchedFilesParams, DidCloseTextDocumentParams, DidOpenTextDocumentParams, DidSaveTextDocumentParams, DocumentFormattingParams, DocumentHighlight, DocumentHighlightKind, DocumentHighlightParams, DocumentRangeFormattingParams, DocumentSymbolParams, DocumentSymbolResponse, Documentation, GotoDefinitionParams, GotoDefinitionResponse, Hover, HoverContents, HoverParams, HoverProviderCapability, InitializeParams, InitializeResult, InitializedParams, InlayHint, InlayHintParams, InsertTextFormat, Location, MarkupContent, MarkupKind, OneOf, ParameterInformation, ParameterLabel, PrepareRenameResponse, Range, ReferenceParams, RenameOptions, RenameParams, SemanticTokens, SemanticTokensFullOptions, SemanticTokensOptions, SemanticTokensParams, SemanticTokensResult, SemanticTokensServerCapabilities, ServerCapabilities, ServerInfo, SignatureHelp, SignatureHelpOptions, SignatureHelpParams, SignatureInformation, SymbolInformation, TextDocumentPositionParams, TextDocumentSyncCapability, TextDocumentSyncKind, TextDocumentSyncOptions, TextDocumentSyncSaveOptions, TextEdit, Url, WorkspaceEdit, WorkspaceSymbolParams};
 
 use ropey::Rope;
 
 use xsc_core::parsing::ast::{Type};
-use xsc_core::r#static::info::IdInfo;
+use xsc_core::r#static::info::{IdInfo, RefKind};
+use xsc_core::r#static::lookup::{binding, NameAt};
 use crate::backend::backend::Backend;
+use crate::code_actions::gen_extern_actions;
+use crate::rename::{chk_new_name, RenameTarget};
 use crate::fmt::pos_info::{pos_from_span, span_from_pos};
+use crate::formatting::{gen_format_edits, gen_range_format_edits};
 use crate::inlay_hints::gen_inlay_hints;
 use crate::semantic_tokens::{get_semantic_token_legend, gen_tokens};
-use crate::utils::{path_from_uri};
+use crate::symbols::{gen_doc_symbols, gen_workspace_symbols};
+use crate::utils::{is_prelude, path_from_uri};
 
 #[async_trait]
 impl LanguageServer for Backend {
     async fn initialize(&self, _: InitializeParams) -> tower_lsp::jsonrpc::Result<InitializeResult> {
         Ok(InitializeResult {
             capabilities: ServerCapabilities {
-                text_document_sync: Some(TextDocumentSyncCapability::Kind(
-                    TextDocumentSyncKind::INCREMENTAL,
-                )),
+                text_document_sync: Some(TextDocumentSyncCapability::Options(TextDocumentSyncOptions {
+                    open_close: Some(true),
+                    change: Some(TextDocumentSyncKind::INCREMENTAL),
+                    save: Some(TextDocumentSyncSaveOptions::Supported(true)),
+                    ..Default::default()
+                })),
                 semantic_tokens_provider: Some(SemanticTokensServerCapabilities::SemanticTokensOptions(SemanticTokensOptions {
                     legend: get_semantic_token_legend(),
                     full: Some(SemanticTokensFullOptions::Bool(true)),
@@ -46,6 +53,17 @@ impl LanguageServer for Backend {
                     retrigger_characters: None,
                     work_done_progress_options: Default::default(),
                 }),
+                document_formatting_provider: Some(OneOf::Left(true)),
+                document_range_formatting_provider: Some(OneOf::Left(true)),
+                code_action_provider: Some(CodeActionProviderCapability::Simple(true)),
+                references_provider: Some(OneOf::Left(true)),
+                document_highlight_provider: Some(OneOf::Left(true)),
+                document_symbol_provider: Some(OneOf::Left(true)),
+                workspace_symbol_provider: Some(OneOf::Left(true)),
+                rename_provider: Some(OneOf::Right(RenameOptions {
+                    prepare_provider: Some(true),
+                    work_done_progress_options: Default::default(),
+                })),
                 ..Default::default()
             },
             server_info: Some(ServerInfo {
@@ -56,7 +74,9 @@ impl LanguageServer for Backend {
     }
 
     async fn initialized(&self, _: InitializedParams) {
-        self.build_prelude_env(false).await;
+        self.load_config(false).await;
+
+        self.watch_files().await;
     }
 
     async fn shutdown(&self) -> tower_lsp::jsonrpc::Result<()> {
@@ -66,7 +86,6 @@ impl LanguageServer for Backend {
     async fn did_open(&self, params: DidOpenTextDocumentParams) {
         let uri = params.text_document.uri.clone();
 
-        self.build_prelude_env(false).await;
         let src = Rope::from(params.text_document.text);
 
         let path = path_from_uri(&uri);
@@ -114,6 +133,22 @@ impl LanguageServer for Backend {
         }
     }
 
+    async fn did_save(&self, params: DidSaveTextDocumentParams) {
+        if self.evict_projects(&path_from_uri(&params.text_document.uri)) {
+            self.relint_all().await;
+        }
+    }
+
+    async fn did_change_watched_files(&self, params: DidChangeWatchedFilesParams) {
+        let mut evicted = false;
+        for change in params.changes {
+            evicted |= self.evict_projects(&path_from_uri(&change.uri));
+        }
+        if evicted {
+            self.relint_all().await;
+        }
+    }
+
     async fn did_close(&self, params: DidCloseTextDocumentParams) {
         let uri = params.text_document.uri;
         let path = path_from_uri(&uri);
@@ -126,60 +161,156 @@ impl LanguageServer for Backend {
         let uri = params.text_document_position_params.text_document.uri;
         let pos = params.text_document_position_params.position;
         let path = path_from_uri(&uri);
+        let project = self.project(&path).await;
 
-        let (_url, src) = &*self.editors.get(&path).expect("Cached before def");
-        let id = self.get_id(src, &pos);
-        let span = span_from_pos(src, &pos, &pos);
+        let Some(editor) = self.editors.get(&path) else {
+            return Ok(None);
+        };
+        let (_url, src) = &*editor;
+        let Some(name_at) = self.name_at(&path, src, &pos) else {
+            return Ok(None);
+        };
 
-        let env = &*self.env_cache.get(&path).expect("Cached before def");
+        let Some(env) = self.env_cache.get(&path) else {
+            return Ok(None);
+        };
+        let env = &*env;
 
-        let info = env.identifiers.get(&id)
-            .or_else(|| env.local_ids(&path, &span).and_then(|ids| ids.get(&id)));
+        let Some(info) = binding(env, &path, &name_at) else {
+            return Ok(None);
+        };
 
-        let Some(info) = info else {
+        if is_prelude(&info.src_loc.file_path, &project.config.extra_prelude_paths) {
             return Ok(None);
+        }
+
+        Ok(self.location(&info.src_loc).map(GotoDefinitionResponse::Scalar))
+    }
+
+    async fn references(&self, params: ReferenceParams) -> tower_lsp::jsonrpc::Result<Option<Vec<Location>>> {
+        let uri = params.text_document_position.text_document.uri;
+        let pos = params.text_document_position.position;
+        let path = path_from_uri(&uri);
+
+        let span = {
+            let Some(editor) = self.editors.get(&path) else {
+                return Ok(None);
+            };
+            let (_url, src) = &*editor;
+            span_from_pos(src, &pos, &pos)
+        };
+
+        let def = {
+            let Some(env) = self.env_cache.get(&path) else {
+                return Ok(None);
+            };
+            let env = &*env;
+            let Some(def) = env.def_at(&path, span.start) else {
+                return Ok(None);
+            };
+            def.clone()
         };
 
-        let config = self.config
-            .get()
-            .expect("Initialized")
-            .read()
-            .await;
+        let mut src_locs = vec![];
+        if params.context.include_declaration {
+            src_locs.push(def.clone());
+        }
+        src_locs.extend(self.refs(&def).into_iter().map(|ref_| ref_.src_loc));
+
+        Ok(Some(src_locs.iter().filter_map(|src_loc| self.location(src_loc)).collect()))
+    }
 
-        let prelude_path = PathBuf::from(r"prelude.xs");
-        if info.src_loc.file_path == prelude_path || info.src_loc.file_path == *config.extra_prelude_path.as_ref().unwrap_or(&prelude_path) {
+    async fn document_highlight(&self, params: DocumentHighlightParams) -> tower_lsp::jsonrpc::Result<Option<Vec<DocumentHighlight>>> {
+        let uri = params.text_document_position_params.text_document.uri;
+        let pos = params.text_document_position_params.position;
+        let path = path_from_uri(&uri);
+
+        let Some(editor) = self.editors.get(&path) else {
+            return Ok(None);
+        };
+        let (_url, src) = &*editor;
+        let span = span_from_pos(src, &pos, &pos);
+
+        let Some(env) = self.env_cache.get(&path) else {
+            return Ok(None);
+        };
+        let env = &*env;
+        let Some(def) = env.def_at(&path, span.start) else {
             return Ok(None);
+        };
+
+        let highlight = |span, kind| {
+            let (start, end) = pos_from_span(src, span);
+            DocumentHighlight { range: Range::new(start, end), kind: Some(kind) }
+        };
+
+        let mut highlights = vec![];
+        if def.file_path == path {
+            highlights.push(highlight(&def.span, DocumentHighlightKind::TEXT));
         }
+        highlights.extend(env.refs(def).iter()
+            .filter(|ref_| ref_.src_loc.file_path == path)
+            .map(|ref_| highlight(&ref_.src_loc.span, match ref_.kind {
+                RefKind::Read => DocumentHighlightKind::READ,
+                RefKind::Write => DocumentHighlightKind::WRITE,
+            })));
+
+        Ok(Some(highlights))
+    }
 
-        let fallback: Option<(Url, Rope)>;
-        let guard: Option<dashmap::mapref::one::Ref<'_, PathBuf, (Url, Rope)>>;
+    async fn prepare_rename(&self, params: TextDocumentPositionParams) -> tower_lsp::jsonrpc::Result<Option<PrepareRenameResponse>> {
+        let path = path_from_uri(&params.text_document.uri);
 
-        match self.editors.get(&info.src_loc.file_path) {
-            Some(ref_) => {
-                fallback = None;
-                guard = Some(ref_);
-            },
-            None => {
-                let Ok(url) = Url::from_file_path(&info.src_loc.file_path) else {
-                    return Ok(None);
-                };
-                let Ok(src) = std::fs::read_to_string(&info.src_loc.file_path) else {
-                    return Ok(None);
-                };
+        let Some(target) = self.rename_target(&path, &params.position).await? else {
+            return Ok(None);
+        };
 
-                // did_open will cache this
-                fallback = Some((url, Rope::from_str(&src)));
-                guard = None;
+        let Some(editor) = self.editors.get(&path) else {
+            return Ok(None);
+        };
+        let (_url, src) = &*editor;
+        let (start, end) = pos_from_span(src, target.span());
+        Ok(Some(PrepareRenameResponse::RangeWithPlaceholder {
+            range: Range::new(start, end),
+            placeholder: target.name().to_string(),
+        }))
+    }
+
+    async fn rename(&self, params: RenameParams) -> tower_lsp::jsonrpc::Result<Option<WorkspaceEdit>> {
+        let path = path_from_uri(&params.text_document_position.text_document.uri);
+        let new_name = params.new_name;
+
+        let Some(target) = self.rename_target(&path, &params.text_document_position.position).await? else {
+            return Ok(None);
+        };
+        if target.name() == new_name {
+            return Ok(None);
+        }
+
+        // every file that knows about the target must be able to take the new name
+        for env in self.env_cache.iter() {
+            let knows_target = match &target {
+                RenameTarget::Name { def, .. } => env.refs.contains_key(def),
+                RenameTarget::Group { name, .. } => env.group_refs.contains_key(name),
+            };
+            if knows_target {
+                chk_new_name(&env, &target, &new_name).map_err(tower_lsp::jsonrpc::Error::invalid_params)?;
             }
+        }
+
+        let src_locs = match &target {
+            RenameTarget::Name { def, .. } => std::iter::once(def.clone())
+                .chain(self.refs(def).into_iter().map(|ref_| ref_.src_loc))
+                .collect(),
+            RenameTarget::Group { name, .. } => self.group_refs(name),
         };
-        let (url, src) = guard
-            .as_ref()
-            .map(|g| g.value())
-            .or(fallback.as_ref())
-            .expect("one value exists");
 
-        let range = pos_from_span(src, &info.src_loc.span);
-        Ok(Some(GotoDefinitionResponse::Scalar(Location::new(url.clone(), Range::new(range.0, range.1)))))
+        let mut changes: HashMap<Url, Vec<TextEdit>> = HashMap::new();
+        for location in src_locs.iter().filter_map(|src_loc| self.location(src_loc)) {
+            changes.entry(location.uri).or_default().push(TextEdit::new(location.range, new_name.clone()));
+        }
+
+        Ok(Some(WorkspaceEdit { changes: Some(changes), ..Default::default() }))
     }
 
     async fn hover(&self, params: HoverParams) -> tower_lsp::jsonrpc::Result<Option<Hover>> {
@@ -187,35 +318,76 @@ impl LanguageServer for Backend {
         let pos = params.text_document_position_params.position;
         let path = path_from_uri(&uri);
 
-        let (_url, src) = &*self.editors.get(&path).expect("Cached before hover");
-        let id = self.get_id(src, &pos);
-        let span = span_from_pos(src, &pos, &pos);
-
-        let env = &*self.env_cache.get(&path).expect("Cached before hover");
+        let Some(editor) = self.editors.get(&path) else {
+            return Ok(None);
+        };
+        let (_url, src) = &*editor;
+        let Some(name_at) = self.name_at(&path, src, &pos) else {
+            return Ok(None);
+        };
 
-        let info = env.identifiers.get(&id)
-            .or_else(|| env.local_ids(&path, &span).and_then(|ids| ids.get(&id)));
+        let Some(env) = self.env_cache.get(&path) else {
+            return Ok(None);
+        };
+        let env = &*env;
 
-        let Some(info) = info else {
+        let Some(info) = binding(env, &path, &name_at) else {
             return Ok(None);
         };
 
+        let (start, end) = pos_from_span(src, &name_at.span);
         Ok(Some(Hover {
             contents: HoverContents::Markup(MarkupContent {
                 kind: MarkupKind::Markdown,
-                value: info.doc.render(&id, info),
+                value: info.doc.render(&name_at.name, info),
             }),
-            range: None,
+            range: Some(Range::new(start, end)),
         }))
     }
 
+    async fn document_symbol(&self, params: DocumentSymbolParams) -> tower_lsp::jsonrpc::Result<Option<DocumentSymbolResponse>> {
+        let uri = params.text_document.uri;
+        let path = path_from_uri(&uri);
+        let Some(editor) = self.editors.get(&path) else {
+            return Ok(None);
+        };
+        let (_uri, src) = &*editor;
+        let Some(ast_info) = self.ast_cache.get(&path) else {
+            return Ok(None);
+        };
+        let (_hash, (ast, _comms), _parse_errs) = &*ast_info;
+
+        Ok(Some(DocumentSymbolResponse::Nested(gen_doc_symbols(src, ast))))
+    }
+
+    async fn symbol(&self, params: WorkspaceSymbolParams) -> tower_lsp::jsonrpc::Result<Option<Vec<SymbolInformation>>> {
+        let files = self.include_graph()
+            .into_iter()
+            .filter_map(|path| {
+                let (url, src) = self.src(&path)?;
+                let (_hash, (ast, _comms), _parse_errs) = &*self.ast_cache.get(&path)?;
+                Some((url, gen_doc_symbols(&src, ast)))
+            }).collect();
+
+        Ok(Some(gen_workspace_symbols(&params.query, files)))
+    }
+
     async fn semantic_tokens_full(&self, params: SemanticTokensParams) -> tower_lsp::jsonrpc::Result<Option<SemanticTokensResult>> {
         let uri = params.text_document.uri;
         let path = path_from_uri(&uri);
-        let (_uri, src) = &*self.editors.get(&path).expect("Cached before semantic_tokens_full");
-        let (_hash, (ast, _comms)) = &*self.ast_cache.get(&path).expect("Cached before semantic_tokens_full");
+        let Some(editor) = self.editors.get(&path) else {
+            return Ok(None);
+        };
+        let (_uri, src) = &*editor;
+        let Some(ast_info) = self.ast_cache.get(&path) else {
+            return Ok(None);
+        };
+        let (_hash, (ast, _comms), _parse_errs) = &*ast_info;
 
-        let env = &*self.env_cache.get(&path).expect("Cached before semantic_tokens_full");
+        let Some(env) = self.env_cache.get(&path) else {
+            return Ok(None);
+        };
+        let env = &*env;
 
         Ok(Some(SemanticTokensResult::Tokens(SemanticTokens {
             result_id: None,
@@ -227,10 +399,19 @@ impl LanguageServer for Backend {
         let uri = params.text_document.uri;
         let range = params.range;
         let path = path_from_uri(&uri);
-        let (_uri, src) = &*self.editors.get(&path).expect("Cached before inlay_hint");
-        let (_hash, (ast, _comms)) = &*self.ast_cache.get(&path).expect("Cached before inlay_hint");
+        let Some(editor) = self.editors.get(&path) else {
+            return Ok(None);
+        };
+        let (_uri, src) = &*editor;
+        let Some(ast_info) = self.ast_cache.get(&path) else {
+            return Ok(None);
+        };
+        let (_hash, (ast, _comms), _parse_errs) = &*ast_info;
 
-        let env = &*self.env_cache.get(&path).expect("Cached before inlay_hint");
+        let Some(env) = self.env_cache.get(&path) else {
+            return Ok(None);
+        };
+        let env = &*env;
 
         Ok(Some(gen_inlay_hints(src, ast, env, range)))
     }
@@ -240,11 +421,20 @@ impl LanguageServer for Backend {
         let pos = params.text_document_position.position;
         let path = path_from_uri(&uri);
 
-        let (_url, src) = &*self.editors.get(&path).expect("Cached before completion");
-        let prefix = self.get_id(src, &pos).0;
+        let Some(editor) = self.editors.get(&path) else {
+            return Ok(None);
+        };
+        let (_url, src) = &*editor;
         let span = span_from_pos(src, &pos, &pos);
+        // only the part of the name before the cursor is typed out
+        let prefix = self.name_at(&path, src, &pos)
+            .and_then(|name_at| name_at.name.0.get(..span.start - name_at.span.start).map(str::to_string))
+            .unwrap_or_default();
 
-        let env = &*self.env_cache.get(&path).expect("Cached before completion");
+        let Some(env) = self.env_cache.get(&path) else {
+            return Ok(None);
+        };
+        let env = &*env;
         
         let ids = env.local_ids(&path, &span)
             .map(|ids| ids.iter())
@@ -295,12 +485,23 @@ impl LanguageServer for Backend {
         let pos = params.text_document_position_params.position;
         let path = path_from_uri(&uri);
 
-        let (_url, src) = &*self.editors.get(&path).expect("Cached before completion");
-        let (fn_name, param_index) = self.get_fn_id(src, &pos);
+        let Some(editor) = self.editors.get(&path) else {
+            return Ok(None);
+        };
+        let (_url, src) = &*editor;
+        let Some(call_at) = self.call_at(&path, src, &pos) else {
+            return Ok(None);
+        };
+        let fn_name = call_at.name.clone();
+        let param_index = call_at.arg_idx as u32;
 
-        let env = &*self.env_cache.get(&path).expect("Cached before completion");
+        let Some(env) = self.env_cache.get(&path) else {
+            return Ok(None);
+        };
+        let env = &*env;
 
-        let Some(IdInfo { type_: Type::Fn { type_sign, .. }, doc, .. }) = &env.get(&fn_name) else {
+        let name_at = NameAt { name: call_at.name, span: call_at.name_span };
+        let Some(IdInfo { type_: Type::Fn { type_sign, .. }, doc, .. }) = binding(env, &path, &name_at) else {
             return Ok(None);
         };
 
@@ -342,11 +543,46 @@ impl LanguageServer for Backend {
         }))
     }
 
+    async fn formatting(&self, params: DocumentFormattingParams) -> tower_lsp::jsonrpc::Result<Option<Vec<TextEdit>>> {
+        let uri = params.text_document.uri;
+        let path = path_from_uri(&uri);
+        let Some(editor) = self.editors.get(&path) else {
+            return Ok(None);
+        };
+        let (_uri, src) = &*editor;
+
+        Ok(gen_format_edits(src, &params.options))
+    }
+
+    async fn range_formatting(&self, params: DocumentRangeFormattingParams) -> tower_lsp::jsonrpc::Result<Option<Vec<TextEdit>>> {
+        let uri = params.text_document.uri;
+        let path = path_from_uri(&uri);
+        let Some(editor) = self.editors.get(&path) else {
+            return Ok(None);
+        };
+        let (_uri, src) = &*editor;
+
+        Ok(gen_range_format_edits(src, &params.range, &params.options))
+    }
+
+    async fn code_action(&self, params: CodeActionParams) -> tower_lsp::jsonrpc::Result<Option<CodeActionResponse>> {
+        let uri = params.text_document.uri;
+        let path = path_from_uri(&uri);
+        let Some(editor) = self.editors.get(&path) else {
+            return Ok(None);
+        };
+        let (_uri, src) = &*editor;
+
+        let Some(env) = self.env_cache.get(&path) else {
+            return Ok(None);
+        };
+        let env = &*env;
+
+        Ok(Some(gen_extern_actions(&uri, src, &params.range, env, &self.ast_cache)))
+    }
+
     async fn did_change_configuration(&self, _: DidChangeConfigurationParams) {
-        self.build_prelude_env(true).await;
-        for entry in self.editors.iter() {
-            let uri = entry.0.clone();
-            self.do_lint(uri).await;
-        }
+        self.load_config(true).await;
+        self.relint_all().await;
     }
 }
@@ -0,0 +1,3 @@
+mod gen_extern_actions;
+
+pub use gen_extern_actions::{gen_extern_actions};
@@ -0,0 +1,67 @@
+use std::collections::HashMap;
+
+use ropey::Rope;
+use tower_lsp::lsp_types::{CodeAction, CodeActionKind, CodeActionOrCommand, Position, Range, TextEdit, Url, WorkspaceEdit};
+use xsc_core::r#static::info::{AstCache, TypeEnv, XsError};
+use xsc_core::r#static::type_inf::{decl, infer_externs};
+
+use crate::fmt::pos_info::span_from_pos;
+use crate::utils::path_from_uri;
+
+/// Quick fixes that declare the undefined names used in the range with an `@extern` doc comment. The declaration
+/// with the inferred type comes first, followed by one for every other type that a use of the name suggests. It is
+/// added to the top of the file with the first use of the name, so that it comes before all of them
+pub fn gen_extern_actions(
+    uri: &Url,
+    src: &Rope,
+    range: &Range,
+    env: &TypeEnv,
+    ast_cache: &AstCache,
+) -> Vec<CodeActionOrCommand> {
+    let path = path_from_uri(uri);
+    let span = span_from_pos(src, &range.start, &range.end);
+
+    // only the files with errors can use undefined names
+    let asts = env.errs().keys()
+        .filter_map(|path| ast_cache.get(path).map(|info| (path.clone(), info.1.0.clone())))
+        .collect::<Vec<_>>();
+    let inferences = infer_externs(env, asts.iter().map(|(path, ast)| (path, ast.as_slice())));
+
+    let mut actions = vec![];
+    for inference in inferences {
+        // any use of the name is offered the fix, even one that its type was not inferred from
+        let in_range = env.errs().get(&path).into_iter().flatten().any(|err| matches!(
+            err,
+            XsError::UndefinedName { name, span: name_span }
+                if *name == inference.name.0 && name_span.start <= span.end && span.start <= name_span.end
+        ));
+        if !in_range {
+            continue;
+        }
+        let first_path = &inference.uses[0].src_loc.file_path;
+        let first_uri = if *first_path == path {
+            uri.clone()
+        } else {
+            let Ok(first_uri) = Url::from_file_path(first_path) else {
+                continue;
+            };
+            first_uri
+        };
+
+        for (i, type_) in inference.candidates().into_iter().enumerate() {
+            let decl = decl(&inference.name, type_);
+            let edit = TextEdit::new(Range::new(Position::new(0, 0), Position::new(0, 0)), format!("{decl}\n"));
+            actions.push(CodeActionOrCommand::CodeAction(CodeAction {
+                title: format!("Declare {} as an extern {}", inference.name, type_),
+                kind: Some(CodeActionKind::QUICKFIX),
+                edit: Some(WorkspaceEdit {
+                    changes: Some(HashMap::from([(first_uri.clone(), vec![edit])])),
+                    ..Default::default()
+                }),
+                is_preferred: Some(i == 0),
+                ..Default::default()
+            }));
+        }
+    }
+    actions
+}
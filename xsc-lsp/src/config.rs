@@ -1,4 +1,2 @@
-pub mod flavour;
 pub mod config;
 pub mod ext_config;
-
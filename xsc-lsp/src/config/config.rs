@@ -3,7 +3,6 @@ use tower_lsp::lsp_types::ConfigurationItem;
 use xsc_core::r#static::info::WarningKind;
 
 use crate::config::ext_config::ExtConfig;
-use crate::config::flavour::Flavour;
 
 #[derive(Debug, serde::Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -42,13 +41,14 @@ pub async fn fetch_config(client: &tower_lsp::Client) -> anyhow::Result<ExtConfi
         }
     });
     let include_dirs = config.include_directories.into_iter().map(|s| s.into()).collect();
-    let flavour = Flavour::from_str(&config.flavour)
+    let flavour = config.flavour.parse()
         .expect("Flavour name generated by enum");
 
     Ok(ExtConfig {
         ignores,
-        extra_prelude_path,
+        extra_prelude_paths: extra_prelude_path.into_iter().collect(),
         include_dirs,
         flavour,
+        severities: Default::default(),
     })
 }
\ No newline at end of file
@@ -1,12 +1,27 @@
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::path::PathBuf;
-use crate::config::flavour::Flavour;
 
-#[allow(dead_code)]
-#[derive(Debug)]
+use xsc_core::config::{Flavour, ProjectConfig, Severity};
+
+#[derive(Debug, Clone)]
 pub struct ExtConfig {
     pub ignores: HashSet<u32>,
-    pub extra_prelude_path: Option<PathBuf>,
+    pub extra_prelude_paths: Vec<PathBuf>,
     pub include_dirs: Vec<PathBuf>,
-    pub flavour: Flavour
+    pub flavour: Flavour,
+    pub severities: HashMap<u32, Severity>,
+}
+
+impl ExtConfig {
+    /// Settings in the project's xsc.toml are added to the ones configured in the editor. The flavour (if set) and
+    /// severity overrides of the project take precedence
+    pub fn merge_project(&mut self, project: &ProjectConfig) {
+        self.ignores.extend(project.ignored_codes());
+        self.extra_prelude_paths.extend(project.extra_preludes.iter().cloned());
+        self.include_dirs.extend(project.include_dirs.iter().cloned());
+        if let Some(flavour) = project.flavour {
+            self.flavour = flavour;
+        }
+        self.severities.extend(project.severities.iter().map(|(code, severity)| (*code, *severity)));
+    }
 }
@@ -1,8 +1,9 @@
 use std::collections::{HashMap, HashSet};
 use std::path::PathBuf;
 
-use tower_lsp::lsp_types::{Diagnostic, DiagnosticSeverity, Position, Range, Url};
+use tower_lsp::lsp_types::{Diagnostic, DiagnosticSeverity, DiagnosticTag, Position, Range, Url};
 
+use xsc_core::config::Severity;
 use xsc_core::r#static::info::{Error, XsError};
 
 use crate::fmt::msg_fmt::msg_fmt;
@@ -13,7 +14,8 @@ pub fn xs_errs_to_diags(
     uri: &Url,
     errs: &HashMap<PathBuf, Vec<XsError>>,
     editors: &SrcCache,
-    ignores: &HashSet<u32>
+    ignores: &HashSet<u32>,
+    severities: &HashMap<u32, Severity>,
 ) -> Vec<Diagnostic> {
     let mut diags = Vec::with_capacity(errs.values().map(|v| v.len()).sum());
 
@@ -31,6 +33,7 @@ pub fn xs_errs_to_diags(
             }
 
             let mut severity = DiagnosticSeverity::ERROR;
+            let mut tags = None;
             let (kind, msg, span) = match err {
                 XsError::ExtraArg { fn_name, span } => {
                     (
@@ -125,6 +128,9 @@ pub fn xs_errs_to_diags(
                 }
                 XsError::Warning { span, msg, keywords, kind, .. } => {
                     severity = DiagnosticSeverity::WARNING;
+                    if kind.is_unnecessary() {
+                        tags = Some(vec![DiagnosticTag::UNNECESSARY]);
+                    }
                     (
                         kind.as_str(),
                         msg_fmt(msg, keywords),
@@ -133,6 +139,10 @@ pub fn xs_errs_to_diags(
                 }
             };
 
+            if severities.get(&err.code()) == Some(&Severity::Error) {
+                severity = DiagnosticSeverity::ERROR;
+            }
+
             let (start, end) = pos_from_span(&src, span);
 
             diags.push(Diagnostic {
@@ -146,7 +156,7 @@ pub fn xs_errs_to_diags(
                  source: Some("xs-check".to_string()),
                  message: format!("{}: {}", kind, msg),
                  related_information: None,
-                 tags: None,
+                 tags,
                  data: None,
              });
         }
@@ -160,7 +170,7 @@ pub fn parse_errs_to_diags(uri: &Url, errs: &Vec<Error>, editors: &SrcCache) ->
 
     for err in errs {
         match err {
-            Error::FileErr(path, msg) => {
+            Error::FileErr(path, msg) | Error::CircularDep(path, msg) => {
                 let Some(entry) = editors.get(path) else {
                     continue;
                 };
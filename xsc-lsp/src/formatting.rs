@@ -0,0 +1,3 @@
+mod gen_edits;
+
+pub use gen_edits::{gen_format_edits, gen_range_format_edits};
@@ -0,0 +1,53 @@
+use ropey::Rope;
+use tower_lsp::lsp_types::{FormattingOptions, Position, Range, TextEdit};
+use xsc_core::formatter::{format_range, format_src, FormatOptions};
+use xsc_core::parsing::span::Span;
+
+use crate::fmt::pos_info::{pos_from_span, span_from_pos};
+
+fn format_options(options: &FormattingOptions) -> FormatOptions {
+    FormatOptions::new(options.tab_size as usize, options.insert_spaces)
+}
+
+/// The formatter works with byte offsets while ropes are indexed by chars
+fn char_span(src: &Rope, span: &Span) -> Span {
+    Span::new(src.byte_to_char(span.start), src.byte_to_char(span.end))
+}
+
+/// Returns None if the document can't be formatted because of syntax errors
+pub fn gen_format_edits(src: &Rope, options: &FormattingOptions) -> Option<Vec<TextEdit>> {
+    let text = src.to_string();
+    let formatted = format_src(&text, &format_options(options)).ok()?;
+    if formatted == text {
+        return Some(vec![]);
+    }
+
+    let last_line = src.len_lines().saturating_sub(1);
+    Some(vec![TextEdit {
+        range: Range {
+            start: Position { line: 0, character: 0 },
+            end: Position { line: last_line as u32, character: src.line(last_line).len_chars() as u32 },
+        },
+        new_text: formatted,
+    }])
+}
+
+/// Returns None if the document can't be formatted because of syntax errors
+pub fn gen_range_format_edits(src: &Rope, range: &Range, options: &FormattingOptions) -> Option<Vec<TextEdit>> {
+    let text = src.to_string();
+    let span = span_from_pos(src, &range.start, &range.end);
+    let span = Span::new(src.char_to_byte(span.start), src.char_to_byte(span.end));
+
+    let Some((span, formatted)) = format_range(&text, span, &format_options(options)).ok()? else {
+        return Some(vec![]);
+    };
+    if text[span.start..span.end] == formatted {
+        return Some(vec![]);
+    }
+
+    let (start, end) = pos_from_span(src, &char_span(src, &span));
+    Some(vec![TextEdit {
+        range: Range { start, end },
+        new_text: formatted,
+    }])
+}
@@ -7,6 +7,10 @@ mod utils;
 mod backend;
 mod semantic_tokens;
 mod inlay_hints;
+mod formatting;
+mod code_actions;
+mod rename;
+mod symbols;
 
 use backend::backend::Backend;
 
@@ -0,0 +1,3 @@
+mod rename_target;
+
+pub use rename_target::{chk_new_name, rename_target, RenameTarget};
@@ -0,0 +1,212 @@
+use std::path::PathBuf;
+
+use xsc_core::parsing::ast::Identifier;
+use xsc_core::parsing::lexer::{is_identifier, is_keyword};
+use xsc_core::parsing::span::Span;
+use xsc_core::r#static::info::{SrcLoc, TypeEnv};
+
+use crate::utils::is_prelude;
+
+/// The name being renamed
+#[derive(Debug, Clone)]
+pub enum RenameTarget {
+    /// A variable, parameter, function, rule or label, which is renamed at its definition and every use
+    Name { def: SrcLoc, name: Identifier, span: Span },
+    /// A rule group, which has no definition and is renamed in every rule option and rule group function call
+    Group { name: String, span: Span },
+}
+
+impl RenameTarget {
+    /// The span of the name at the position that the rename was requested at
+    pub fn span(&self) -> &Span {
+        match self {
+            RenameTarget::Name { span, .. } | RenameTarget::Group { span, .. } => span,
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        match self {
+            RenameTarget::Name { name, .. } => &name.0,
+            RenameTarget::Group { name, .. } => name,
+        }
+    }
+}
+
+fn contains(span: &Span, pos: usize) -> bool {
+    span.start <= pos && pos <= span.end
+}
+
+/// The name at a position in a file. `Err` if the name can't be renamed because it is defined in a prelude or is
+/// `extern`, as other scripts and triggers may refer to those by their current names
+pub fn rename_target(
+    env: &TypeEnv,
+    path: &PathBuf,
+    pos: usize,
+    prelude_paths: &[PathBuf],
+) -> Result<Option<RenameTarget>, String> {
+    if let Some(def) = env.def_at(path, pos) {
+        let Some((name, info)) = env.info_of(def) else {
+            return Ok(None);
+        };
+        if is_prelude(&def.file_path, prelude_paths) {
+            return Err(format!("{name} is defined in a prelude and can't be renamed"));
+        }
+        if info.modifiers.is_extern() {
+            return Err(format!("{name} is extern and may be used by its name outside of this project"));
+        }
+        let span = match def.file_path == *path && contains(&def.span, pos) {
+            true => def.span,
+            false => env.refs(def).iter()
+                .find(|ref_| ref_.src_loc.file_path == *path && contains(&ref_.src_loc.span, pos))
+                .map(|ref_| ref_.src_loc.span)
+                .expect("def_at found a use at this position"),
+        };
+        return Ok(Some(RenameTarget::Name { def: def.clone(), name: name.clone(), span }));
+    }
+
+    let Some(group) = env.group_at(path, pos) else {
+        return Ok(None);
+    };
+    let span = env.group_refs[group].iter()
+        .find(|src_loc| src_loc.file_path == *path && contains(&src_loc.span, pos))
+        .map(|src_loc| src_loc.span)
+        .expect("group_at found a use at this position");
+    Ok(Some(RenameTarget::Group { name: group.clone(), span }))
+}
+
+/// Checks that a target can be renamed to `new_name` in a file's environment. The new name must not be a keyword
+/// and must not already be in use where the target is visible, since the definition of the target would then be
+/// a redefinition.
+///
+/// Locals are checked against the other locals of their function or rule and all globals. Globals are checked
+/// against all other names, since a local with the same name in the same file is a redefinition as well
+pub fn chk_new_name(env: &TypeEnv, target: &RenameTarget, new_name: &str) -> Result<(), String> {
+    if is_keyword(new_name) {
+        return Err(format!("{new_name} is a keyword"));
+    }
+    if !is_identifier(new_name) {
+        return Err(format!("{new_name} is not a valid name"));
+    }
+
+    let already_defined = Err(format!("Name {new_name} is already defined"));
+    let new_id = Identifier::new(new_name);
+    match target {
+        RenameTarget::Name { def, .. } => {
+            let fn_env = env.fn_envs.values()
+                .flatten()
+                .find(|fn_env| fn_env.identifiers.values().any(|info| info.src_loc == *def));
+            let is_local_defined = match fn_env {
+                Some(fn_env) => fn_env.identifiers.contains_key(&new_id),
+                None => env.fn_envs.values().flatten().any(|fn_env| fn_env.identifiers.contains_key(&new_id)),
+            };
+            if is_local_defined || env.identifiers.contains_key(&new_id) {
+                return already_defined;
+            }
+        }
+        RenameTarget::Group { .. } => {
+            if env.group_refs.contains_key(new_name) || env.groups.contains(new_name) {
+                return already_defined;
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use dashmap::DashMap;
+    use xsc_core::config::Flavour;
+    use xsc_core::r#static::info::{gen_errs_from_src, AstMap};
+
+    use super::*;
+
+    const SRC: &str = "\
+extern int cShared = 1;
+int total = 0;
+void add(int amount = 0) {
+    int doubled = amount * 2;
+    total = total + doubled;
+}
+void reset() {
+    int count = 0;
+    total = count;
+    xsEnableRuleGroup(\"timers\");
+}
+rule tick group timers {
+    add(1);
+    xsChatData(\"%d\", cShared);
+}
+";
+
+    fn env() -> TypeEnv {
+        let (ast_cache, editors) = (AstMap::new(), DashMap::new());
+        let mut env = TypeEnv::new(vec![]);
+        gen_errs_from_src(&PathBuf::from("prelude.xs"), Flavour::AoE2DE.prelude(), &mut env, &ast_cache, &editors)
+            .expect("Prelude can't produce parse errors");
+        gen_errs_from_src(&PathBuf::from("test.xs"), SRC, &mut env, &ast_cache, &editors)
+            .expect("Source has no syntax errors");
+        env
+    }
+
+    /// The target at the first occurrence of `at` in [`SRC`], and the code its span covers
+    fn target_at(env: &TypeEnv, at: &str) -> Result<Option<(RenameTarget, &'static str)>, String> {
+        let pos = SRC.find(at).expect("Source contains the name");
+        let target = rename_target(env, &PathBuf::from("test.xs"), pos, &[])?;
+        Ok(target.map(|target| {
+            let code = &SRC[target.span().start..target.span().end];
+            (target, code)
+        }))
+    }
+
+    #[test]
+    fn names_are_renamed_from_their_definition_or_a_use() {
+        let env = env();
+        let (def, code) = target_at(&env, "total = 0").unwrap().unwrap();
+        assert_eq!((def.name(), code), ("total", "total"));
+        let (use_, code) = target_at(&env, "add(1)").unwrap().unwrap();
+        assert_eq!((use_.name(), code), ("add", "add"));
+
+        let (RenameTarget::Name { def: def1, .. }, RenameTarget::Name { def: def2, .. }) =
+            (def, target_at(&env, "total = count").unwrap().unwrap().0) else {
+            panic!("Variables are renamed as names");
+        };
+        assert_eq!(def1, def2);
+
+        let (group, code) = target_at(&env, "timers {").unwrap().unwrap();
+        assert!(matches!(group, RenameTarget::Group { .. }));
+        assert_eq!(code, "timers");
+        assert!(target_at(&env, "void").unwrap().is_none());
+    }
+
+    #[test]
+    fn prelude_and_extern_names_are_not_renamed() {
+        let env = env();
+        assert_eq!(
+            target_at(&env, "xsChatData").unwrap_err(),
+            "xsChatData is defined in a prelude and can't be renamed",
+        );
+        assert_eq!(
+            target_at(&env, "cShared);").unwrap_err(),
+            "cShared is extern and may be used by its name outside of this project",
+        );
+    }
+
+    #[test]
+    fn new_names_must_be_free_where_the_target_is_visible() {
+        let env = env();
+        let (local, _code) = target_at(&env, "doubled =").unwrap().unwrap();
+        assert_eq!(chk_new_name(&env, &local, "while"), Err("while is a keyword".to_string()));
+        assert_eq!(chk_new_name(&env, &local, "2x"), Err("2x is not a valid name".to_string()));
+        assert_eq!(chk_new_name(&env, &local, "amount"), Err("Name amount is already defined".to_string()));
+        assert_eq!(chk_new_name(&env, &local, "total"), Err("Name total is already defined".to_string()));
+        // locals of other functions are out of sight
+        assert_eq!(chk_new_name(&env, &local, "count"), Ok(()));
+
+        let (global, _code) = target_at(&env, "total = 0").unwrap().unwrap();
+        assert_eq!(chk_new_name(&env, &global, "count"), Err("Name count is already defined".to_string()));
+        assert_eq!(chk_new_name(&env, &global, "score"), Ok(()));
+
+        let (group, _code) = target_at(&env, "timers {").unwrap().unwrap();
+        assert_eq!(chk_new_name(&env, &group, "timers2"), Ok(()));
+    }
+}
@@ -0,0 +1,5 @@
+mod gen_doc_symbols;
+mod gen_workspace_symbols;
+
+pub use gen_doc_symbols::{gen_doc_symbols};
+pub use gen_workspace_symbols::{gen_workspace_symbols};
@@ -0,0 +1,133 @@
+use ropey::Rope;
+use tower_lsp::lsp_types::{DocumentSymbol, Range, SymbolKind};
+use xsc_core::parsing::ast::{AstNode, Identifier};
+use xsc_core::parsing::span::{Span, Spanned};
+use crate::fmt::pos_info::pos_from_span;
+
+fn symbol(
+    src: &Rope,
+    (name, name_span): &Spanned<Identifier>,
+    span: &Span,
+    kind: SymbolKind,
+    detail: String,
+    children: Vec<DocumentSymbol>,
+) -> DocumentSymbol {
+    let (start, end) = pos_from_span(src, span);
+    let (name_start, name_end) = pos_from_span(src, name_span);
+
+    #[allow(deprecated)]
+    DocumentSymbol {
+        name: name.0.clone(),
+        detail: Some(detail),
+        kind,
+        tags: None,
+        deprecated: None,
+        range: Range::new(start, end),
+        selection_range: Range::new(name_start, name_end),
+        children: (!children.is_empty()).then_some(children),
+    }
+}
+
+/// The labels, consts and statics in the body of a function or rule. Locals that change between calls are left
+/// out of the outline
+fn xs_symbols_body(body: &[Spanned<AstNode>], symbols: &mut Vec<DocumentSymbol>, src: &Rope) {
+    for stmt in body {
+        match &stmt.0 {
+            AstNode::VarDef { is_const, is_static, .. } if *is_const || *is_static => {
+                xs_symbols_stmt(stmt, symbols, src);
+            }
+            AstNode::LabelDef(_name) => {
+                xs_symbols_stmt(stmt, symbols, src);
+            }
+            AstNode::IfElse { consequent: (consequent, _span), alternate, .. } => {
+                xs_symbols_body(consequent, symbols, src);
+                if let Some((alternate, _span)) = alternate {
+                    xs_symbols_body(alternate, symbols, src);
+                }
+            }
+            AstNode::While { body: (body, _span), .. } | AstNode::For { body: (body, _span), .. } => {
+                xs_symbols_body(body, symbols, src);
+            }
+            AstNode::Switch { cases, .. } => {
+                for (_case, (body, _span)) in cases {
+                    xs_symbols_body(body, symbols, src);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+fn xs_symbols_stmt((stmt, span): &Spanned<AstNode>, symbols: &mut Vec<DocumentSymbol>, src: &Rope) {
+    match stmt {
+        AstNode::VarDef { is_export, is_extern, is_const, is_static, type_, name, value } => {
+            let mut detail = [
+                (*is_export, "export"),
+                (*is_extern, "extern"),
+                (*is_static, "static"),
+                (*is_const, "const"),
+            ].iter()
+                .filter(|(is_set, _modifier)| *is_set)
+                .map(|(_is_set, modifier)| format!("{modifier} "))
+                .collect::<String>();
+            detail.push_str(&type_.to_string());
+            if let (true, Some((value, _span))) = (*is_const, value) {
+                detail.push_str(&format!(" = {value}"));
+            }
+
+            let kind = if *is_const { SymbolKind::CONSTANT } else { SymbolKind::VARIABLE };
+            symbols.push(symbol(src, name, span, kind, detail, vec![]));
+        }
+        AstNode::FnDef { is_mutable, return_type, name, params, body: (body, _span) } => {
+            let params = params.iter()
+                .map(|param| format!("{} {} = {}", param.type_, param.name.0, param.default.0))
+                .collect::<Vec<_>>()
+                .join(", ");
+            let detail = format!(
+                "{}{} {}({})",
+                if *is_mutable { "mutable " } else { "" },
+                return_type,
+                name.0,
+                params,
+            );
+
+            let mut children = vec![];
+            xs_symbols_body(body, &mut children, src);
+            symbols.push(symbol(src, name, span, SymbolKind::FUNCTION, detail, children));
+        }
+        AstNode::RuleDef { name, rule_opts, body: (body, _span) } => {
+            let detail = rule_opts.iter()
+                .map(|(opt, _span)| opt.render())
+                .collect::<Vec<_>>()
+                .join(", ");
+
+            let mut children = vec![];
+            xs_symbols_body(body, &mut children, src);
+            symbols.push(symbol(src, name, span, SymbolKind::EVENT, detail, children));
+        }
+        AstNode::Class { name, member_vars } => {
+            let mut children = vec![];
+            for member_var in member_vars {
+                xs_symbols_stmt(member_var, &mut children, src);
+            }
+            for child in children.iter_mut() {
+                child.kind = SymbolKind::FIELD;
+            }
+            symbols.push(symbol(src, name, span, SymbolKind::CLASS, "class".into(), children));
+        }
+        AstNode::LabelDef(name) => {
+            symbols.push(symbol(src, name, span, SymbolKind::KEY, "label".into(), vec![]));
+        }
+        _ => {}
+    }
+}
+
+/// The outline of a file: its functions, rules, globals, consts and classes, with the labels, consts and statics
+/// in the bodies of functions and rules under them
+pub fn gen_doc_symbols(src: &Rope, ast: &[Spanned<AstNode>]) -> Vec<DocumentSymbol> {
+    let mut symbols = vec![];
+    for stmt in ast {
+        xs_symbols_stmt(stmt, &mut symbols, src);
+    }
+    symbols
+}
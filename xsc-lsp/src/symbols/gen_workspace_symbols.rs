@@ -0,0 +1,109 @@
+use tower_lsp::lsp_types::{DocumentSymbol, Location, SymbolInformation, Url};
+
+/// How far apart the characters of the query are in a name, if they all appear in it in order. Case is ignored,
+/// so `chd` matches `xsChatData`. Lower is a closer match
+fn fuzzy_score(query: &str, name: &str) -> Option<usize> {
+    let mut chars = name.chars().enumerate();
+    let mut score = 0;
+    let mut next_idx = 0;
+    for query_char in query.chars() {
+        let (idx, _char) = chars.find(|(_idx, char)| char.eq_ignore_ascii_case(&query_char))?;
+        score += idx - next_idx;
+        next_idx = idx + 1;
+    }
+    Some(score)
+}
+
+fn xs_symbols_flatten(
+    query: &str,
+    url: &Url,
+    doc_symbols: Vec<DocumentSymbol>,
+    container_name: Option<&String>,
+    symbols: &mut Vec<(usize, SymbolInformation)>,
+) {
+    for doc_symbol in doc_symbols {
+        if let Some(score) = fuzzy_score(query, &doc_symbol.name) {
+            #[allow(deprecated)]
+            symbols.push((score, SymbolInformation {
+                name: doc_symbol.name.clone(),
+                kind: doc_symbol.kind,
+                tags: None,
+                deprecated: None,
+                location: Location::new(url.clone(), doc_symbol.selection_range),
+                container_name: container_name.cloned(),
+            }));
+        }
+        if let Some(children) = doc_symbol.children {
+            xs_symbols_flatten(query, url, children, Some(&doc_symbol.name), symbols);
+        }
+    }
+}
+
+/// The symbols in the outlines of the given files whose names fuzzily match the query, closest matches first
+pub fn gen_workspace_symbols(query: &str, files: Vec<(Url, Vec<DocumentSymbol>)>) -> Vec<SymbolInformation> {
+    let mut symbols = vec![];
+    for (url, doc_symbols) in files {
+        xs_symbols_flatten(query, &url, doc_symbols, None, &mut symbols);
+    }
+    symbols.sort_by(|(score1, symbol1), (score2, symbol2)| {
+        (score1, symbol1.name.len(), &symbol1.name).cmp(&(score2, symbol2.name.len(), &symbol2.name))
+    });
+    symbols.into_iter().map(|(_score, symbol)| symbol).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use tower_lsp::lsp_types::{Range, SymbolKind};
+
+    use super::*;
+
+    #[allow(deprecated)]
+    fn doc_symbol(name: &str, children: Vec<DocumentSymbol>) -> DocumentSymbol {
+        DocumentSymbol {
+            name: name.to_string(),
+            detail: None,
+            kind: SymbolKind::FUNCTION,
+            tags: None,
+            deprecated: None,
+            range: Range::default(),
+            selection_range: Range::default(),
+            children: (!children.is_empty()).then_some(children),
+        }
+    }
+
+    #[test]
+    fn fuzzy_scores_count_the_characters_skipped_between_matches() {
+        assert_eq!(fuzzy_score("", "xsChatData"), Some(0));
+        assert_eq!(fuzzy_score("xsChat", "xsChatData"), Some(0));
+        assert_eq!(fuzzy_score("chd", "xsChatData"), Some(2 + 2));
+        assert_eq!(fuzzy_score("CHAT", "xschatdata"), Some(2));
+        // characters must appear in the same order as in the query
+        assert_eq!(fuzzy_score("dc", "xsChatData"), None);
+        assert_eq!(fuzzy_score("chatz", "xsChatData"), None);
+    }
+
+    #[test]
+    fn symbols_are_ranked_by_score_then_length_then_name() {
+        let url = Url::parse("file:///test.xs").unwrap();
+        let files = vec![(url, vec![
+            doc_symbol("setupArmy", vec![doc_symbol("armySize", vec![])]),
+            doc_symbol("army", vec![]),
+            doc_symbol("armyB", vec![]),
+            doc_symbol("armyA", vec![]),
+            doc_symbol("navy", vec![]),
+        ])];
+
+        let symbols = gen_workspace_symbols("army", files);
+
+        let ranked: Vec<_> = symbols.iter()
+            .map(|symbol| (symbol.name.as_str(), symbol.container_name.as_deref()))
+            .collect();
+        assert_eq!(ranked, vec![
+            ("army", None),
+            ("armyA", None),
+            ("armyB", None),
+            ("armySize", Some("setupArmy")),
+            ("setupArmy", None),
+        ]);
+    }
+}
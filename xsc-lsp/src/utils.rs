@@ -1,4 +1,4 @@
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use dunce::canonicalize;
 use tower_lsp::lsp_types::Url;
 
@@ -11,3 +11,35 @@ pub fn path_from_uri(uri: &Url) -> PathBuf {
         }
     }
 }
+
+/// Whether two paths are the same file. Configured paths may be relative, so both are canonicalised first
+pub fn is_same_file(path1: &Path, path2: &Path) -> bool {
+    let canonical = |path: &Path| canonicalize(path).unwrap_or(path.to_path_buf());
+    canonical(path1) == canonical(path2)
+}
+
+/// Whether a file is the built in prelude or one of the configured extra preludes
+pub fn is_prelude(path: &Path, extra_prelude_paths: &[PathBuf]) -> bool {
+    path.as_os_str() == "prelude.xs"
+        || extra_prelude_paths.iter().any(|prelude_path| is_same_file(prelude_path, path))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn extra_preludes_match_however_their_path_is_written() {
+        let dir = std::env::temp_dir().join(format!("xsc-lsp-prelude-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("sub")).expect("Temp dir is writable");
+        std::fs::write(dir.join("extra.xs"), "").expect("Temp dir is writable");
+        let written = dir.join("sub").join("..").join("extra.xs");
+        let def_path = canonicalize(dir.join("extra.xs")).expect("File was just created");
+
+        assert!(is_prelude(&def_path, &[written]));
+        assert!(is_prelude(Path::new("prelude.xs"), &[]));
+        assert!(!is_prelude(&def_path, &[dir.join("other.xs")]));
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+}